@@ -0,0 +1,67 @@
+use wgpu::util::DeviceExt;
+
+/// A gpu buffer that's re-written in place for as long as what's
+/// written to it fits, and is only re-allocated when it's outgrown.
+pub struct DynamicBuffer {
+    buffer: wgpu::Buffer,
+    /// The size of the buffer in bytes.
+    capacity: wgpu::BufferAddress,
+    label: &'static str,
+    usage: wgpu::BufferUsages,
+}
+
+impl DynamicBuffer {
+    pub fn new(
+        device: &wgpu::Device,
+        label: &'static str,
+        usage: wgpu::BufferUsages,
+        contents: &[u8],
+    ) -> Self {
+        let contents = pad_to_copy_alignment(contents);
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(label),
+            contents: &contents,
+            usage: usage | wgpu::BufferUsages::COPY_DST,
+        });
+        Self {
+            buffer,
+            capacity: contents.len() as wgpu::BufferAddress,
+            label,
+            usage,
+        }
+    }
+
+    /// Writes the contents to the start of the buffer, re-allocating
+    /// the buffer if the contents don't fit in it.
+    pub fn write(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, contents: &[u8]) {
+        let contents = pad_to_copy_alignment(contents);
+        let size = contents.len() as wgpu::BufferAddress;
+        if size > self.capacity {
+            // Grow geometrically so that a steadily growing tree
+            // doesn't re-allocate on every change.
+            self.capacity = size.next_power_of_two();
+            self.buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(self.label),
+                size: self.capacity,
+                usage: self.usage | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+        }
+        queue.write_buffer(&self.buffer, 0, &contents);
+    }
+
+    pub fn buffer(&self) -> &wgpu::Buffer {
+        &self.buffer
+    }
+}
+
+/// Buffers can only be written to in multiples of four bytes, and
+/// can't be empty, so the contents are padded with zeros to fit.
+fn pad_to_copy_alignment(contents: &[u8]) -> Vec<u8> {
+    let alignment = wgpu::COPY_BUFFER_ALIGNMENT as usize;
+    let size = contents.len().max(1).div_ceil(alignment) * alignment;
+    let mut padded = Vec::with_capacity(size);
+    padded.extend_from_slice(contents);
+    padded.resize(size, 0);
+    padded
+}
@@ -0,0 +1,249 @@
+use crate::guiprocessing::dynamic_buffer::DynamicBuffer;
+use crate::guiproperties::{GUIColorStop, GUIFill};
+
+/// The kind of a fill whose gradient position goes along a line.
+pub const LINEAR: u32 = 0;
+/// The kind of a fill whose gradient position goes out from a center.
+pub const RADIAL: u32 = 1;
+
+/// A fill as the shape fragment shader reads it.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct FillData {
+    /// The start and end of a linear gradient, or the center and radius of
+    /// a radial one, in absolute logical pixels.
+    pub geometry: [f32; 4],
+    pub kind: u32,
+    /// Where the fill's stops start in the stops buffer.
+    pub first_stop: u32,
+    pub stop_count: u32,
+    pub _padding: u32,
+}
+
+/// A color stop as the shape fragment shader reads it.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct StopData {
+    /// The linear, premultiplied RGBA color of the stop.
+    pub color: [f32; 4],
+    pub offset: f32,
+    /// Pads the struct out to the 16 byte alignment of its color.
+    pub _padding: [f32; 3],
+}
+
+/// Lays fills out the way that the shape fragment shader reads them. A
+/// solid fill becomes a gradient with a single stop. Stops that come before
+/// the stop in front of them are moved up to it, as they are in CSS.
+pub fn pack(fills: &[GUIFill]) -> (Vec<FillData>, Vec<StopData>) {
+    let mut packed = Vec::with_capacity(fills.len());
+    let mut packed_stops = Vec::new();
+    for fill in fills {
+        let (geometry, kind, stops) = match fill {
+            GUIFill::Solid(color) => ([0.; 4], LINEAR, vec![GUIColorStop::new(0., *color)]),
+            GUIFill::LinearGradient { start, end, stops } => (
+                [start.x, start.y, end.x, end.y].map(|length| length.get_length() as f32),
+                LINEAR,
+                stops.clone(),
+            ),
+            GUIFill::RadialGradient {
+                center,
+                radius,
+                stops,
+            } => (
+                [center.x, center.y, *radius, Default::default()]
+                    .map(|length| length.get_length() as f32),
+                RADIAL,
+                stops.clone(),
+            ),
+        };
+        packed.push(FillData {
+            geometry,
+            kind,
+            first_stop: packed_stops.len() as u32,
+            stop_count: stops.len() as u32,
+            _padding: 0,
+        });
+        let mut last_offset = f32::MIN;
+        for stop in stops {
+            let color = stop.color;
+            last_offset = last_offset.max(stop.offset as f32);
+            packed_stops.push(StopData {
+                color: [
+                    color.r * color.a,
+                    color.g * color.a,
+                    color.b * color.a,
+                    color.a,
+                ]
+                .map(|channel| channel as f32),
+                offset: last_offset,
+                _padding: [0.; 3],
+            });
+        }
+    }
+    (packed, packed_stops)
+}
+
+/// Works out the premultiplied color of a fill at a position in logical
+/// pixels, the same way that the shape fragment shader does.
+pub fn shade(fill: &FillData, stops: &[StopData], position: [f32; 2]) -> [f32; 4] {
+    let [x, y, z, w] = fill.geometry;
+    let offset = if fill.kind == RADIAL {
+        (position[0] - x).hypot(position[1] - y) / z.max(f32::EPSILON)
+    } else {
+        let direction = [z - x, w - y];
+        let length_squared = direction[0] * direction[0] + direction[1] * direction[1];
+        if length_squared <= 0. {
+            0.
+        } else {
+            ((position[0] - x) * direction[0] + (position[1] - y) * direction[1]) / length_squared
+        }
+    };
+
+    let first = fill.first_stop as usize;
+    let stops = &stops[first..first + fill.stop_count as usize];
+    let Some(mut previous) = stops.first() else {
+        return [0.; 4];
+    };
+    if offset <= previous.offset {
+        return previous.color;
+    }
+    for stop in &stops[1..] {
+        if offset <= stop.offset {
+            let amount = (offset - previous.offset) / (stop.offset - previous.offset);
+            return std::array::from_fn(|channel| {
+                previous.color[channel] + (stop.color[channel] - previous.color[channel]) * amount
+            });
+        }
+        previous = stop;
+    }
+    previous.color
+}
+
+/// The storage buffers that hold the fills that shapes are painted with,
+/// and the bind group that the shape pipeline reads them through.
+pub struct FillsBinding {
+    fill_buffer: DynamicBuffer,
+    stop_buffer: DynamicBuffer,
+    pub bind_group_layout: wgpu::BindGroupLayout,
+    pub bind_group: wgpu::BindGroup,
+}
+
+impl FillsBinding {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only: true },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Fills Bind Group Layout"),
+            entries: &[entry(0), entry(1)],
+        });
+        let usage = wgpu::BufferUsages::STORAGE;
+        let fill_buffer = DynamicBuffer::new(
+            device,
+            "Fill Buffer",
+            usage,
+            bytemuck::bytes_of(&FillData::default()),
+        );
+        let stop_buffer = DynamicBuffer::new(
+            device,
+            "Stop Buffer",
+            usage,
+            bytemuck::bytes_of(&StopData::default()),
+        );
+        let bind_group = Self::bind_group(device, &bind_group_layout, &fill_buffer, &stop_buffer);
+        Self {
+            fill_buffer,
+            stop_buffer,
+            bind_group_layout,
+            bind_group,
+        }
+    }
+
+    fn bind_group(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        fill_buffer: &DynamicBuffer,
+        stop_buffer: &DynamicBuffer,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Fills Bind Group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: fill_buffer.buffer().as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: stop_buffer.buffer().as_entire_binding(),
+                },
+            ],
+        })
+    }
+
+    /// Uploads the fills, re-making the bind group in case the buffers
+    /// had to be re-allocated to fit them.
+    pub fn write(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, fills: &[GUIFill]) {
+        let (mut fills, mut stops) = pack(fills);
+        // Storage buffers have to hold at least one of what they're an array of.
+        if fills.is_empty() {
+            fills.push(FillData::default());
+        }
+        if stops.is_empty() {
+            stops.push(StopData::default());
+        }
+        self.fill_buffer
+            .write(device, queue, bytemuck::cast_slice(&fills[..]));
+        self.stop_buffer
+            .write(device, queue, bytemuck::cast_slice(&stops[..]));
+        self.bind_group = Self::bind_group(
+            device,
+            &self.bind_group_layout,
+            &self.fill_buffer,
+            &self.stop_buffer,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::guiproperties::guiposition::{GUILength, GUIPosition};
+    use crate::guiproperties::GUIColor;
+
+    #[test]
+    fn gradients_blend_between_their_stops() {
+        let red = GUIColor::from_rgba_u8u8u8u8(255, 0, 0, 255);
+        let clear = GUIColor::from_rgba_u8u8u8u8(0, 0, 255, 0);
+        let stops = vec![GUIColorStop::new(0.25, red), GUIColorStop::new(0.75, clear)];
+        let (fills, packed_stops) = pack(&[
+            GUIFill::LinearGradient {
+                start: GUIPosition::from_pixels(0., 0.),
+                end: GUIPosition::from_pixels(100., 0.),
+                stops: stops.clone(),
+            },
+            GUIFill::RadialGradient {
+                center: GUIPosition::from_pixels(50., 50.),
+                radius: GUILength::from_pixels(40.),
+                stops,
+            },
+        ]);
+        let linear = |x| shade(&fills[0], &packed_stops, [x, 30.]);
+        assert_eq!(linear(0.), [1., 0., 0., 1.]);
+        // Premultiplied, the transparent blue stop adds no blue.
+        assert_eq!(linear(50.), [0.5, 0., 0., 0.5]);
+        assert_eq!(linear(100.), [0.; 4]);
+
+        let radial = |x, y| shade(&fills[1], &packed_stops, [x, y]);
+        assert_eq!(radial(50., 50.), [1., 0., 0., 1.]);
+        assert_eq!(radial(50., 70.), radial(30., 50.));
+        assert_eq!(radial(50., 70.), [0.5, 0., 0., 0.5]);
+    }
+}
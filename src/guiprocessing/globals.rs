@@ -0,0 +1,109 @@
+use wgpu::util::DeviceExt;
+
+use crate::guiproperties::guiposition::GUISize;
+
+/// Values that every vertex in a frame is drawn with. Vertices are in
+/// logical pixels, so when the window is resized or its scale factor
+/// changes, only these need to be re-uploaded.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Globals {
+    /// Maps logical pixels, with the origin at the top left of the window
+    /// and the y axis pointing down, onto clip space. Column major.
+    pub projection: [[f32; 4]; 4],
+    /// The number of physical pixels in a logical pixel.
+    pub scale_factor: f32,
+    /// Pads the struct out to the 16 byte alignment of a uniform buffer.
+    pub _padding: [f32; 3],
+}
+
+impl Globals {
+    /// Makes the globals for a window of the given size in logical pixels.
+    pub fn new(window_size: &GUISize, scale_factor: f64) -> Self {
+        let width = window_size.width.get_length() as f32;
+        let height = window_size.height.get_length() as f32;
+        Self {
+            projection: [
+                [2. / width, 0., 0., 0.],
+                [0., -2. / height, 0., 0.],
+                [0., 0., 1., 0.],
+                [-1., 1., 0., 1.],
+            ],
+            scale_factor: scale_factor as f32,
+            _padding: [0.; 3],
+        }
+    }
+
+    /// Projects a position in logical pixels onto clip space,
+    /// the same way that the vertex shaders do.
+    pub fn project(&self, position: [f32; 3]) -> [f32; 2] {
+        let [x, y, z] = position;
+        let column = |index: usize| self.projection[index];
+        let row = |index: usize| {
+            column(0)[index] * x + column(1)[index] * y + column(2)[index] * z + column(3)[index]
+        };
+        [row(0), row(1)]
+    }
+}
+
+/// The uniform buffer that holds the globals, and the bind group that
+/// the pipelines read it through.
+pub struct GlobalsBinding {
+    buffer: wgpu::Buffer,
+    pub bind_group_layout: wgpu::BindGroupLayout,
+    pub bind_group: wgpu::BindGroup,
+}
+
+impl GlobalsBinding {
+    pub fn new(device: &wgpu::Device, globals: &Globals) -> Self {
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Globals Buffer"),
+            contents: bytemuck::bytes_of(globals),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Globals Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Globals Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+        });
+        Self {
+            buffer,
+            bind_group_layout,
+            bind_group,
+        }
+    }
+
+    pub fn write(&self, queue: &wgpu::Queue, globals: &Globals) {
+        queue.write_buffer(&self.buffer, 0, bytemuck::bytes_of(globals));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn projection_maps_the_window_onto_clip_space() {
+        let globals = Globals::new(&GUISize::from_pixels(200., 100.), 2.);
+        assert_eq!(globals.project([0., 0., 0.]), [-1., 1.]);
+        assert_eq!(globals.project([200., 100., 0.]), [1., -1.]);
+        assert_eq!(globals.project([100., 25., 0.]), [0., 0.5]);
+        assert_eq!(std::mem::size_of::<Globals>() % 16, 0);
+    }
+}
@@ -1,25 +1,41 @@
-use ::std::time::SystemTime;
-
 use winit::event::{ElementState, Event, KeyboardInput, VirtualKeyCode, WindowEvent};
 use winit::event_loop::{ControlFlow, EventLoop};
 use winit::window::WindowBuilder;
 
 use crate::guiproperties::guiposition::GUISize;
-use crate::guiproperties::guitraits::{Widget, Parent};
 use crate::guiresources::GUIResources;
 use crate::guiwidgets::GUIWindow;
 
+mod dynamic_buffer;
+pub mod fills;
+pub mod globals;
+mod offscreen;
+pub mod pointer;
+pub mod rects;
+mod renderer;
+pub mod software;
 mod state;
+pub mod tessellation;
+pub mod text;
 pub mod vertices;
 pub mod window_building_utils;
 
+pub use offscreen::{render_to_image, render_to_image_async};
 use state::State;
 
 /// The main funciton that executes everthing.
-pub fn run(mut guiwindow: GUIWindow, guiresources: GUIResources) {
+/// It only returns if the window can't be opened, which is always
+/// the case for the software backend, since it can only render offscreen.
+#[allow(clippy::collapsible_match)]
+pub fn run(mut guiwindow: GUIWindow, guiresources: GUIResources) -> Result<(), &'static str> {
+    if guiresources.is_software() {
+        return Err("the software backend can only render offscreen, with render_to_image");
+    }
     env_logger::init();
     let event_loop = EventLoop::new();
-    let window = WindowBuilder::new().build(&event_loop).unwrap();
+    let window = WindowBuilder::new()
+        .build(&event_loop)
+        .map_err(|_| "the window couldn't be made")?;
     guiwindow.logical_scale = Some(window.scale_factor());
     // guiwindow.set_id(0);
     // (guiwindow.children, _) = window_building_utils::set_widget_ids(guiwindow.give_children(), 1);
@@ -27,7 +43,7 @@ pub fn run(mut guiwindow: GUIWindow, guiresources: GUIResources) {
     let window = window_building_utils::set_window_properties(window, &guiwindow);
 
     // State::new uses async code, so we're going to wait for it to finish
-    let mut state: State = pollster::block_on(State::new(&window, guiwindow, guiresources));
+    let mut state: State = pollster::block_on(State::new(&window, guiwindow, guiresources))?;
 
     event_loop.run(move |event, _, control_flow| {
         match event {
@@ -54,20 +70,28 @@ pub fn run(mut guiwindow: GUIWindow, guiresources: GUIResources) {
                                 &state.guiwindow.logical_scale.unwrap(),
                             ));
                         }
-                        WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
-                            // new_inner_size is &&mut so w have to dereference it twice
-                            state.resize(GUISize::from_physical_pixels(
-                                new_inner_size.width as f64,
-                                new_inner_size.height as f64,
-                                &state.guiwindow.logical_scale.unwrap(),
-                            ));
+                        WindowEvent::ScaleFactorChanged {
+                            scale_factor,
+                            new_inner_size,
+                        } => {
+                            state.set_scale_factor(
+                                *scale_factor,
+                                new_inner_size.width,
+                                new_inner_size.height,
+                            );
                         }
                         _ => {}
                     }
                 }
             }
             Event::RedrawRequested(window_id) if window_id == window.id() => {
-                // state.update();
+                state.update();
+                // The os window follows any size that on_update gave the window.
+                let (width, height) = state.physical_size();
+                let size = winit::dpi::PhysicalSize::new(width, height);
+                if window.inner_size() != size {
+                    window.set_inner_size(size);
+                }
                 match state.render() {
                     Ok(_) => {
                         // println!("good!");
@@ -75,7 +99,7 @@ pub fn run(mut guiwindow: GUIWindow, guiresources: GUIResources) {
                     }
                     // Reconfigure the surface if lost
                     Err(wgpu::SurfaceError::Lost) => {
-                        state.guiwindow.size;
+                        state.resize(state.guiwindow.size);
                     }
                     // The system is out of memory, we should probably quit
                     Err(wgpu::SurfaceError::OutOfMemory) => *control_flow = ControlFlow::Exit,
@@ -92,3 +116,16 @@ pub fn run(mut guiwindow: GUIWindow, guiresources: GUIResources) {
         }
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::guiresources::GUIBackend;
+
+    #[test]
+    fn the_software_backend_cant_open_a_window() {
+        let mut guiresources = GUIResources::default();
+        guiresources.set_backend(GUIBackend::Software);
+        assert!(run(GUIWindow::default(), guiresources).is_err());
+    }
+}
@@ -0,0 +1,209 @@
+use std::iter;
+
+use crate::guiproperties::GUIImage;
+use crate::guiresources::GUIResources;
+use crate::guiwidgets::GUIWindow;
+
+use crate::guiprocessing::renderer::{self, RenderTarget, Renderer};
+use crate::guiprocessing::software;
+
+/// The format of the offscreen texture, which is sRGB so that the colors
+/// match what's shown on a typical sRGB surface.
+const OFFSCREEN_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+/// Renders a single frame of the window into an offscreen texture, or with the
+/// software rasterizer if it's selected, and returns its pixels, without
+/// opening a window. The window is rendered at its
+/// logical_scale, or at a scale of 1 if it doesn't have one.
+pub async fn render_to_image_async(
+    mut guiwindow: GUIWindow,
+    guiresources: GUIResources,
+) -> Result<GUIImage, &'static str> {
+    let scale = *guiwindow.logical_scale.get_or_insert(1.);
+    let width = guiwindow.size.width.get_physical_length(&scale) as u32;
+    let height = guiwindow.size.height.get_physical_length(&scale) as u32;
+    if width == 0 || height == 0 {
+        return Err("the window has no area to render");
+    }
+    if guiresources.is_software() {
+        return Ok(software::render_window(
+            &mut guiwindow,
+            &guiresources,
+            width,
+            height,
+        ));
+    }
+
+    let instance = wgpu::Instance::new(guiresources.backend());
+    let (adapter, device, queue) = renderer::request_device(&instance, &guiresources, None)
+        .await
+        .ok_or("no adapter and device could be found for the selected backend")?;
+
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Offscreen Texture"),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: OFFSCREEN_FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+    let target = RenderTarget {
+        format: OFFSCREEN_FORMAT,
+        width,
+        height,
+        sample_count: renderer::supported_sample_count(
+            &adapter,
+            OFFSCREEN_FORMAT,
+            guiresources.sample_count(),
+        ),
+    };
+    let mut renderer = Renderer::new(device, queue, target, &mut guiwindow, &guiresources);
+    renderer.update(&mut guiwindow);
+    renderer.draw(&view, &guiwindow);
+
+    // Rows that are copied out of a texture have to be padded to a multiple of 256 bytes.
+    let unpadded_bytes_per_row = width * 4;
+    let alignment = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    let padded_bytes_per_row = unpadded_bytes_per_row.div_ceil(alignment) * alignment;
+    let output_buffer = renderer.device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Offscreen Output Buffer"),
+        size: (padded_bytes_per_row * height) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = renderer
+        .device
+        .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Offscreen Copy Encoder"),
+        });
+    encoder.copy_texture_to_buffer(
+        wgpu::ImageCopyTexture {
+            texture: &texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+            aspect: wgpu::TextureAspect::All,
+        },
+        wgpu::ImageCopyBuffer {
+            buffer: &output_buffer,
+            layout: wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: std::num::NonZeroU32::new(padded_bytes_per_row),
+                rows_per_image: std::num::NonZeroU32::new(height),
+            },
+        },
+        wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+    );
+    renderer.queue.submit(iter::once(encoder.finish()));
+
+    let buffer_slice = output_buffer.slice(..);
+    let mapping = buffer_slice.map_async(wgpu::MapMode::Read);
+    renderer.device.poll(wgpu::Maintain::Wait);
+    mapping
+        .await
+        .map_err(|_| "the rendered frame couldn't be read back")?;
+
+    let pixels = unpad_rows(
+        &buffer_slice.get_mapped_range(),
+        padded_bytes_per_row,
+        unpadded_bytes_per_row,
+    );
+    output_buffer.unmap();
+
+    GUIImage::from_premultiplied_srgb(width, height, pixels)
+}
+
+/// Drops the padding from the end of each row of pixels that were copied out of a texture.
+fn unpad_rows(padded: &[u8], padded_bytes_per_row: u32, unpadded_bytes_per_row: u32) -> Vec<u8> {
+    padded
+        .chunks(padded_bytes_per_row as usize)
+        .flat_map(|row| &row[..unpadded_bytes_per_row as usize])
+        .copied()
+        .collect()
+}
+
+/// Renders a single frame of the window into an image, blocking until it's done.
+/// See render_to_image_async.
+pub fn render_to_image(
+    guiwindow: GUIWindow,
+    guiresources: GUIResources,
+) -> Result<GUIImage, &'static str> {
+    pollster::block_on(render_to_image_async(guiwindow, guiresources))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::guiproperties::guiposition::{GUIPosition, GUISize};
+    use crate::guiproperties::guitraits::{Parent, Widget};
+    use crate::guiproperties::{GUIColor, GUIFillRule, GUIPath};
+    use crate::guiresources::GUIBackend;
+    use crate::guitesting::diff_images;
+    use crate::guiwidgets::GUIShape;
+
+    /// A window whose rows don't fill a multiple of 256 bytes, with a red
+    /// square in the top left of a translucent background.
+    fn red_square_window() -> GUIWindow {
+        let mut path = GUIPath::new(GUIFillRule::NonZero);
+        path.move_to(GUIPosition::from_pixels(10., 10.));
+        path.line_to(GUIPosition::from_pixels(10., 30.));
+        path.line_to(GUIPosition::from_pixels(30., 30.));
+        path.line_to(GUIPosition::from_pixels(30., 10.));
+        path.close();
+        let mut shape = GUIShape::from_path(path);
+        shape.set_color(GUIColor::from_rgba_u8u8u8u8(255, 0, 0, 255));
+        let mut guiwindow = GUIWindow::default();
+        guiwindow.set_size(GUISize::from_pixels(70., 40.));
+        guiwindow.background_color = GUIColor::from_rgba_u8u8u8u8(0, 0, 255, 128);
+        guiwindow.add_child(Box::new(shape));
+        guiwindow
+    }
+
+    #[test]
+    fn windows_without_an_area_are_not_rendered() {
+        let mut guiwindow = GUIWindow::default();
+        guiwindow.size = GUISize::from_pixels(0., 100.);
+        assert!(render_to_image(guiwindow, GUIResources::default()).is_err());
+    }
+
+    #[test]
+    fn rows_are_unpadded() {
+        let padded = [1, 2, 3, 0, 4, 5, 6, 0];
+        assert_eq!(unpad_rows(&padded, 4, 3), [1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn software_frames_have_the_widgets_colors() {
+        let mut guiresources = GUIResources::default();
+        guiresources.set_backend(GUIBackend::Software);
+        let image = render_to_image(red_square_window(), guiresources).unwrap();
+        assert_eq!((image.width(), image.height()), (70, 40));
+        assert_eq!(image.pixel(20, 20), [255, 0, 0, 255]);
+        assert_eq!(image.pixel(50, 20), [0, 0, 255, 128]);
+    }
+
+    #[test]
+    fn gpu_frames_match_the_software_rasterizer() {
+        // The frame can only be compared where there's an adapter to render it with.
+        let image = match render_to_image(red_square_window(), GUIResources::default()) {
+            Ok(image) => image,
+            Err(_) => return,
+        };
+        let mut guiresources = GUIResources::default();
+        guiresources.set_backend(GUIBackend::Software);
+        let expected = render_to_image(red_square_window(), guiresources).unwrap();
+        // The translucent background has to have been unpremultiplied to match.
+        assert!(diff_images(&expected, &image, 2).is_none());
+    }
+}
@@ -0,0 +1,212 @@
+use crate::guiproperties::guiposition::GUIPosition;
+use crate::guiproperties::guitraits::Family;
+
+use super::window_building_utils;
+
+/// Keeps track of the mouse so that pointer events can be
+/// resolved against the widget tree.
+#[derive(Default)]
+pub struct Pointer {
+    /// Where the cursor is in logical pixels, or None if it's outside of the window.
+    pub position: Option<GUIPosition>,
+    /// The id of the widget that the cursor is over.
+    hovered: Option<u128>,
+    /// The id of the widget that the mouse button was pressed on.
+    pressed: Option<u128>,
+}
+
+impl Pointer {
+    /// Records that the cursor moved to the given position.
+    pub fn moved(&mut self, position: GUIPosition, children: &mut [Box<dyn Family>]) {
+        self.position = Some(position);
+        self.update_hovered(children);
+    }
+
+    /// Records that the cursor left the window.
+    pub fn left(&mut self, children: &mut [Box<dyn Family>]) {
+        self.position = None;
+        self.update_hovered(children);
+    }
+
+    /// Records a mouse button press.
+    /// Returns whether or not the press landed on a widget.
+    pub fn press(&mut self, children: &mut [Box<dyn Family>]) -> bool {
+        self.pressed = self.widget_under_cursor(children);
+        match self.pressed {
+            Some(pressed) => {
+                self.notify(children, pressed, |widget| widget.set_pressed(true));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Records a mouse button release, clicking the widget that
+    /// was pressed if the release happened over that same widget.
+    /// Returns whether or not the release ended a press on a widget.
+    pub fn release(&mut self, children: &mut [Box<dyn Family>]) -> bool {
+        let pressed = match self.pressed.take() {
+            Some(pressed) => pressed,
+            None => return false,
+        };
+        self.notify(children, pressed, |widget| widget.set_pressed(false));
+        if self.widget_under_cursor(children) == Some(pressed) {
+            if let Some(widget) = window_building_utils::find_child_mut(children, pressed) {
+                widget.click();
+            }
+        }
+        true
+    }
+
+    fn update_hovered(&mut self, children: &mut [Box<dyn Family>]) {
+        let hovered = self.widget_under_cursor(children);
+        if hovered == self.hovered {
+            return;
+        }
+        if let Some(previous) = self.hovered {
+            self.notify(children, previous, |widget| widget.set_hovered(false));
+        }
+        if let Some(hovered) = hovered {
+            self.notify(children, hovered, |widget| widget.set_hovered(true));
+        }
+        self.hovered = hovered;
+    }
+
+    /// Applies a change to the widget with the given id.
+    fn notify(
+        &mut self,
+        children: &mut [Box<dyn Family>],
+        id: u128,
+        change: impl FnOnce(&mut Box<dyn Family>),
+    ) {
+        if let Some(widget) = window_building_utils::find_child_mut(children, id) {
+            change(widget);
+        }
+    }
+
+    fn widget_under_cursor(&self, children: &[Box<dyn Family>]) -> Option<u128> {
+        let position = self.position?;
+        find_widget_at(children, &GUIPosition::from_pixels(0., 0.), &position)
+    }
+}
+
+/// Finds the id of the top most widget that contains the point.
+/// Widgets are drawn in the order in which they were added, with
+/// children drawn over their parents, so the tree is searched in
+/// the reverse of that order.
+pub fn find_widget_at(
+    children: &[Box<dyn Family>],
+    parent_position: &GUIPosition,
+    point: &GUIPosition,
+) -> Option<u128> {
+    for child in children.iter().rev() {
+        let child_position = *parent_position + child.get_position();
+        if let Some(id) = find_widget_at(child.get_children(), &child_position, point) {
+            return Some(id);
+        }
+        if child.contains_point(parent_position, point) {
+            return Some(child.get_id());
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use crate::guiproperties::guitraits::{Child, Parent, Widget};
+    use crate::guiwidgets::GUIButton;
+
+    use super::*;
+
+    fn counting_button(x: f64, y: f64, clicks: &Rc<Cell<u32>>) -> GUIButton {
+        let mut button = GUIButton::default();
+        button.set_position_from_pixels(x, y);
+        let clicks = Rc::clone(clicks);
+        button.set_on_click(move || clicks.set(clicks.get() + 1));
+        button
+    }
+
+    #[test]
+    fn click_inside_button_calls_on_click() {
+        let clicks = Rc::new(Cell::new(0));
+        let mut children: Vec<Box<dyn Family>> = vec![Box::new(counting_button(50., 50., &clicks))];
+        let mut pointer = Pointer::default();
+
+        pointer.moved(GUIPosition::from_pixels(150., 100.), &mut children);
+        assert!(pointer.press(&mut children));
+        assert!(pointer.release(&mut children));
+
+        assert_eq!(clicks.get(), 1);
+    }
+
+    #[test]
+    fn release_outside_button_does_not_click() {
+        let clicks = Rc::new(Cell::new(0));
+        let mut children: Vec<Box<dyn Family>> = vec![Box::new(counting_button(50., 50., &clicks))];
+        let mut pointer = Pointer::default();
+
+        pointer.moved(GUIPosition::from_pixels(150., 100.), &mut children);
+        pointer.press(&mut children);
+        pointer.moved(GUIPosition::from_pixels(400., 400.), &mut children);
+        pointer.release(&mut children);
+
+        assert_eq!(clicks.get(), 0);
+    }
+
+    #[test]
+    fn rounded_corner_is_not_hit() {
+        let clicks = Rc::new(Cell::new(0));
+        let children: Vec<Box<dyn Family>> = vec![Box::new(counting_button(0., 0., &clicks))];
+        let origin = GUIPosition::from_pixels(0., 0.);
+
+        // Inside the bounding box, but outside of the 25 pixel corner radius.
+        let corner = GUIPosition::from_pixels(2., 2.);
+        assert_eq!(find_widget_at(&children, &origin, &corner), None);
+        let edge = GUIPosition::from_pixels(2., 50.);
+        assert_eq!(
+            find_widget_at(&children, &origin, &edge),
+            Some(children[0].get_id())
+        );
+    }
+
+    #[test]
+    fn nested_child_is_hit_before_its_parent() {
+        let clicks = Rc::new(Cell::new(0));
+        let mut parent = counting_button(100., 100., &clicks);
+        parent.set_size(crate::guiproperties::guiposition::GUISize::from_pixels(
+            300., 300.,
+        ));
+        let child = counting_button(10., 10., &clicks);
+        let child_id = child.id;
+        parent.add_child(Box::new(child));
+        let children: Vec<Box<dyn Family>> = vec![Box::new(parent)];
+
+        let point = GUIPosition::from_pixels(160., 150.);
+        assert_eq!(
+            find_widget_at(&children, &GUIPosition::from_pixels(0., 0.), &point),
+            Some(child_id)
+        );
+    }
+
+    #[test]
+    fn hovering_marks_the_button_dirty() {
+        let clicks = Rc::new(Cell::new(0));
+        let mut children: Vec<Box<dyn Family>> = vec![Box::new(counting_button(50., 50., &clicks))];
+        children[0].set_dirty(false);
+        let mut pointer = Pointer::default();
+
+        pointer.moved(GUIPosition::from_pixels(150., 100.), &mut children);
+        assert!(children[0].is_dirty());
+        children[0].set_dirty(false);
+        pointer.moved(GUIPosition::from_pixels(160., 100.), &mut children);
+        assert!(!children[0].is_dirty());
+        pointer.press(&mut children);
+        assert!(children[0].is_dirty());
+        children[0].set_dirty(false);
+        pointer.left(&mut children);
+        assert!(children[0].is_dirty());
+    }
+}
@@ -0,0 +1,246 @@
+use crate::guiproperties::guiposition::GUILength;
+use crate::guiproperties::{GUIColor, GUIFill, GUIRoundedRect};
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct RectVertex {
+    /// The position of the quad's corner in logical pixels.
+    pub position: [f32; 2],
+    /// The left, top, right and bottom edges of the rectangle in logical pixels.
+    pub rect: [f32; 4],
+    /// The radius of each corner, clockwise from the top left.
+    pub corner_radii: [f32; 4],
+    /// The straight, not premultiplied, RGBA color of the fill.
+    pub color: [f32; 4],
+    /// The straight, not premultiplied, RGBA color of the border.
+    pub border_color: [f32; 4],
+    pub border_width: f32,
+    /// Which of the frame's fills, counting from 1, the rectangle is filled
+    /// with instead of `color`, or 0 to use `color`.
+    pub fill: u32,
+    /// The standard deviation of the Gaussian blur of the rectangle's edge,
+    /// in logical pixels, or 0 if its edge is sharp.
+    pub blur: f32,
+}
+
+impl RectVertex {
+    pub fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        const ATTRIBUTES: [wgpu::VertexAttribute; 8] = wgpu::vertex_attr_array![
+            0 => Float32x2,
+            1 => Float32x4,
+            2 => Float32x4,
+            3 => Float32x4,
+            4 => Float32x4,
+            5 => Float32,
+            6 => Uint32,
+            7 => Float32,
+        ];
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<RectVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &ATTRIBUTES,
+        }
+    }
+}
+
+/// Makes a quad for each rectangle, which is a physical pixel bigger than the
+/// rectangle all the way around so that there's room for its anti aliased edge,
+/// and three standard deviations bigger again if it's blurred, which is as far
+/// as a blur visibly reaches.
+/// Gradient fills are placed and added to `fills`, which the vertices count into.
+pub fn make_vertices_and_indices(
+    rects: &[GUIRoundedRect],
+    scale: f64,
+    fills: &mut Vec<GUIFill>,
+) -> (Vec<RectVertex>, Vec<u32>) {
+    let mut vertices = Vec::with_capacity(rects.len() * 4);
+    let mut indices = Vec::with_capacity(rects.len() * 6);
+    for rounded_rect in rects {
+        let rect = &rounded_rect.rect;
+        let radii = rounded_rect.corner_radii.fit_within(rect.size);
+        let (color, fill) = match &rounded_rect.background {
+            GUIFill::Solid(color) => (*color, 0),
+            background => {
+                fills.push(background.placed(rect));
+                (GUIColor::default(), fills.len() as u32)
+            }
+        };
+        let to_array = |color: &GUIColor| [color.r, color.g, color.b, color.a].map(|c| c as f32);
+        let template = RectVertex {
+            position: [0.; 2],
+            rect: [rect.left(), rect.top(), rect.right(), rect.bottom()]
+                .map(|edge| edge.get_length() as f32),
+            corner_radii: [
+                radii.top_left,
+                radii.top_right,
+                radii.bottom_right,
+                radii.bottom_left,
+            ]
+            .map(|radius| radius.get_length() as f32),
+            color: to_array(&color),
+            border_color: to_array(&rounded_rect.border_color),
+            border_width: rounded_rect.border_width.get_length().max(0.) as f32,
+            fill,
+            blur: rounded_rect.blur.get_length().max(0.) as f32,
+        };
+        let offset = vertices.len() as u32;
+        let margin = 1. / scale + 3. * template.blur as f64;
+        let quad = rect.inset(GUILength::from_pixels(-margin));
+        vertices.extend(quad.corners().iter().map(|corner| RectVertex {
+            position: [corner.x, corner.y].map(|length| length.get_length() as f32),
+            ..template
+        }));
+        indices.extend([0, 1, 2, 0, 2, 3].map(|index| offset + index));
+    }
+    (vertices, indices)
+}
+
+/// Works out the premultiplied color of a rounded rectangle at a position in
+/// logical pixels, given the color of its fill there, the same way that the
+/// rect fragment shader does.
+pub fn shade(vertex: &RectVertex, position: [f32; 2], scale: f32, fill: [f32; 4]) -> [f32; 4] {
+    if vertex.blur > 0. {
+        let coverage = blurred_coverage(vertex, position);
+        return fill.map(|channel| channel * coverage);
+    }
+    let distance = rounded_rect_distance(vertex, position);
+    let coverage = |distance: f32| (0.5 - distance * scale).clamp(0., 1.);
+    let border = if vertex.border_width > 0. {
+        1. - coverage(distance + vertex.border_width)
+    } else {
+        0.
+    };
+    let [r, g, b, a] = vertex.border_color;
+    let border = [r * a, g * a, b * a, a].map(|channel| channel * border);
+    let outside = coverage(distance);
+    std::array::from_fn(|channel| (border[channel] + fill[channel] * (1. - border[3])) * outside)
+}
+
+/// The signed distance from a position to the edge of the rectangle, in
+/// logical pixels, which is negative inside of the rectangle.
+fn rounded_rect_distance(vertex: &RectVertex, position: [f32; 2]) -> f32 {
+    let [left, top, right, bottom] = vertex.rect;
+    let center = [(left + right) / 2., (top + bottom) / 2.];
+    let half_size = [(right - left) / 2., (bottom - top) / 2.];
+    let [x, y] = [position[0] - center[0], position[1] - center[1]];
+    let [top_left, top_right, bottom_right, bottom_left] = vertex.corner_radii;
+    let radius = match (x < 0., y < 0.) {
+        (true, true) => top_left,
+        (false, true) => top_right,
+        (false, false) => bottom_right,
+        (true, false) => bottom_left,
+    };
+    let q = [
+        x.abs() - half_size[0] + radius,
+        y.abs() - half_size[1] + radius,
+    ];
+    q[0].max(q[1]).min(0.) + q[0].max(0.).hypot(q[1].max(0.)) - radius
+}
+
+/// How much of the rectangle, blurred by a Gaussian, covers a position in
+/// logical pixels. The blur is separable, so across each row it's worked out
+/// exactly from the error function, and down the rows it's summed over a few
+/// samples of the Gaussian. The rows are cut short by the radius of the
+/// corner of the quadrant that the position is in.
+fn blurred_coverage(vertex: &RectVertex, position: [f32; 2]) -> f32 {
+    let [left, top, right, bottom] = vertex.rect;
+    let center = [(left + right) / 2., (top + bottom) / 2.];
+    let half_size = [(right - left) / 2., (bottom - top) / 2.];
+    let [x, y] = [position[0] - center[0], position[1] - center[1]];
+    let [top_left, top_right, bottom_right, bottom_left] = vertex.corner_radii;
+    let radius = match (x < 0., y < 0.) {
+        (true, true) => top_left,
+        (false, true) => top_right,
+        (false, false) => bottom_right,
+        (true, false) => bottom_left,
+    };
+    let sigma = vertex.blur;
+    let gaussian =
+        |x: f32| (-(x * x) / (2. * sigma * sigma)).exp() / (std::f32::consts::TAU.sqrt() * sigma);
+    let row_coverage = |row: f32| {
+        let past_corner = (half_size[1] - radius - row.abs()).min(0.);
+        let half_width =
+            half_size[0] - radius + (radius * radius - past_corner * past_corner).max(0.).sqrt();
+        let integral =
+            |edge: f32| 0.5 + 0.5 * erf((x + edge) * std::f32::consts::FRAC_1_SQRT_2 / sigma);
+        integral(half_width) - integral(-half_width)
+    };
+    const SAMPLES: usize = 4;
+    let start = (-3. * sigma).clamp(y - half_size[1], y + half_size[1]);
+    let end = (3. * sigma).clamp(y - half_size[1], y + half_size[1]);
+    let step = (end - start) / SAMPLES as f32;
+    (0..SAMPLES)
+        .map(|sample| {
+            let offset = start + step * (sample as f32 + 0.5);
+            row_coverage(y - offset) * gaussian(offset) * step
+        })
+        .sum()
+}
+
+/// An approximation of the error function, good to about 5e-4, that the
+/// rect fragment shader uses as well.
+fn erf(x: f32) -> f32 {
+    let a = x.abs();
+    let denominator = 1. + (0.278393 + (0.230389 + 0.078108 * a * a) * a) * a;
+    let denominator = denominator * denominator;
+    x.signum() - x.signum() / (denominator * denominator)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::guiproperties::guiposition::GUIRect;
+    use crate::guiproperties::GUICornerRadii;
+
+    #[test]
+    fn edges_are_anti_aliased_and_corners_are_cut_off() {
+        let red = GUIColor::from_rgba_u8u8u8u8(255, 0, 0, 255);
+        let rect = GUIRoundedRect {
+            rect: GUIRect::from_pixels(10., 10., 40., 20.),
+            corner_radii: GUICornerRadii::from_pixels(10., 0., 0., 0.),
+            background: GUIFill::Solid(red),
+            border_width: GUILength::from_pixels(2.),
+            border_color: GUIColor::from_rgba_u8u8u8u8(0, 0, 255, 255),
+            blur: GUILength::from_pixels(0.),
+        };
+        let mut fills = Vec::new();
+        let (vertices, indices) = make_vertices_and_indices(&[rect], 2., &mut fills);
+        assert_eq!((vertices.len(), indices.len(), fills.len()), (4, 6, 0));
+        assert_eq!(vertices[0].position, [9.5, 9.5]);
+
+        let fill = [1., 0., 0., 1.];
+        let shade = |x, y| shade(&vertices[0], [x, y], 2., fill);
+        assert_eq!(shade(30., 20.), fill);
+        assert_eq!(shade(30., 11.), [0., 0., 1., 1.]);
+        // The edge is blurred across a physical pixel.
+        assert_eq!(shade(30., 10.), [0., 0., 0.5, 0.5]);
+        assert_eq!(shade(30., 9.5), [0.; 4]);
+        // The top left corner is round, and the top right one is square.
+        assert_eq!(shade(11., 11.), [0.; 4]);
+        assert_eq!(shade(49.5, 10.5), [0., 0., 1., 1.]);
+    }
+
+    #[test]
+    fn blurred_edges_fade_out_over_the_blur() {
+        let rect = GUIRoundedRect {
+            rect: GUIRect::from_pixels(0., 0., 100., 100.),
+            corner_radii: GUICornerRadii::uniform(GUILength::from_pixels(10.)),
+            background: GUIFill::Solid(GUIColor::from_rgba_u8u8u8u8(0, 0, 0, 255)),
+            border_width: GUILength::from_pixels(4.),
+            border_color: GUIColor::from_rgba_u8u8u8u8(255, 0, 0, 255),
+            blur: GUILength::from_pixels(5.),
+        };
+        let (vertices, _) = make_vertices_and_indices(&[rect], 1., &mut Vec::new());
+        // The quad reaches three standard deviations past the rectangle.
+        assert_eq!(vertices[0].position, [-16., -16.]);
+
+        let alpha = |x, y| shade(&vertices[0], [x, y], 1., [0., 0., 0., 1.])[3];
+        assert!((alpha(50., 50.) - 1.).abs() < 0.01);
+        // Half of a straight edge is covered, and there's no border.
+        assert!((alpha(0., 50.) - 0.5).abs() < 0.01);
+        assert!((alpha(-5., 50.) - 0.16).abs() < 0.01);
+        assert!(alpha(-16., 50.) < 0.01);
+        // Rounded corners are covered less than square ones would be.
+        assert!(alpha(0., 0.) < 0.2);
+    }
+}
@@ -0,0 +1,456 @@
+use std::iter;
+
+use crate::guiproperties::guitraits::Widget;
+use crate::guiresources::{GUIResources, GUISampleCount};
+use crate::guiwidgets::GUIWindow;
+
+use crate::guiprocessing::dynamic_buffer::DynamicBuffer;
+use crate::guiprocessing::fills::FillsBinding;
+use crate::guiprocessing::globals::{Globals, GlobalsBinding};
+use crate::guiprocessing::rects::RectVertex;
+use crate::guiprocessing::text::{TextPipeline, TextRenderer};
+use crate::guiprocessing::vertices::Vertex;
+use crate::guiprocessing::window_building_utils::{DrawBatch, GeometryCache};
+
+/// Requests a device from an adapter that matches the resources, and that
+/// can present to the surface if one is given. If there's no such
+/// graphics card, a fallback adapter, such as a software one that
+/// the backend provides, is used instead where there is one.
+pub async fn request_device(
+    instance: &wgpu::Instance,
+    guiresources: &GUIResources,
+    compatible_surface: Option<&wgpu::Surface>,
+) -> Option<(wgpu::Adapter, wgpu::Device, wgpu::Queue)> {
+    // The adapter is the handle to the actual graphics card.
+    let options = |force_fallback_adapter| wgpu::RequestAdapterOptions {
+        power_preference: guiresources.power_preference(),
+        compatible_surface,
+        force_fallback_adapter,
+    };
+    let adapter = match instance.request_adapter(&options(false)).await {
+        Some(adapter) => adapter,
+        None => instance.request_adapter(&options(true)).await?,
+    };
+
+    let (device, queue) = adapter
+        .request_device(
+            &wgpu::DeviceDescriptor {
+                label: None,
+                features: wgpu::Features::empty(),
+                limits: wgpu::Limits::default(),
+            },
+            // Some(&std::path::Path::new("trace")), // Trace path
+            None,
+        )
+        .await
+        .ok()?;
+    Some((adapter, device, queue))
+}
+
+/// Returns the most samples, up to the requested count, that can be taken of
+/// each pixel of the format and resolved. wgpu 0.12 only begins render passes
+/// with 1 or 4 samples, so counts that it can't render with fall back to the
+/// highest lower one that it can.
+pub fn supported_sample_count(
+    adapter: &wgpu::Adapter,
+    format: wgpu::TextureFormat,
+    requested: GUISampleCount,
+) -> u32 {
+    let features = adapter.get_texture_format_features(format);
+    // Resolving averages the samples, which can only be done to formats that can be filtered.
+    let multisampled = features
+        .allowed_usages
+        .contains(wgpu::TextureUsages::RENDER_ATTACHMENT)
+        && features.filterable;
+    fallback_sample_count(requested, multisampled)
+}
+
+/// The most samples, up to the requested count, that wgpu 0.12 renders with,
+/// which is only ever 1 if the format can't be multisampled.
+fn fallback_sample_count(requested: GUISampleCount, multisampled: bool) -> u32 {
+    [4, 1]
+        .into_iter()
+        .find(|&count| count <= requested.count() && (count == 1 || multisampled))
+        .unwrap_or(1)
+}
+
+/// The texture that a renderer draws into.
+#[derive(Clone, Copy, Debug)]
+pub struct RenderTarget {
+    pub format: wgpu::TextureFormat,
+    /// The size of the texture in physical pixels.
+    pub width: u32,
+    pub height: u32,
+    /// How many samples are taken of each pixel. If there's more than one, the
+    /// widgets are drawn into a multisampled texture that's resolved into the target.
+    pub sample_count: u32,
+}
+
+/// Everything that's needed to draw a window's widgets into a texture,
+/// whether that texture belongs to a surface or is offscreen.
+pub struct Renderer {
+    pub device: wgpu::Device,
+    pub queue: wgpu::Queue,
+
+    target: RenderTarget,
+    /// What's drawn into when more than one sample is taken of each pixel.
+    multisampled_framebuffer: Option<(wgpu::Texture, wgpu::TextureView)>,
+    globals: GlobalsBinding,
+    /// The gradients that shapes are painted with.
+    fills: FillsBinding,
+    render_pipeline: wgpu::RenderPipeline,
+    /// Draws rounded rectangles as single quads.
+    rect_pipeline: wgpu::RenderPipeline,
+    text_pipeline: TextPipeline,
+
+    vertex_buffer: DynamicBuffer,
+    index_buffer: DynamicBuffer,
+    text_vertex_buffer: DynamicBuffer,
+    text_index_buffer: DynamicBuffer,
+    rect_vertex_buffer: DynamicBuffer,
+    rect_index_buffer: DynamicBuffer,
+    /// The order in which the shapes and text are drawn.
+    batches: Vec<DrawBatch>,
+    geometry_cache: GeometryCache,
+    /// Lays out text, or None if there's no font to draw text with.
+    text_renderer: Option<TextRenderer>,
+}
+
+impl Renderer {
+    /// Makes a renderer that draws into textures like the target,
+    /// and tessellates the window's widgets for the first time.
+    pub fn new(
+        device: wgpu::Device,
+        queue: wgpu::Queue,
+        target: RenderTarget,
+        guiwindow: &mut GUIWindow,
+        guiresources: &GUIResources,
+    ) -> Self {
+        let shader = device.create_shader_module(&wgpu::ShaderModuleDescriptor {
+            label: Some("Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../shaders/shader.wgsl").into()),
+        });
+
+        let globals = GlobalsBinding::new(&device, &Self::globals(guiwindow));
+        let fills = FillsBinding::new(&device);
+
+        let render_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Render Pipeline Layout"),
+                bind_group_layouts: &[&globals.bind_group_layout, &fills.bind_group_layout],
+                push_constant_ranges: &[],
+            });
+
+        // The shape and rect pipelines differ only in their shaders and vertices.
+        let make_pipeline = |label, vertex_entry_point, fragment_entry_point, vertex_buffer| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(label),
+                layout: Some(&render_pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: vertex_entry_point,
+                    buffers: &[vertex_buffer],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: fragment_entry_point,
+                    targets: &[wgpu::ColorTargetState {
+                        format: target.format,
+                        // The fragment shader premultiplies its colors, so translucent
+                        // widgets composite correctly over each other.
+                        blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    }],
+                }),
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleList,
+                    strip_index_format: None,
+                    front_face: wgpu::FrontFace::Ccw,
+                    cull_mode: Some(wgpu::Face::Back),
+                    // Setting this to anything other than Fill requires Features::NON_FILL_POLYGON_MODE
+                    polygon_mode: wgpu::PolygonMode::Fill,
+                    // Requires Features::DEPTH_CLIP_CONTROL
+                    unclipped_depth: false,
+                    // Requires Features::CONSERVATIVE_RASTERIZATION
+                    conservative: false,
+                },
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState {
+                    count: target.sample_count,
+                    mask: !0,
+                    alpha_to_coverage_enabled: false,
+                },
+                // If the pipeline will be used with a multiview render pass, this
+                // indicates how many array layers the attachments will have.
+                multiview: None,
+            })
+        };
+        let render_pipeline =
+            make_pipeline("Render Pipeline", "vs_main", "fs_main", Vertex::desc());
+        let rect_pipeline =
+            make_pipeline("Rect Pipeline", "vs_rect", "fs_rect", RectVertex::desc());
+
+        let text_pipeline = TextPipeline::new(&device, &target, &globals.bind_group_layout);
+        let multisampled_framebuffer = Self::multisampled_framebuffer(&device, &target);
+
+        let buffer = |label, usage| DynamicBuffer::new(&device, label, usage, &[]);
+        let vertex_buffer = buffer("Vertex Buffer", wgpu::BufferUsages::VERTEX);
+        let index_buffer = buffer("Index Buffer", wgpu::BufferUsages::INDEX);
+        let text_vertex_buffer = buffer("Text Vertex Buffer", wgpu::BufferUsages::VERTEX);
+        let text_index_buffer = buffer("Text Index Buffer", wgpu::BufferUsages::INDEX);
+        let rect_vertex_buffer = buffer("Rect Vertex Buffer", wgpu::BufferUsages::VERTEX);
+        let rect_index_buffer = buffer("Rect Index Buffer", wgpu::BufferUsages::INDEX);
+
+        let mut renderer = Self {
+            device,
+            queue,
+            target,
+            multisampled_framebuffer,
+            globals,
+            fills,
+            render_pipeline,
+            rect_pipeline,
+            text_pipeline,
+            vertex_buffer,
+            index_buffer,
+            text_vertex_buffer,
+            text_index_buffer,
+            rect_vertex_buffer,
+            rect_index_buffer,
+            batches: Vec::new(),
+            geometry_cache: GeometryCache::default(),
+            text_renderer: guiresources.font().map(TextRenderer::new),
+        };
+        guiwindow.layout_children();
+        // Everything is about to be tessellated, so nothing is dirty anymore.
+        guiwindow.set_dirty(false);
+        renderer
+            .geometry_cache
+            .invalidate_dirty(&mut guiwindow.children);
+        renderer.rebuild_buffers(guiwindow);
+        renderer
+    }
+
+    /// Re-tessellates the widgets that have changed, along with their
+    /// descendants, and re-uploads the buffers and glyph atlas.
+    pub fn rebuild_buffers(&mut self, guiwindow: &GUIWindow) {
+        let geometry = self.geometry_cache.make_geometry(
+            guiwindow.logical_scale.unwrap_or(1.),
+            &guiwindow.children,
+            self.text_renderer.as_mut(),
+        );
+        let (device, queue) = (&self.device, &self.queue);
+        self.vertex_buffer
+            .write(device, queue, bytemuck::cast_slice(&geometry.vertices[..]));
+        self.index_buffer
+            .write(device, queue, bytemuck::cast_slice(&geometry.indices[..]));
+        self.text_vertex_buffer.write(
+            device,
+            queue,
+            bytemuck::cast_slice(&geometry.text_vertices[..]),
+        );
+        self.text_index_buffer.write(
+            device,
+            queue,
+            bytemuck::cast_slice(&geometry.text_indices[..]),
+        );
+        self.rect_vertex_buffer.write(
+            device,
+            queue,
+            bytemuck::cast_slice(&geometry.rect_vertices[..]),
+        );
+        self.rect_index_buffer.write(
+            device,
+            queue,
+            bytemuck::cast_slice(&geometry.rect_indices[..]),
+        );
+        self.fills.write(device, queue, &geometry.fills);
+        self.batches = geometry.batches;
+        if let Some(text_renderer) = self.text_renderer.as_mut() {
+            self.text_pipeline
+                .upload_atlas(&self.queue, &mut text_renderer.atlas);
+        }
+    }
+
+    fn globals(guiwindow: &GUIWindow) -> Globals {
+        Globals::new(&guiwindow.size, guiwindow.logical_scale.unwrap_or(1.))
+    }
+
+    /// Makes a multisampled texture the size of the target, if more than
+    /// one sample is taken of each pixel.
+    fn multisampled_framebuffer(
+        device: &wgpu::Device,
+        target: &RenderTarget,
+    ) -> Option<(wgpu::Texture, wgpu::TextureView)> {
+        if target.sample_count <= 1 {
+            return None;
+        }
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Multisampled Framebuffer"),
+            size: wgpu::Extent3d {
+                width: target.width.max(1),
+                height: target.height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: target.sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format: target.format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        Some((texture, view))
+    }
+
+    /// Updates the projection after the window is resized, along with the
+    /// multisampled framebuffer, given the new size of the target in physical
+    /// pixels. The vertices are in logical pixels, so none of them have to be redone.
+    pub fn resize(&mut self, guiwindow: &GUIWindow, width: u32, height: u32) {
+        self.globals.write(&self.queue, &Self::globals(guiwindow));
+        if (width, height) != (self.target.width, self.target.height) {
+            self.target.width = width;
+            self.target.height = height;
+            self.multisampled_framebuffer =
+                Self::multisampled_framebuffer(&self.device, &self.target);
+        }
+    }
+
+    /// Re-tessellates everything after the window's logical_scale has changed.
+    /// The widgets are told about the new scale factor first, and glyphs are
+    /// re-rasterized at the new density. The target's new size is in physical pixels.
+    pub fn scale_factor_changed(&mut self, guiwindow: &mut GUIWindow, width: u32, height: u32) {
+        let scale_factor = guiwindow.logical_scale.unwrap_or(1.);
+        self.geometry_cache.scale_factor_changed(
+            &mut guiwindow.children,
+            scale_factor,
+            self.text_renderer.as_mut(),
+        );
+        self.rebuild_buffers(guiwindow);
+        self.resize(guiwindow, width, height);
+    }
+
+    /// Runs the window's on_update closure, lays the widgets out again in
+    /// case anything or the window's size has changed, and then re-tessellates
+    /// and re-uploads whatever has changed since the last update.
+    pub fn update(&mut self, guiwindow: &mut GUIWindow) {
+        guiwindow.run_on_update();
+        guiwindow.layout_children();
+
+        let window_dirty = guiwindow.is_dirty();
+        guiwindow.set_dirty(false);
+        let children_dirty = self
+            .geometry_cache
+            .invalidate_dirty(&mut guiwindow.children);
+        if window_dirty || children_dirty {
+            self.rebuild_buffers(guiwindow);
+        }
+    }
+
+    /// Clears the view to the window's background color and draws the widgets
+    /// over it. The view has to be of a texture like the renderer's target.
+    pub fn draw(&self, view: &wgpu::TextureView, guiwindow: &GUIWindow) {
+        // The samples only have to be kept until they're resolved into the view.
+        let (view, resolve_target) = match &self.multisampled_framebuffer {
+            Some((_, multisampled_view)) => (multisampled_view, Some(view)),
+            None => (view, None),
+        };
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Render Encoder"),
+            });
+
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Render Pass"),
+                color_attachments: &[wgpu::RenderPassColorAttachment {
+                    view,
+                    resolve_target,
+                    ops: wgpu::Operations {
+                        // Everything that's drawn is premultiplied, including the background.
+                        load: wgpu::LoadOp::Clear(wgpu::Color {
+                            r: guiwindow.background_color.r * guiwindow.background_color.a,
+                            g: guiwindow.background_color.g * guiwindow.background_color.a,
+                            b: guiwindow.background_color.b * guiwindow.background_color.a,
+                            a: guiwindow.background_color.a,
+                        }),
+                        store: resolve_target.is_none(),
+                    },
+                }],
+                depth_stencil_attachment: None,
+            });
+
+            render_pass.set_bind_group(0, &self.globals.bind_group, &[]);
+            for batch in self.batches.iter() {
+                match batch {
+                    DrawBatch::Rects(indices) => {
+                        render_pass.set_pipeline(&self.rect_pipeline);
+                        render_pass.set_bind_group(1, &self.fills.bind_group, &[]);
+                        render_pass
+                            .set_vertex_buffer(0, self.rect_vertex_buffer.buffer().slice(..));
+                        render_pass.set_index_buffer(
+                            self.rect_index_buffer.buffer().slice(..),
+                            wgpu::IndexFormat::Uint32,
+                        );
+                        render_pass.draw_indexed(indices.clone(), 0, 0..1);
+                    }
+                    DrawBatch::Shapes(indices) => {
+                        render_pass.set_pipeline(&self.render_pipeline);
+                        render_pass.set_bind_group(1, &self.fills.bind_group, &[]);
+                        render_pass.set_vertex_buffer(0, self.vertex_buffer.buffer().slice(..));
+                        render_pass.set_index_buffer(
+                            self.index_buffer.buffer().slice(..),
+                            wgpu::IndexFormat::Uint32,
+                        );
+                        render_pass.draw_indexed(indices.clone(), 0, 0..1);
+                    }
+                    DrawBatch::Text(indices) => {
+                        render_pass.set_pipeline(&self.text_pipeline.render_pipeline);
+                        render_pass.set_bind_group(1, &self.text_pipeline.bind_group, &[]);
+                        render_pass
+                            .set_vertex_buffer(0, self.text_vertex_buffer.buffer().slice(..));
+                        render_pass.set_index_buffer(
+                            self.text_index_buffer.buffer().slice(..),
+                            wgpu::IndexFormat::Uint32,
+                        );
+                        render_pass.draw_indexed(indices.clone(), 0, 0..1);
+                    }
+                }
+            }
+        }
+
+        self.queue.submit(iter::once(encoder.finish()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parses and validates a shader the same way that wgpu does
+    /// when it makes a shader module.
+    fn validate(source: &str) {
+        let module = naga::front::wgsl::parse_str(source).unwrap();
+        naga::valid::Validator::new(
+            naga::valid::ValidationFlags::all(),
+            naga::valid::Capabilities::empty(),
+        )
+        .validate(&module)
+        .unwrap();
+    }
+
+    #[test]
+    fn shaders_are_valid() {
+        validate(include_str!("../shaders/shader.wgsl"));
+        validate(include_str!("../shaders/text.wgsl"));
+    }
+
+    #[test]
+    fn sample_counts_fall_back_to_ones_that_wgpu_renders_with() {
+        assert_eq!(fallback_sample_count(GUISampleCount::One, true), 1);
+        assert_eq!(fallback_sample_count(GUISampleCount::Two, true), 1);
+        assert_eq!(fallback_sample_count(GUISampleCount::Four, true), 4);
+        assert_eq!(fallback_sample_count(GUISampleCount::Eight, true), 4);
+        assert_eq!(fallback_sample_count(GUISampleCount::Eight, false), 1);
+    }
+}
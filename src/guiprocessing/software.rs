@@ -0,0 +1,528 @@
+use crate::guiproperties::guiimage::{linear_to_srgb, to_u8};
+use crate::guiproperties::guiposition::GUISize;
+use crate::guiproperties::{GUIColor, GUIFill, GUIImage};
+use crate::guiresources::{GUIResources, GUISampleCount};
+use crate::guiwidgets::GUIWindow;
+
+use crate::guiprocessing::fills::{self, FillData, StopData};
+use crate::guiprocessing::globals::Globals;
+use crate::guiprocessing::rects::{self, RectVertex};
+use crate::guiprocessing::text::{GlyphAtlas, TextRenderer, TextVertex, ATLAS_SIZE};
+use crate::guiprocessing::vertices::Vertex;
+use crate::guiprocessing::window_building_utils::{DrawBatch, GeometryCache};
+
+/// Rasterizes the same vertices and indices that are sent to the gpu into
+/// a framebuffer on the cpu. It follows the gpu pipelines as closely as it
+/// can: triangles that face away are culled, colors are blended in linear
+/// space as premultiplied alpha, and the framebuffer is sRGB encoded when
+/// it's turned into an image. Taking more than one sample of each pixel is
+/// multisampling, as the gpu does it.
+pub struct SoftwareRasterizer {
+    width: u32,
+    height: u32,
+    /// Where in each pixel its samples are taken.
+    sample_positions: &'static [[f32; 2]],
+    /// The linear, premultiplied RGBA color of each sample of each
+    /// pixel, row by row from the top left.
+    pixels: Vec<[f32; 4]>,
+    /// Projects vertices onto the framebuffer, like the uniform of the same name.
+    globals: Globals,
+    /// The fills that shapes are painted with, laid out like the storage buffers.
+    fills: Vec<FillData>,
+    stops: Vec<StopData>,
+}
+
+impl SoftwareRasterizer {
+    /// Makes a rasterizer with a transparent black framebuffer of the given
+    /// size in physical pixels, which vertices are drawn to at a scale of 1.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self::with_sample_count(width, height, GUISampleCount::One)
+    }
+
+    /// Makes a rasterizer like new does, that takes the given
+    /// number of samples of each pixel.
+    pub fn with_sample_count(width: u32, height: u32, sample_count: GUISampleCount) -> Self {
+        let sample_positions = sample_positions(sample_count);
+        Self {
+            width,
+            height,
+            sample_positions,
+            pixels: vec![[0.; 4]; width as usize * height as usize * sample_positions.len()],
+            globals: Globals::new(&GUISize::from_pixels(width as f64, height as f64), 1.),
+            fills: Vec::new(),
+            stops: Vec::new(),
+        }
+    }
+
+    /// Sets the globals that vertices are projected with.
+    pub fn set_globals(&mut self, globals: Globals) {
+        self.globals = globals;
+    }
+
+    /// Sets the fills that the vertices' `fill`s count into.
+    pub fn set_fills(&mut self, fills: &[GUIFill]) {
+        (self.fills, self.stops) = fills::pack(fills);
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Fills the whole framebuffer with a color.
+    pub fn clear(&mut self, color: GUIColor) {
+        let color = premultiply(
+            [color.r, color.g, color.b].map(|channel| channel as f32),
+            color.a as f32,
+        );
+        self.pixels.iter_mut().for_each(|pixel| *pixel = color);
+    }
+
+    /// Draws shapes, as made by make_vertices_and_indices,
+    /// blending them over whatever is under them.
+    pub fn draw_shapes(&mut self, vertices: &[Vertex], indices: &[u32]) {
+        for triangle in indices.chunks_exact(3) {
+            let corners = [0, 1, 2].map(|corner| vertices[triangle[corner] as usize]);
+            let positions = corners.map(|vertex| self.globals.project(vertex.position));
+            // Like the gpu, the fill isn't interpolated, it's taken from the first corner.
+            let fill = match corners[0].fill {
+                0 => None,
+                fill => Some(self.fills[fill as usize - 1]),
+            };
+            let stops = std::mem::take(&mut self.stops);
+            self.rasterize(positions, |weights, destination| {
+                let interpolate = |value: &dyn Fn(&Vertex) -> f32| {
+                    (0..3)
+                        .map(|corner| value(&corners[corner]) * weights[corner])
+                        .sum::<f32>()
+                };
+                let source = match fill.as_ref() {
+                    Some(fill) => fills::shade(
+                        fill,
+                        &stops,
+                        [
+                            interpolate(&|vertex| vertex.position[0]),
+                            interpolate(&|vertex| vertex.position[1]),
+                        ],
+                    ),
+                    None => {
+                        let channel = |index: usize| interpolate(&|vertex| vertex.color[index]);
+                        premultiply([channel(0), channel(1), channel(2)], channel(3))
+                    }
+                };
+                blend(source, destination)
+            });
+            self.stops = stops;
+        }
+    }
+
+    /// Draws rounded rectangles, as made by rects::make_vertices_and_indices,
+    /// working out their edges and borders for every pixel like the gpu does.
+    pub fn draw_rects(&mut self, vertices: &[RectVertex], indices: &[u32]) {
+        let scale = self.globals.scale_factor;
+        for triangle in indices.chunks_exact(3) {
+            let corners = [0, 1, 2].map(|corner| vertices[triangle[corner] as usize]);
+            let positions = corners.map(|vertex| {
+                self.globals
+                    .project([vertex.position[0], vertex.position[1], 0.])
+            });
+            // Everything but the position is the same for every corner.
+            let rect = corners[0];
+            let fill = match rect.fill {
+                0 => None,
+                fill => Some(self.fills[fill as usize - 1]),
+            };
+            let stops = std::mem::take(&mut self.stops);
+            self.rasterize(positions, |weights, destination| {
+                let position = [0, 1].map(|axis| {
+                    (0..3)
+                        .map(|corner| corners[corner].position[axis] * weights[corner])
+                        .sum::<f32>()
+                });
+                let fill = match fill.as_ref() {
+                    Some(fill) => fills::shade(fill, &stops, position),
+                    None => {
+                        let [r, g, b, a] = rect.color;
+                        premultiply([r, g, b], a)
+                    }
+                };
+                blend(rects::shade(&rect, position, scale, fill), destination)
+            });
+            self.stops = stops;
+        }
+    }
+
+    /// Draws glyph quads, sampling their coverage from the atlas and
+    /// blending them over whatever is under them.
+    pub fn draw_text(&mut self, vertices: &[TextVertex], indices: &[u32], atlas: &GlyphAtlas) {
+        for triangle in indices.chunks_exact(3) {
+            let corners = [0, 1, 2].map(|corner| vertices[triangle[corner] as usize]);
+            let positions = corners.map(|vertex| self.globals.project(vertex.position));
+            self.rasterize(positions, |weights, destination| {
+                let interpolate = |value: fn(&TextVertex) -> f32| {
+                    (0..3)
+                        .map(|corner| value(&corners[corner]) * weights[corner])
+                        .sum::<f32>()
+                };
+                let coverage = sample_atlas(
+                    atlas,
+                    interpolate(|vertex| vertex.tex_coords[0]),
+                    interpolate(|vertex| vertex.tex_coords[1]),
+                );
+                let color = [
+                    interpolate(|vertex| vertex.color[0]),
+                    interpolate(|vertex| vertex.color[1]),
+                    interpolate(|vertex| vertex.color[2]),
+                ];
+                let alpha = interpolate(|vertex| vertex.color[3]) * coverage;
+                blend(premultiply(color, alpha), destination)
+            });
+        }
+    }
+
+    /// Fills the samples that are inside of a triangle, given in clip space,
+    /// with the color that `shade` returns. `shade` is given the barycentric
+    /// weights of the pixel's center, even if only some of its samples are
+    /// inside, and the current color of a sample. Triangles that aren't
+    /// counter clockwise are culled, like they are by the gpu pipelines.
+    fn rasterize(
+        &mut self,
+        positions: [[f32; 2]; 3],
+        mut shade: impl FnMut([f32; 3], [f32; 4]) -> [f32; 4],
+    ) {
+        let (width, height) = (self.width as f32, self.height as f32);
+        let [a, b, c] = positions.map(|[x, y]| [(x + 1.) / 2. * width, (1. - y) / 2. * height]);
+        // The y axis points down in the framebuffer, so counter clockwise
+        // triangles have a negative area here.
+        let area = edge(a, b, c);
+        if area >= 0. {
+            return;
+        }
+        // Wind the triangle the other way so that the edge functions of
+        // points inside of it are positive, keeping track of which corner
+        // each weight belongs to.
+        let (corners, order) = ([a, c, b], [0, 2, 1]);
+        let area = -area;
+
+        let min_x = corners.iter().map(|p| p[0]).fold(f32::MAX, f32::min);
+        let max_x = corners.iter().map(|p| p[0]).fold(f32::MIN, f32::max);
+        let min_y = corners.iter().map(|p| p[1]).fold(f32::MAX, f32::min);
+        let max_y = corners.iter().map(|p| p[1]).fold(f32::MIN, f32::max);
+        let first_column = min_x.floor().max(0.) as u32;
+        let last_column = ((max_x.ceil() - 1.).min(width - 1.)).max(-1.) as i64;
+        let first_row = min_y.floor().max(0.) as u32;
+        let last_row = ((max_y.ceil() - 1.).min(height - 1.)).max(-1.) as i64;
+
+        let edges = |point: [f32; 2]| {
+            [
+                edge(corners[1], corners[2], point),
+                edge(corners[2], corners[0], point),
+                edge(corners[0], corners[1], point),
+            ]
+        };
+        let starts = [corners[1], corners[2], corners[0]];
+        let ends = [corners[2], corners[0], corners[1]];
+        let inside = |point: [f32; 2]| {
+            let edges = edges(point);
+            (0..3).all(|index| {
+                edges[index] > 0. || (edges[index] == 0. && is_top_left(starts[index], ends[index]))
+            })
+        };
+        let samples = self.sample_positions.len();
+        for row in first_row as i64..=last_row {
+            for column in first_column as i64..=last_column {
+                let pixel = row as usize * self.width as usize + column as usize;
+                let mut weights = None;
+                for (sample, [x, y]) in self.sample_positions.iter().enumerate() {
+                    if !inside([column as f32 + x, row as f32 + y]) {
+                        continue;
+                    }
+                    let weights = *weights.get_or_insert_with(|| {
+                        let edges = edges([column as f32 + 0.5, row as f32 + 0.5]);
+                        let mut weights = [0.; 3];
+                        for index in 0..3 {
+                            weights[order[index]] = edges[index] / area;
+                        }
+                        weights
+                    });
+                    let destination = &mut self.pixels[pixel * samples + sample];
+                    *destination = shade(weights, *destination);
+                }
+            }
+        }
+    }
+
+    /// Returns the linear, premultiplied RGBA color of the pixel at x, y,
+    /// which is the average of its samples.
+    pub fn pixel(&self, x: u32, y: u32) -> [f32; 4] {
+        let samples = self.sample_positions.len();
+        let pixel = y as usize * self.width as usize + x as usize;
+        resolve(&self.pixels[pixel * samples..(pixel + 1) * samples])
+    }
+
+    /// sRGB encodes the framebuffer into an image, the way that an
+    /// Rgba8UnormSrgb texture stores what's drawn to it, with colors
+    /// that aren't premultiplied.
+    pub fn to_image(&self) -> GUIImage {
+        let pixels = self
+            .pixels
+            .chunks_exact(self.sample_positions.len())
+            .map(resolve)
+            .flat_map(|[r, g, b, a]| {
+                let unpremultiply = |channel: f32| if a > 0. { channel / a } else { 0. };
+                [
+                    linear_to_srgb(unpremultiply(r)),
+                    linear_to_srgb(unpremultiply(g)),
+                    linear_to_srgb(unpremultiply(b)),
+                    to_u8(a),
+                ]
+            })
+            .collect();
+        GUIImage::from_pixels(self.width, self.height, pixels)
+            .expect("the framebuffer has four channels per pixel")
+    }
+}
+
+/// Where the samples of a pixel are taken, relative to its top left corner,
+/// at the standard positions that Vulkan, Metal and D3D all use.
+fn sample_positions(sample_count: GUISampleCount) -> &'static [[f32; 2]] {
+    match sample_count {
+        GUISampleCount::One => &[[0.5, 0.5]],
+        GUISampleCount::Two => &[[0.75, 0.75], [0.25, 0.25]],
+        GUISampleCount::Four => &[
+            [0.375, 0.125],
+            [0.875, 0.375],
+            [0.125, 0.625],
+            [0.625, 0.875],
+        ],
+        GUISampleCount::Eight => &[
+            [0.5625, 0.3125],
+            [0.4375, 0.6875],
+            [0.8125, 0.5625],
+            [0.3125, 0.1875],
+            [0.1875, 0.8125],
+            [0.0625, 0.4375],
+            [0.6875, 0.9375],
+            [0.9375, 0.0625],
+        ],
+    }
+}
+
+/// Averages the samples of a pixel, like a multisampled texture is resolved.
+fn resolve(samples: &[[f32; 4]]) -> [f32; 4] {
+    let count = samples.len() as f32;
+    std::array::from_fn(|channel| samples.iter().map(|sample| sample[channel]).sum::<f32>() / count)
+}
+
+fn premultiply(color: [f32; 3], alpha: f32) -> [f32; 4] {
+    [color[0] * alpha, color[1] * alpha, color[2] * alpha, alpha]
+}
+
+/// Blends a premultiplied color over another, the same
+/// way as wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING.
+fn blend(source: [f32; 4], destination: [f32; 4]) -> [f32; 4] {
+    let mut blended = [0.; 4];
+    for channel in 0..4 {
+        blended[channel] = source[channel] + destination[channel] * (1. - source[3]);
+    }
+    blended
+}
+
+/// Twice the signed area of the triangle a, b, p.
+fn edge(a: [f32; 2], b: [f32; 2], p: [f32; 2]) -> f32 {
+    (b[0] - a[0]) * (p[1] - a[1]) - (b[1] - a[1]) * (p[0] - a[0])
+}
+
+/// Whether or not an edge of a clockwise triangle, in a framebuffer whose y
+/// axis points down, is a top or left edge. Pixel centers that are exactly
+/// on an edge are only filled for top and left edges, so that triangles
+/// that share an edge never both fill the same pixel.
+fn is_top_left(start: [f32; 2], end: [f32; 2]) -> bool {
+    let (dx, dy) = (end[0] - start[0], end[1] - start[1]);
+    (dy == 0. && dx > 0.) || dy < 0.
+}
+
+/// Samples the atlas with bilinear filtering and clamped edges,
+/// like the sampler in the text pipeline.
+fn sample_atlas(atlas: &GlyphAtlas, u: f32, v: f32) -> f32 {
+    let size = ATLAS_SIZE as f32;
+    let (x, y) = (u * size - 0.5, v * size - 0.5);
+    let (x0, y0) = (x.floor(), y.floor());
+    let (fx, fy) = (x - x0, y - y0);
+    let texel = |x: f32, y: f32| {
+        let x = x.clamp(0., size - 1.) as usize;
+        let y = y.clamp(0., size - 1.) as usize;
+        atlas.pixels[y * ATLAS_SIZE as usize + x] as f32 / 255.
+    };
+    let top = texel(x0, y0) * (1. - fx) + texel(x0 + 1., y0) * fx;
+    let bottom = texel(x0, y0 + 1.) * (1. - fx) + texel(x0 + 1., y0 + 1.) * fx;
+    top * (1. - fy) + bottom * fy
+}
+
+/// Renders a single frame of the window with the software rasterizer,
+/// at the given size in physical pixels.
+pub(crate) fn render_window(
+    guiwindow: &mut GUIWindow,
+    guiresources: &GUIResources,
+    width: u32,
+    height: u32,
+) -> GUIImage {
+    guiwindow.run_on_update();
+    guiwindow.layout_children();
+    let mut text_renderer = guiresources.font().map(TextRenderer::new);
+    let scale = guiwindow.logical_scale.unwrap_or(1.);
+    let geometry =
+        GeometryCache::default().make_geometry(scale, &guiwindow.children, text_renderer.as_mut());
+
+    let mut rasterizer =
+        SoftwareRasterizer::with_sample_count(width, height, guiresources.sample_count());
+    rasterizer.set_globals(Globals::new(&guiwindow.size, scale));
+    rasterizer.set_fills(&geometry.fills);
+    rasterizer.clear(guiwindow.background_color);
+    for batch in geometry.batches.iter() {
+        match batch {
+            DrawBatch::Rects(indices) => rasterizer.draw_rects(
+                &geometry.rect_vertices,
+                &geometry.rect_indices[indices.start as usize..indices.end as usize],
+            ),
+            DrawBatch::Shapes(indices) => rasterizer.draw_shapes(
+                &geometry.vertices,
+                &geometry.indices[indices.start as usize..indices.end as usize],
+            ),
+            DrawBatch::Text(indices) => {
+                if let Some(text_renderer) = text_renderer.as_ref() {
+                    rasterizer.draw_text(
+                        &geometry.text_vertices,
+                        &geometry.text_indices[indices.start as usize..indices.end as usize],
+                        &text_renderer.atlas,
+                    );
+                }
+            }
+        }
+    }
+    rasterizer.to_image()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::guiprocessing::window_building_utils::make_vertices_and_indices;
+    use crate::guiproperties::guiposition::{GUIPosition, GUISize};
+    use crate::guiproperties::guitraits::{Child, Parent, Widget};
+    use crate::guiwidgets::{GUIButton, GUIButtonRendering};
+
+    fn red() -> GUIColor {
+        GUIColor {
+            r: 1.,
+            g: 0.,
+            b: 0.,
+            a: 1.,
+        }
+    }
+
+    #[test]
+    fn clear_fills_every_pixel() {
+        let mut rasterizer = SoftwareRasterizer::new(3, 2);
+        rasterizer.clear(red());
+        let image = rasterizer.to_image();
+        assert!(image
+            .pixels()
+            .chunks(4)
+            .all(|pixel| pixel == [255, 0, 0, 255]));
+    }
+
+    #[test]
+    fn triangles_that_share_an_edge_fill_each_pixel_once() {
+        // Two counter clockwise triangles that make up the whole framebuffer.
+        let vertex = |x, y| Vertex::new([x, y, 0.], [0.5, 0.5, 0.5, 1.], 0);
+        let vertices = [
+            vertex(0., 0.),
+            vertex(0., 4.),
+            vertex(4., 4.),
+            vertex(4., 0.),
+        ];
+        let mut rasterizer = SoftwareRasterizer::new(4, 4);
+        rasterizer.draw_shapes(&vertices, &[0, 1, 2, 0, 2, 3]);
+        for y in 0..4 {
+            for x in 0..4 {
+                assert_eq!(rasterizer.pixel(x, y), [0.5, 0.5, 0.5, 1.]);
+            }
+        }
+
+        // Clockwise triangles face away and are culled.
+        let mut rasterizer = SoftwareRasterizer::new(4, 4);
+        rasterizer.draw_shapes(&vertices, &[0, 2, 1, 0, 3, 2]);
+        assert_eq!(rasterizer.pixel(1, 2), [0.; 4]);
+    }
+
+    #[test]
+    fn multisampled_pixels_average_the_samples_that_are_covered() {
+        // A triangle that covers the left half of a pixel, missing its center.
+        let vertex = |x, y| Vertex::new([x, y, 0.], [1., 1., 1., 1.], 0);
+        let vertices = [
+            vertex(0., 0.),
+            vertex(0., 1.),
+            vertex(0.5, 1.),
+            vertex(0.5, 0.),
+        ];
+        let indices = [0, 1, 2, 0, 2, 3];
+        let mut rasterizer = SoftwareRasterizer::new(1, 1);
+        rasterizer.draw_shapes(&vertices, &indices);
+        assert_eq!(rasterizer.pixel(0, 0), [0.; 4]);
+
+        let mut rasterizer = SoftwareRasterizer::with_sample_count(1, 1, GUISampleCount::Four);
+        rasterizer.draw_shapes(&vertices, &indices);
+        assert_eq!(rasterizer.pixel(0, 0), [0.5; 4]);
+    }
+
+    #[test]
+    fn translucent_shapes_are_blended_over_each_other() {
+        let quad = |left: f32, color: [f32; 4]| {
+            let vertex = |x, y| Vertex::new([x, y, 0.], color, 0);
+            [
+                vertex(left, 0.),
+                vertex(left, 1.),
+                vertex(left + 2., 1.),
+                vertex(left + 2., 0.),
+            ]
+        };
+        let mut vertices = quad(0., [1., 0., 0., 0.5]).to_vec();
+        vertices.extend(quad(1., [0., 1., 0., 0.5]));
+        let mut rasterizer = SoftwareRasterizer::new(3, 1);
+        rasterizer.clear(GUIColor {
+            r: 0.,
+            g: 0.,
+            b: 1.,
+            a: 0.5,
+        });
+        rasterizer.draw_shapes(&vertices, &[0, 1, 2, 0, 2, 3, 4, 5, 6, 4, 6, 7]);
+
+        assert_eq!(rasterizer.pixel(0, 0), [0.5, 0., 0.25, 0.75]);
+        assert_eq!(rasterizer.pixel(1, 0), [0.25, 0.5, 0.125, 0.875]);
+        assert_eq!(rasterizer.pixel(2, 0), [0., 0.5, 0.25, 0.75]);
+    }
+
+    #[test]
+    fn renders_a_button_over_the_window_background() {
+        let mut button = GUIButton::default();
+        button.set_rendering(GUIButtonRendering::Tessellated);
+        button.set_size(GUISize::from_pixels(40., 20.));
+        button.set_position_from_position(GUIPosition::from_pixels(10., 10.));
+        button.set_background_color(red());
+        let mut guiwindow = GUIWindow::default();
+        guiwindow.set_size(GUISize::from_pixels(100., 100.));
+        guiwindow.add_child(Box::new(button));
+
+        let (vertices, indices) = make_vertices_and_indices(&guiwindow.children);
+        let mut rasterizer = SoftwareRasterizer::new(100, 100);
+        rasterizer.clear(guiwindow.background_color);
+        rasterizer.draw_shapes(&vertices, &indices);
+        let image = rasterizer.to_image();
+        assert_eq!(image.pixel(30, 20), [255, 0, 0, 255]);
+        assert_eq!(image.pixel(52, 20), [170, 170, 170, 255]);
+        assert_eq!(image.pixel(90, 90), [170, 170, 170, 255]);
+    }
+}
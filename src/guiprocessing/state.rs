@@ -1,65 +1,48 @@
-use std::iter;
-
-use wgpu::util::DeviceExt;
-use winit::event::{ElementState, KeyboardInput, VirtualKeyCode, WindowEvent};
+use winit::event::{ElementState, MouseButton, WindowEvent};
 use winit::window::Window;
 
-use crate::guiproperties::guiposition::GUISize;
+use crate::guiproperties::guiposition::{GUIPosition, GUISize};
 use crate::guiresources::GUIResources;
-use crate::guiwidgets::{GUIButton, GUIWindow};
+use crate::guiwidgets::GUIWindow;
 
-use crate::guiprocessing::vertices::Vertex;
-// use crate::guiprocessing::vertices::{Vertex, INDICES, VERTICES};
-use crate::guiprocessing::window_building_utils;
+use crate::guiprocessing::pointer::Pointer;
+use crate::guiprocessing::renderer::{self, RenderTarget, Renderer};
 
 pub struct State {
     surface: wgpu::Surface,
-    device: wgpu::Device,
-    queue: wgpu::Queue,
     config: wgpu::SurfaceConfiguration,
+    renderer: Renderer,
 
-    render_pipeline: wgpu::RenderPipeline,
-
-    vertex_buffer: wgpu::Buffer,
-    index_buffer: wgpu::Buffer,
-    num_indices: u32,
+    pointer: Pointer,
 
     pub guiwindow: GUIWindow,
 }
 
 impl State {
-    pub async fn new(window: &Window, guiwindow: GUIWindow, guiresources: GUIResources) -> Self {
+    pub async fn new(
+        window: &Window,
+        mut guiwindow: GUIWindow,
+        guiresources: GUIResources,
+    ) -> Result<Self, &'static str> {
         // The instance is a handle to our GPU
         // BackendBit::PRIMARY => Vulkan + Metal + DX12 + Browser WebGPU
         let instance = wgpu::Instance::new(guiresources.backend());
         // The surface is part of the window that's drawn to.
         let surface = unsafe { instance.create_surface(window) };
-        // The adapter is the handle to the actual graphics card.
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: guiresources.power_preference(),
-                compatible_surface: Some(&surface),
-                force_fallback_adapter: false,
-            })
-            .await
-            .unwrap();
-
-        let (device, queue) = adapter
-            .request_device(
-                &wgpu::DeviceDescriptor {
-                    label: None,
-                    features: wgpu::Features::empty(),
-                    limits: wgpu::Limits::default(),
-                },
-                // Some(&std::path::Path::new("trace")), // Trace path
-                None,
-            )
-            .await
-            .unwrap();
-
+        let (adapter, device, queue) =
+            renderer::request_device(&instance, &guiresources, Some(&surface))
+                .await
+                .ok_or("no adapter and device could be found for the selected backend")?;
+
+        // wgpu 0.12 always configures surfaces with an opaque composite alpha
+        // mode and has no way to ask for another. Translucent window backgrounds
+        // need wgpu to be upgraded to a version whose SurfaceConfiguration takes
+        // an alpha mode, and the window to be made with with_transparent.
         let config = wgpu::SurfaceConfiguration {
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            format: surface.get_preferred_format(&adapter).unwrap(),
+            format: surface
+                .get_preferred_format(&adapter)
+                .ok_or("the adapter can't draw to the window")?,
             width: guiwindow
                 .size
                 .width
@@ -72,154 +55,122 @@ impl State {
         };
         surface.configure(&device, &config);
 
-        let shader = device.create_shader_module(&wgpu::ShaderModuleDescriptor {
-            label: Some("Shader"),
-            source: wgpu::ShaderSource::Wgsl(include_str!("../shaders/shader.wgsl").into()),
-        });
-
-        let render_pipeline_layout =
-            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                label: Some("Render Pipeline Layout"),
-                bind_group_layouts: &[],
-                push_constant_ranges: &[],
-            });
-
-        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Render Pipeline"),
-            layout: Some(&render_pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: "vs_main",
-                buffers: &[Vertex::desc()],
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &shader,
-                entry_point: "fs_main",
-                targets: &[wgpu::ColorTargetState {
-                    format: config.format,
-                    blend: Some(wgpu::BlendState {
-                        color: wgpu::BlendComponent::REPLACE,
-                        alpha: wgpu::BlendComponent::REPLACE,
-                    }),
-                    write_mask: wgpu::ColorWrites::ALL,
-                }],
-            }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: Some(wgpu::Face::Back),
-                // Setting this to anything other than Fill requires Features::NON_FILL_POLYGON_MODE
-                polygon_mode: wgpu::PolygonMode::Fill,
-                // Requires Features::DEPTH_CLIP_CONTROL
-                unclipped_depth: false,
-                // Requires Features::CONSERVATIVE_RASTERIZATION
-                conservative: false,
-            },
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState {
-                count: 1,
-                mask: !0,
-                alpha_to_coverage_enabled: false,
-            },
-            // If the pipeline will be used with a multiview render pass, this
-            // indicates how many array layers the attachments will have.
-            multiview: None,
-        });
-
-        let (vertices, indices) = window_building_utils::make_vertices_and_indices(
-            &guiwindow.size,
-            &guiwindow.children,
-        );
-        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Vertex Buffer"),
-            // contents: bytemuck::cast_slice(VERTICES),
-            contents: bytemuck::cast_slice(&vertices[..]),
-            usage: wgpu::BufferUsages::VERTEX,
-        });
-        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Index Buffer"),
-            // contents: bytemuck::cast_slice(INDICES),
-            contents: bytemuck::cast_slice(&indices[..]),
-            usage: wgpu::BufferUsages::INDEX,
-        });
-        // let num_indices = INDICES.len() as u32;
-        let num_indices = indices.len() as u32;
-
-        Self {
+        let target = RenderTarget {
+            format: config.format,
+            width: config.width,
+            height: config.height,
+            sample_count: renderer::supported_sample_count(
+                &adapter,
+                config.format,
+                guiresources.sample_count(),
+            ),
+        };
+        let renderer = Renderer::new(device, queue, target, &mut guiwindow, &guiresources);
+
+        Ok(Self {
             surface,
-            device,
-            queue,
             config,
-            render_pipeline,
-            vertex_buffer,
-            index_buffer,
-            num_indices,
+            renderer,
+            pointer: Pointer::default(),
             guiwindow,
-        }
+        })
     }
+
     pub fn resize(&mut self, new_size: GUISize) {
         self.guiwindow.size = new_size;
-        self.config.width = new_size
-            .width
-            .get_physical_length(&self.guiwindow.logical_scale.unwrap())
-            .round() as u32;
-        self.config.height = new_size
-            .height
-            .get_physical_length(&self.guiwindow.logical_scale.unwrap())
-            .round() as u32;
-        self.surface.configure(&self.device, &self.config);
+        let (width, height) = self.physical_size();
+        self.config.width = width;
+        self.config.height = height;
+        self.surface.configure(&self.renderer.device, &self.config);
+        self.renderer
+            .resize(&self.guiwindow, self.config.width, self.config.height);
+    }
+
+    /// Moves the window to a display with a different scale factor. The window
+    /// keeps the physical size that the windowing system gives it, so its
+    /// logical size changes along with the scale factor.
+    pub fn set_scale_factor(
+        &mut self,
+        scale_factor: f64,
+        physical_width: u32,
+        physical_height: u32,
+    ) {
+        self.guiwindow.logical_scale = Some(scale_factor);
+        self.guiwindow.size = GUISize::from_physical_pixels(
+            physical_width as f64,
+            physical_height as f64,
+            &scale_factor,
+        );
+        self.config.width = physical_width;
+        self.config.height = physical_height;
+        self.surface.configure(&self.renderer.device, &self.config);
+        self.renderer
+            .scale_factor_changed(&mut self.guiwindow, physical_width, physical_height);
     }
 
-    #[allow(unused_variables)]
+    /// Handles pointer events, returning whether or not the event was consumed.
     pub fn input(&mut self, event: &WindowEvent) -> bool {
-        false
+        let children = &mut self.guiwindow.children;
+        match event {
+            WindowEvent::CursorMoved { position, .. } => {
+                let position = GUIPosition::from_physical_pixels(
+                    position.x,
+                    position.y,
+                    &self.guiwindow.logical_scale.unwrap(),
+                );
+                self.pointer.moved(position, children);
+                true
+            }
+            WindowEvent::CursorLeft { .. } => {
+                self.pointer.left(children);
+                true
+            }
+            WindowEvent::MouseInput {
+                state,
+                button: MouseButton::Left,
+                ..
+            } => match state {
+                ElementState::Pressed => self.pointer.press(children),
+                ElementState::Released => self.pointer.release(children),
+            },
+            _ => false,
+        }
     }
 
-    #[warn(dead_code)]
-    pub fn update(&mut self) {}
+    /// Runs the window's on_update closure, lays the widgets out, and then
+    /// re-tessellates and re-uploads whatever has changed since the last update.
+    /// If on_update changed the window's size, the surface and the projection
+    /// are resized to match.
+    pub fn update(&mut self) {
+        self.renderer.update(&mut self.guiwindow);
+        if self.physical_size() != (self.config.width, self.config.height) {
+            self.resize(self.guiwindow.size);
+        }
+    }
+
+    /// The window's size in physical pixels.
+    pub fn physical_size(&self) -> (u32, u32) {
+        let logical_scale = self.guiwindow.logical_scale.unwrap();
+        (
+            self.guiwindow
+                .size
+                .width
+                .get_physical_length(&logical_scale)
+                .round() as u32,
+            self.guiwindow
+                .size
+                .height
+                .get_physical_length(&logical_scale)
+                .round() as u32,
+        )
+    }
 
     pub fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
         let output = self.surface.get_current_texture()?;
         let view = output
             .texture
             .create_view(&wgpu::TextureViewDescriptor::default());
-
-        let mut encoder = self
-            .device
-            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                label: Some("Render Encoder"),
-            });
-
-        {
-            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Render Pass"),
-                color_attachments: &[wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color {
-                            r: self.guiwindow.background_color.r,
-                            g: self.guiwindow.background_color.g,
-                            b: self.guiwindow.background_color.b,
-                            a: self.guiwindow.background_color.a,
-                        }),
-                        store: true,
-                    },
-                }],
-                depth_stencil_attachment: None,
-            });
-
-            render_pass.set_pipeline(&self.render_pipeline);
-
-            let data = (&self.vertex_buffer, &self.index_buffer, self.num_indices);
-            render_pass.set_vertex_buffer(0, data.0.slice(..));
-            render_pass.set_index_buffer(data.1.slice(..), wgpu::IndexFormat::Uint16);
-
-            render_pass.draw_indexed(0..data.2, 0, 0..1);
-        }
-
-        self.queue.submit(iter::once(encoder.finish()));
+        self.renderer.draw(&view, &self.guiwindow);
         output.present();
 
         Ok(())
@@ -0,0 +1,636 @@
+use std::collections::HashMap;
+
+use crate::guiproperties::guiposition::GUIPosition;
+use crate::guiproperties::{GUILineCap, GUILineJoin, GUIStroke};
+use crate::guiwidgets::widget_utils::arcs;
+
+/// Splits a polygon into triangles by ear clipping. The polygon can be concave,
+/// and can have holes cut out of it. Holes are joined onto the outline by
+/// bridges first, so that there's a single outline to clip ears from.
+///
+/// The returned indices point into the outline's positions followed by each
+/// hole's, in order. Every triangle is counter clockwise on screen, whichever
+/// way the outline and holes are wound, so none of them are culled.
+/// Self intersecting outlines are tessellated, but not necessarily correctly.
+pub fn tessellate_polygon(outline: &[GUIPosition], holes: &[Vec<GUIPosition>]) -> Vec<u32> {
+    // Flip the y axis so that counter clockwise on screen is the
+    // mathematically positive direction.
+    let mut points: Vec<[f64; 2]> = outline.iter().map(to_point).collect();
+    let mut ring: Vec<usize> = (0..points.len()).collect();
+    if signed_area(&points, &ring) < 0. {
+        ring.reverse();
+    }
+
+    let mut hole_rings = Vec::new();
+    for hole in holes.iter() {
+        let start = points.len();
+        points.extend(hole.iter().map(to_point));
+        let mut hole_ring: Vec<usize> = (start..points.len()).collect();
+        if hole_ring.len() < 3 {
+            continue;
+        }
+        // Holes go the opposite way to the outline.
+        if signed_area(&points, &hole_ring) > 0. {
+            hole_ring.reverse();
+        }
+        hole_rings.push(hole_ring);
+    }
+    // Bridging the rightmost holes first keeps later bridges from crossing them.
+    hole_rings.sort_by(|a, b| {
+        let rightmost = |ring: &Vec<usize>| {
+            ring.iter()
+                .map(|&index| points[index][0])
+                .fold(f64::MIN, f64::max)
+        };
+        rightmost(b).total_cmp(&rightmost(a))
+    });
+    for hole_ring in hole_rings.iter() {
+        bridge_hole(&points, &mut ring, hole_ring);
+    }
+
+    clip_ears(&points, ring)
+}
+
+fn to_point(position: &GUIPosition) -> [f64; 2] {
+    [position.x.get_length(), -position.y.get_length()]
+}
+
+/// Twice the area of a ring, which is positive if it's counter clockwise.
+fn signed_area(points: &[[f64; 2]], ring: &[usize]) -> f64 {
+    (0..ring.len())
+        .map(|i| {
+            let [ax, ay] = points[ring[i]];
+            let [bx, by] = points[ring[(i + 1) % ring.len()]];
+            ax * by - bx * ay
+        })
+        .sum()
+}
+
+/// Twice the signed area of the triangle abc, which is positive if it's counter clockwise.
+fn cross(a: [f64; 2], b: [f64; 2], c: [f64; 2]) -> f64 {
+    (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0])
+}
+
+/// Whether or not p is inside of, or on the edge of, the counter clockwise triangle abc.
+fn in_triangle(a: [f64; 2], b: [f64; 2], c: [f64; 2], p: [f64; 2]) -> bool {
+    cross(a, b, p) >= 0. && cross(b, c, p) >= 0. && cross(c, a, p) >= 0.
+}
+
+/// Joins a clockwise hole onto the counter clockwise ring by cutting from the
+/// hole's rightmost vertex to a vertex of the ring that it can see, and back.
+fn bridge_hole(points: &[[f64; 2]], ring: &mut Vec<usize>, hole: &[usize]) {
+    let (hole_start, &hole_vertex) = hole
+        .iter()
+        .enumerate()
+        .max_by(|(_, &a), (_, &b)| points[a][0].total_cmp(&points[b][0]))
+        .expect("holes have at least three vertices");
+    let m = points[hole_vertex];
+
+    // Find the closest edge that a ray going right from m hits.
+    let mut closest: Option<(f64, usize)> = None;
+    for i in 0..ring.len() {
+        let a = points[ring[i]];
+        let b = points[ring[(i + 1) % ring.len()]];
+        // The ring is counter clockwise, so edges that are hit from
+        // the inside go upwards.
+        if a[1] > m[1] || b[1] < m[1] || a[1] == b[1] {
+            continue;
+        }
+        let x = a[0] + (m[1] - a[1]) * (b[0] - a[0]) / (b[1] - a[1]);
+        if x >= m[0] && closest.is_none_or(|(closest_x, _)| x < closest_x) {
+            // Of the edge's ends, the one furthest to the right is the candidate.
+            let end = if a[0] > b[0] { i } else { (i + 1) % ring.len() };
+            closest = Some((x, end));
+        }
+    }
+    let Some((x, mut bridge)) = closest else {
+        // The hole isn't inside of the outline, so leave it out.
+        return;
+    };
+
+    // A reflex vertex inside of the triangle between m, the hit and the candidate
+    // would block the bridge. If there are any, use the one closest to the ray.
+    let hit = [x, m[1]];
+    let candidate = points[ring[bridge]];
+    let (a, b, c) = if candidate[1] < m[1] {
+        (m, candidate, hit)
+    } else {
+        (m, hit, candidate)
+    };
+    let mut best_angle = f64::MAX;
+    for i in 0..ring.len() {
+        let point = points[ring[i]];
+        let previous = points[ring[(i + ring.len() - 1) % ring.len()]];
+        let next = points[ring[(i + 1) % ring.len()]];
+        let reflex = cross(previous, point, next) < 0.;
+        if i == bridge || !reflex || point == m || !in_triangle(a, b, c, point) {
+            continue;
+        }
+        let angle = (point[1] - m[1]).abs().atan2(point[0] - m[0]);
+        if angle < best_angle {
+            best_angle = angle;
+            bridge = i;
+        }
+    }
+
+    // Go around the hole from its rightmost vertex back to it, and then
+    // back along the bridge to where it started.
+    let mut spliced = Vec::with_capacity(ring.len() + hole.len() + 2);
+    spliced.extend_from_slice(&ring[..=bridge]);
+    spliced.extend((0..=hole.len()).map(|i| hole[(hole_start + i) % hole.len()]));
+    spliced.extend_from_slice(&ring[bridge..]);
+    *ring = spliced;
+}
+
+/// Clips ears off of a counter clockwise ring until there's nothing left.
+fn clip_ears(points: &[[f64; 2]], mut ring: Vec<usize>) -> Vec<u32> {
+    let mut indices = Vec::with_capacity(ring.len().saturating_sub(2) * 3);
+    let mut i = 0;
+    // How many vertices in a row have been looked at without finding an ear.
+    let mut misses = 0;
+    while ring.len() >= 3 {
+        let length = ring.len();
+        let (previous, current, next) = (
+            ring[(i + length - 1) % length],
+            ring[i % length],
+            ring[(i + 1) % length],
+        );
+        let (a, b, c) = (points[previous], points[current], points[next]);
+        let area = cross(a, b, c);
+
+        let is_ear = if area == 0. {
+            // Collinear and repeated vertices, like the ends of a bridge,
+            // can be dropped without leaving a gap.
+            ring.remove(i % length);
+            misses = 0;
+            continue;
+        } else if misses >= length {
+            // Nothing is an ear, which means that the polygon intersects
+            // itself. Clip convex vertices anyway so that it's still filled.
+            area > 0. || misses >= 2 * length
+        } else {
+            area > 0.
+                && !ring.iter().any(|&other| {
+                    let point = points[other];
+                    other != previous
+                        && other != current
+                        && other != next
+                        && point != a
+                        && point != b
+                        && point != c
+                        && in_triangle(a, b, c, point)
+                })
+        };
+
+        if is_ear {
+            if area > 0. {
+                indices.extend([previous as u32, current as u32, next as u32]);
+            } else {
+                indices.extend([previous as u32, next as u32, current as u32]);
+            }
+            ring.remove(i % length);
+            misses = 0;
+        } else {
+            i = (i + 1) % length;
+            misses += 1;
+        }
+    }
+    indices
+}
+
+/// Tessellates a stroke along an outline, which is closed back on itself if
+/// closed is set. Round joins and caps stray no more than tolerance logical
+/// pixels from circles. Returns the positions of the triangles' vertices,
+/// and the triangles, which are all counter clockwise on screen.
+/// Where the stroke turns back sharply on itself, triangles can overlap.
+pub fn tessellate_stroke(
+    outline: &[GUIPosition],
+    closed: bool,
+    stroke: &GUIStroke,
+    tolerance: f64,
+) -> (Vec<GUIPosition>, Vec<u32>) {
+    let mut points: Vec<[f64; 2]> = outline
+        .iter()
+        .map(|position| [position.x.get_length(), position.y.get_length()])
+        .collect();
+    points.dedup_by(|a, b| distance(*a, *b) < EPSILON);
+    if closed && points.len() > 1 && distance(points[0], points[points.len() - 1]) < EPSILON {
+        points.pop();
+    }
+
+    let mut builder = StrokeBuilder {
+        stroke,
+        half_width: stroke.width.get_length() / 2.,
+        tolerance,
+        positions: Vec::new(),
+        indices: Vec::new(),
+        shared: HashMap::new(),
+    };
+    if builder.half_width > 0. && points.len() >= 2 {
+        let dashes: Vec<f64> = stroke
+            .dashes
+            .iter()
+            .map(|dash| dash.get_length().max(0.))
+            .collect();
+        if dashes.iter().sum::<f64>() > 0. {
+            let offset = stroke.dash_offset.get_length();
+            for dash in split_into_dashes(&points, closed, &dashes, offset) {
+                builder.add_polyline(&dash, false);
+            }
+        } else {
+            builder.add_polyline(&points, closed);
+        }
+    }
+
+    let positions = builder
+        .positions
+        .iter()
+        .map(|&[x, y]| GUIPosition::from_pixels(x, y))
+        .collect();
+    (positions, builder.indices)
+}
+
+/// Lengths shorter than this, in logical pixels, are treated as nothing.
+const EPSILON: f64 = 1e-9;
+
+fn distance(a: [f64; 2], b: [f64; 2]) -> f64 {
+    (b[0] - a[0]).hypot(b[1] - a[1])
+}
+
+/// Moves from a point along a direction, scaled by the amount.
+fn offset(point: [f64; 2], direction: [f64; 2], amount: f64) -> [f64; 2] {
+    [
+        point[0] + direction[0] * amount,
+        point[1] + direction[1] * amount,
+    ]
+}
+
+/// Cuts a polyline into the pieces that lie on the dashes of the pattern,
+/// which alternates between dashes and gaps. A pattern with an odd number
+/// of lengths is repeated twice, so that dashes and gaps alternate.
+fn split_into_dashes(
+    points: &[[f64; 2]],
+    closed: bool,
+    pattern: &[f64],
+    dash_offset: f64,
+) -> Vec<Vec<[f64; 2]>> {
+    let mut pattern = pattern.to_vec();
+    if pattern.len() % 2 == 1 {
+        pattern.extend_from_within(..);
+    }
+    let total: f64 = pattern.iter().sum();
+    let mut index = 0;
+    let mut remaining = pattern[0];
+    let mut skip = dash_offset.rem_euclid(total);
+    while skip > 0. {
+        if skip < remaining {
+            remaining -= skip;
+            break;
+        }
+        skip -= remaining;
+        index = (index + 1) % pattern.len();
+        remaining = pattern[index];
+    }
+
+    let mut path = points.to_vec();
+    if closed {
+        path.push(points[0]);
+    }
+    let mut dashes = Vec::new();
+    let mut dash = vec![path[0]];
+    for window in path.windows(2) {
+        let (start, end) = (window[0], window[1]);
+        let length = distance(start, end);
+        let direction = [(end[0] - start[0]) / length, (end[1] - start[1]) / length];
+        let mut travelled = 0.;
+        while length - travelled > remaining {
+            travelled += remaining;
+            let point = offset(start, direction, travelled);
+            if index % 2 == 0 {
+                dash.push(point);
+                dashes.push(std::mem::take(&mut dash));
+            } else {
+                dash = vec![point];
+            }
+            index = (index + 1) % pattern.len();
+            remaining = pattern[index];
+        }
+        remaining -= length - travelled;
+        if index % 2 == 0 {
+            dash.push(end);
+        }
+    }
+    if index % 2 == 0 {
+        dashes.push(dash);
+    }
+    dashes.retain(|dash| dash.len() >= 2);
+    dashes
+}
+
+/// Collects the triangles of a stroke.
+struct StrokeBuilder<'a> {
+    stroke: &'a GUIStroke,
+    half_width: f64,
+    tolerance: f64,
+    positions: Vec<[f64; 2]>,
+    indices: Vec<u32>,
+    /// The index of each position, so that corners shared by neighbouring
+    /// segments and joins are only added once.
+    shared: HashMap<[u64; 2], u32>,
+}
+
+impl StrokeBuilder<'_> {
+    fn add_point(&mut self, point: [f64; 2]) -> u32 {
+        let positions = &mut self.positions;
+        *self
+            .shared
+            .entry(point.map(f64::to_bits))
+            .or_insert_with(|| {
+                positions.push(point);
+                (positions.len() - 1) as u32
+            })
+    }
+
+    /// Adds a triangle, turning it counter clockwise on screen if it isn't already.
+    fn add_triangle(&mut self, a: u32, b: u32, c: u32) {
+        let [pa, pb, pc] = [a, b, c].map(|index| self.positions[index as usize]);
+        // Counter clockwise on screen is clockwise with y going down.
+        let area = cross(pa, pb, pc);
+        if area > 0. {
+            self.indices.extend([a, c, b]);
+        } else if area < 0. {
+            self.indices.extend([a, b, c]);
+        }
+    }
+
+    /// Adds triangles fanning out from the center to each point along the rim.
+    fn add_fan(&mut self, center: [f64; 2], rim: &[[f64; 2]]) {
+        let center = self.add_point(center);
+        let rim: Vec<u32> = rim.iter().map(|&point| self.add_point(point)).collect();
+        for pair in rim.windows(2) {
+            self.add_triangle(center, pair[0], pair[1]);
+        }
+    }
+
+    /// Returns the points along the arc around center from one direction to
+    /// another, at half of the stroke's width, turning the shorter way round.
+    fn arc(&self, center: [f64; 2], from: [f64; 2], to: [f64; 2]) -> Vec<[f64; 2]> {
+        let sweep = (from[0] * to[1] - from[1] * to[0]).atan2(from[0] * to[0] + from[1] * to[1]);
+        self.arc_through(center, from, sweep)
+    }
+
+    fn arc_through(&self, center: [f64; 2], from: [f64; 2], sweep: f64) -> Vec<[f64; 2]> {
+        let segments = arcs::segment_count(self.half_width, sweep, self.tolerance);
+        (0..=segments)
+            .map(|i| {
+                let (sin, cos) = (sweep * i as f64 / segments as f64).sin_cos();
+                let direction = [from[0] * cos - from[1] * sin, from[0] * sin + from[1] * cos];
+                offset(center, direction, self.half_width)
+            })
+            .collect()
+    }
+
+    fn add_polyline(&mut self, points: &[[f64; 2]], closed: bool) {
+        let h = self.half_width;
+        let count = points.len();
+        let segment_count = if closed { count } else { count - 1 };
+        // The direction, normal and length of each segment.
+        let segments: Vec<([f64; 2], [f64; 2], f64)> = (0..segment_count)
+            .map(|i| {
+                let (start, end) = (points[i], points[(i + 1) % count]);
+                let length = distance(start, end);
+                let direction = [(end[0] - start[0]) / length, (end[1] - start[1]) / length];
+                (direction, [-direction[1], direction[0]], length)
+            })
+            .collect();
+        // The corners of each segment's quad, on the normal's side and the other side.
+        let mut starts: Vec<[[f64; 2]; 2]> = (0..segment_count)
+            .map(|i| {
+                let normal = segments[i].1;
+                [offset(points[i], normal, h), offset(points[i], normal, -h)]
+            })
+            .collect();
+        let mut ends: Vec<[[f64; 2]; 2]> = (0..segment_count)
+            .map(|i| {
+                let (point, normal) = (points[(i + 1) % count], segments[i].1);
+                [offset(point, normal, h), offset(point, normal, -h)]
+            })
+            .collect();
+
+        let joins = if closed { 0..count } else { 1..count - 1 };
+        for j in joins {
+            let incoming = (j + segment_count - 1) % segment_count;
+            let outgoing = j % segment_count;
+            let point = points[j];
+            let (direction_a, normal_a, length_a) = segments[incoming];
+            let (direction_b, normal_b, length_b) = segments[outgoing];
+            let turn = direction_a[0] * direction_b[1] - direction_a[1] * direction_b[0];
+            let dot = normal_a[0] * normal_b[0] + normal_a[1] * normal_b[1];
+            if turn.abs() < EPSILON && dot > 0. {
+                continue;
+            }
+            // The side that's on the inside of the corner.
+            let (inner, outer) = if turn > 0. { (0, 1) } else { (1, 0) };
+            let inner_sign = if turn > 0. { 1. } else { -1. };
+            let bisector = [normal_a[0] + normal_b[0], normal_a[1] + normal_b[1]];
+
+            // The inner edges meet at a point, unless the segments are too short to reach it.
+            let mut center = point;
+            if 1. + dot > EPSILON {
+                let meeting = offset(point, bisector, inner_sign * h / (1. + dot));
+                let reach = ((meeting[0] - point[0]) * direction_a[0]
+                    + (meeting[1] - point[1]) * direction_a[1])
+                    .abs();
+                if reach <= length_a.min(length_b) {
+                    ends[incoming][inner] = meeting;
+                    starts[outgoing][inner] = meeting;
+                    center = meeting;
+                }
+            }
+
+            let from = ends[incoming][outer];
+            let to = starts[outgoing][outer];
+            match self.stroke.join {
+                GUILineJoin::Round => {
+                    let outward =
+                        |normal: [f64; 2]| [-inner_sign * normal[0], -inner_sign * normal[1]];
+                    let rim = self.arc(point, outward(normal_a), outward(normal_b));
+                    self.add_fan(center, &rim);
+                }
+                GUILineJoin::Miter
+                    if 1. + dot > EPSILON
+                        && (2. / (1. + dot)).sqrt() <= self.stroke.miter_limit =>
+                {
+                    let tip = offset(point, bisector, -inner_sign * h / (1. + dot));
+                    self.add_fan(center, &[from, tip, to]);
+                }
+                _ => self.add_fan(center, &[from, to]),
+            }
+        }
+
+        if !closed {
+            let (first_direction, first_normal, _) = segments[0];
+            let (last_direction, last_normal, _) = segments[segment_count - 1];
+            let (first, last) = (points[0], points[count - 1]);
+            match self.stroke.cap {
+                GUILineCap::Butt => {}
+                GUILineCap::Square => {
+                    for corner in starts[0].iter_mut() {
+                        *corner = offset(*corner, first_direction, -h);
+                    }
+                    for corner in ends[segment_count - 1].iter_mut() {
+                        *corner = offset(*corner, last_direction, h);
+                    }
+                }
+                GUILineCap::Round => {
+                    let rim = self.arc_through(first, first_normal, std::f64::consts::PI);
+                    self.add_fan(first, &rim);
+                    let back = [-last_normal[0], -last_normal[1]];
+                    let rim = self.arc_through(last, back, std::f64::consts::PI);
+                    self.add_fan(last, &rim);
+                }
+            }
+        }
+
+        for i in 0..segment_count {
+            let [start_a, start_b] = starts[i];
+            let [end_a, end_b] = ends[i];
+            let corners = [start_a, start_b, end_b, end_a].map(|point| self.add_point(point));
+            self.add_triangle(corners[0], corners[1], corners[2]);
+            self.add_triangle(corners[0], corners[2], corners[3]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::guiproperties::guiposition::GUILength;
+    use crate::guiproperties::GUIColor;
+
+    fn positions(pixels: &[[f64; 2]]) -> Vec<GUIPosition> {
+        pixels
+            .iter()
+            .map(|&[x, y]| GUIPosition::from_pixels(x, y))
+            .collect()
+    }
+
+    /// Returns the area covered by the triangles, checking that they're all
+    /// counter clockwise on screen and so won't be culled.
+    fn covered_area(positions: &[GUIPosition], indices: &[u32]) -> f64 {
+        let points: Vec<[f64; 2]> = positions.iter().map(to_point).collect();
+        indices
+            .chunks(3)
+            .map(|triangle| {
+                let area = cross(
+                    points[triangle[0] as usize],
+                    points[triangle[1] as usize],
+                    points[triangle[2] as usize],
+                );
+                assert!(area > 0., "triangle {:?} would be culled", triangle);
+                area / 2.
+            })
+            .sum()
+    }
+
+    #[test]
+    fn concave_polygons_are_covered_exactly() {
+        // An L shape, wound clockwise on screen.
+        let outline = positions(&[
+            [0., 0.],
+            [30., 0.],
+            [30., 10.],
+            [10., 10.],
+            [10., 30.],
+            [0., 30.],
+        ]);
+        let indices = tessellate_polygon(&outline, &[]);
+        assert_eq!(indices.len(), 4 * 3);
+        assert_eq!(covered_area(&outline, &indices), 500.);
+    }
+
+    #[test]
+    fn holes_are_left_uncovered() {
+        let outline = positions(&[[0., 0.], [0., 40.], [40., 40.], [40., 0.]]);
+        let holes = vec![
+            positions(&[[5., 5.], [15., 5.], [15., 15.], [5., 15.]]),
+            positions(&[[20., 20.], [20., 35.], [35., 35.], [35., 20.]]),
+        ];
+        let indices = tessellate_polygon(&outline, &holes);
+
+        let mut all = outline.clone();
+        all.extend(holes.iter().flatten().copied());
+        assert_eq!(covered_area(&all, &indices), 1600. - 100. - 225.);
+        // Nothing covers the middle of either hole.
+        let points: Vec<[f64; 2]> = all.iter().map(to_point).collect();
+        for center in [[10., -10.], [27.5, -27.5]] {
+            assert!(!indices.chunks(3).any(|triangle| {
+                let [a, b, c] = [0, 1, 2].map(|i| points[triangle[i] as usize]);
+                in_triangle(a, b, c, center)
+            }));
+        }
+    }
+
+    fn stroke_area((positions, indices): (Vec<GUIPosition>, Vec<u32>)) -> f64 {
+        covered_area(&positions, &indices)
+    }
+
+    fn line(width: f64) -> GUIStroke {
+        GUIStroke::new(
+            GUILength::from_pixels(width),
+            GUIColor::from_rgba_u8u8u8u8(0, 0, 0, 255),
+        )
+    }
+
+    #[test]
+    fn strokes_cover_their_outlines_once() {
+        let square = positions(&[[0., 0.], [0., 20.], [20., 20.], [20., 0.]]);
+        for join in [GUILineJoin::Miter, GUILineJoin::Bevel, GUILineJoin::Round] {
+            let stroke = GUIStroke { join, ..line(4.) };
+            let area = stroke_area(tessellate_stroke(&square, true, &stroke, 0.0001));
+            // Each corner is missing a 2 by 2 square, less whatever the join fills in.
+            let corner = match join {
+                GUILineJoin::Miter => 0.,
+                GUILineJoin::Bevel => 2.,
+                GUILineJoin::Round => 4. - std::f64::consts::PI,
+            };
+            assert!(
+                (area - (24. * 24. - 16. * 16. - 4. * corner)).abs() < 0.01,
+                "{:?}",
+                join
+            );
+        }
+    }
+
+    #[test]
+    fn open_strokes_are_capped() {
+        let segment = positions(&[[0., 0.], [50., 0.], [100., 0.]]);
+        for (cap, area) in [
+            (GUILineCap::Butt, 400.),
+            (GUILineCap::Square, 400. + 2. * 8.),
+            (GUILineCap::Round, 400. + std::f64::consts::PI * 4.),
+        ] {
+            let stroke = GUIStroke { cap, ..line(4.) };
+            let covered = stroke_area(tessellate_stroke(&segment, false, &stroke, 0.001));
+            assert!((covered - area).abs() < 0.01, "{:?}", cap);
+        }
+    }
+
+    #[test]
+    fn dashed_strokes_skip_the_gaps() {
+        let segment = positions(&[[0., 0.], [100., 0.]]);
+        let stroke = GUIStroke {
+            dashes: vec![GUILength::from_pixels(10.), GUILength::from_pixels(15.)],
+            dash_offset: GUILength::from_pixels(5.),
+            ..line(2.)
+        };
+        let (positions, indices) = tessellate_stroke(&segment, false, &stroke, 0.25);
+        // Dashes run from 0 to 5, 20 to 30, 45 to 55, 70 to 80 and 95 to 100.
+        assert_eq!(indices.len(), 5 * 6);
+        assert!((stroke_area((positions.clone(), indices.clone())) - 40. * 2.).abs() < 1e-9);
+        assert!(positions.iter().all(|position| {
+            let x = position.x.get_length();
+            !(5.001..19.999).contains(&x) && !(80.001..94.999).contains(&x)
+        }));
+    }
+}
@@ -0,0 +1,542 @@
+use std::collections::HashMap;
+
+use crate::guiproperties::{GUIAlignment, GUIFont, GUIText};
+
+use crate::guiprocessing::renderer::RenderTarget;
+
+/// The width and height of the glyph atlas texture in pixels.
+pub const ATLAS_SIZE: u32 = 1024;
+
+/// Space left between glyphs in the atlas so that
+/// sampling one glyph never bleeds into its neighbour.
+const ATLAS_PADDING: u32 = 1;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct TextVertex {
+    pub position: [f32; 3],
+    pub tex_coords: [f32; 2],
+    /// The straight, not premultiplied, RGBA color of the text.
+    pub color: [f32; 4],
+}
+
+impl TextVertex {
+    pub fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<TextVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 5]>() as wgpu::BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+            ],
+        }
+    }
+}
+
+/// Where a rasterized glyph is in the atlas, in pixels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AtlasRegion {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// A single channel texture that rasterized glyphs are packed into, row by row.
+pub struct GlyphAtlas {
+    /// The coverage of each pixel, row major.
+    pub pixels: Vec<u8>,
+    /// Whether or not the pixels have changed since they were last uploaded.
+    pub dirty: bool,
+    /// Incremented whenever the atlas fills up and is emptied, since
+    /// text that was laid out before then points at glyphs that are gone.
+    pub generation: u32,
+    /// Whether the atlas is emptied to make room when it fills up.
+    /// When it isn't, glyphs that don't fit are left out.
+    pub evict: bool,
+    regions: HashMap<(char, u32), (fontdue::Metrics, Option<AtlasRegion>)>,
+    cursor_x: u32,
+    cursor_y: u32,
+    row_height: u32,
+}
+
+impl Default for GlyphAtlas {
+    fn default() -> Self {
+        Self {
+            pixels: vec![0; (ATLAS_SIZE * ATLAS_SIZE) as usize],
+            dirty: true,
+            generation: 0,
+            evict: true,
+            regions: HashMap::new(),
+            cursor_x: 0,
+            cursor_y: 0,
+            row_height: 0,
+        }
+    }
+}
+
+impl GlyphAtlas {
+    /// Gets a glyph's metrics and where it is in the atlas,
+    /// rasterizing and packing it if it isn't already there.
+    /// `px` is the size of the font in physical pixels.
+    /// There's no region for glyphs that are larger than the atlas,
+    /// or that don't fit in it when it can't be emptied.
+    pub fn glyph(
+        &mut self,
+        font: &GUIFont,
+        character: char,
+        px: f32,
+    ) -> (fontdue::Metrics, Option<AtlasRegion>) {
+        let key = (character, px.to_bits());
+        if let Some(glyph) = self.regions.get(&key) {
+            return *glyph;
+        }
+        let (metrics, coverage) = font.font().rasterize(character, px);
+        let (width, height) = (metrics.width as u32, metrics.height as u32);
+        if width + ATLAS_PADDING > ATLAS_SIZE || height + ATLAS_PADDING > ATLAS_SIZE {
+            // Emptying the atlas wouldn't make room for it.
+            self.regions.insert(key, (metrics, None));
+            return (metrics, None);
+        }
+        let region = match self.allocate(width, height) {
+            Some(region) => region,
+            None if self.evict => {
+                self.clear();
+                match self.allocate(width, height) {
+                    Some(region) => region,
+                    None => return (metrics, None),
+                }
+            }
+            None => return (metrics, None),
+        };
+        for row in 0..region.height {
+            let start = ((region.y + row) * ATLAS_SIZE + region.x) as usize;
+            let source = (row * region.width) as usize;
+            self.pixels[start..start + region.width as usize]
+                .copy_from_slice(&coverage[source..source + region.width as usize]);
+        }
+        self.dirty = true;
+        self.regions.insert(key, (metrics, Some(region)));
+        (metrics, Some(region))
+    }
+
+    /// Finds space for a glyph, starting a new row when the current one is full.
+    fn allocate(&mut self, width: u32, height: u32) -> Option<AtlasRegion> {
+        if self.cursor_x + width + ATLAS_PADDING > ATLAS_SIZE {
+            self.cursor_x = 0;
+            self.cursor_y += self.row_height;
+            self.row_height = 0;
+        }
+        if self.cursor_y + height + ATLAS_PADDING > ATLAS_SIZE || width + ATLAS_PADDING > ATLAS_SIZE
+        {
+            return None;
+        }
+        let region = AtlasRegion {
+            x: self.cursor_x,
+            y: self.cursor_y,
+            width,
+            height,
+        };
+        self.cursor_x += width + ATLAS_PADDING;
+        self.row_height = self.row_height.max(height + ATLAS_PADDING);
+        Some(region)
+    }
+
+    /// Empties the atlas.
+    pub fn clear(&mut self) {
+        self.pixels.iter_mut().for_each(|pixel| *pixel = 0);
+        self.regions.clear();
+        self.cursor_x = 0;
+        self.cursor_y = 0;
+        self.row_height = 0;
+        self.dirty = true;
+        self.generation += 1;
+    }
+}
+
+/// The pipeline that draws text, sampling glyph coverage from the atlas.
+pub struct TextPipeline {
+    pub render_pipeline: wgpu::RenderPipeline,
+    pub bind_group: wgpu::BindGroup,
+    atlas_texture: wgpu::Texture,
+}
+
+impl TextPipeline {
+    /// Makes the pipeline. The globals are bound to group 0 and the atlas to group 1.
+    pub fn new(
+        device: &wgpu::Device,
+        target: &RenderTarget,
+        globals_layout: &wgpu::BindGroupLayout,
+    ) -> Self {
+        let atlas_size = wgpu::Extent3d {
+            width: ATLAS_SIZE,
+            height: ATLAS_SIZE,
+            depth_or_array_layers: 1,
+        };
+        let atlas_texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Glyph Atlas Texture"),
+            size: atlas_size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::R8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        });
+        let atlas_view = atlas_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let atlas_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Glyph Atlas Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Text Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Text Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&atlas_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&atlas_sampler),
+                },
+            ],
+        });
+
+        let shader = device.create_shader_module(&wgpu::ShaderModuleDescriptor {
+            label: Some("Text Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../shaders/text.wgsl").into()),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Text Pipeline Layout"),
+            bind_group_layouts: &[globals_layout, &bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Text Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[TextVertex::desc()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[wgpu::ColorTargetState {
+                    format: target.format,
+                    // Glyph coverage scales the alpha, so the edges of glyphs are blended.
+                    blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                }],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: Some(wgpu::Face::Back),
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState {
+                count: target.sample_count,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+        });
+
+        Self {
+            render_pipeline,
+            bind_group,
+            atlas_texture,
+        }
+    }
+
+    /// Uploads the atlas to the gpu if it's changed since it was last uploaded.
+    pub fn upload_atlas(&self, queue: &wgpu::Queue, atlas: &mut GlyphAtlas) {
+        if !atlas.dirty {
+            return;
+        }
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &self.atlas_texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            &atlas.pixels,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: std::num::NonZeroU32::new(ATLAS_SIZE),
+                rows_per_image: std::num::NonZeroU32::new(ATLAS_SIZE),
+            },
+            wgpu::Extent3d {
+                width: ATLAS_SIZE,
+                height: ATLAS_SIZE,
+                depth_or_array_layers: 1,
+            },
+        );
+        atlas.dirty = false;
+    }
+}
+
+/// Lays out text with a font, packing the glyphs that it uses into an atlas.
+pub struct TextRenderer {
+    pub font: GUIFont,
+    pub atlas: GlyphAtlas,
+}
+
+impl TextRenderer {
+    pub fn new(font: GUIFont) -> Self {
+        Self {
+            font,
+            atlas: GlyphAtlas::default(),
+        }
+    }
+
+    /// Makes a textured quad for each glyph of each run of text.
+    /// Glyphs are rasterized at the physical pixel size so that they're crisp,
+    /// and the indices start at zero.
+    pub fn make_vertices_and_indices(
+        &mut self,
+        texts: &[GUIText],
+        scale: f64,
+    ) -> (Vec<TextVertex>, Vec<u32>) {
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        for text in texts {
+            let px = (text.font_size.get_length() * scale) as f32;
+            if px <= 0. {
+                continue;
+            }
+            let line_metrics = self.font.font().horizontal_line_metrics(px);
+            let (ascent, line_height) = match line_metrics {
+                Some(metrics) => (metrics.ascent, metrics.new_line_size),
+                None => (px, px),
+            };
+            let lines: Vec<&str> = text.text.lines().collect();
+            let text_height = line_height * lines.len() as f32;
+            let area_height = (text.size.height.get_length() * scale) as f32;
+            let area_width = (text.size.width.get_length() * scale) as f32;
+            let top = align(text.vertical_alignment, area_height, text_height);
+
+            for (line_number, line) in lines.iter().enumerate() {
+                let width = self.measure(line, px);
+                let mut pen_x = align(text.horizontal_alignment, area_width, width);
+                let baseline = (top + ascent + line_height * line_number as f32).round();
+                let mut previous = None;
+                for character in line.chars() {
+                    if let Some(previous) = previous {
+                        pen_x += self
+                            .font
+                            .font()
+                            .horizontal_kern(previous, character, px)
+                            .unwrap_or(0.);
+                    }
+                    previous = Some(character);
+                    let (metrics, region) = self.atlas.glyph(&self.font, character, px);
+                    if let Some(region) =
+                        region.filter(|region| region.width > 0 && region.height > 0)
+                    {
+                        // Snap glyphs to the physical pixel grid so that
+                        // they're sampled texel for texel.
+                        let left = (pen_x + metrics.xmin as f32).round();
+                        let glyph_top = baseline - (metrics.ymin + metrics.height as i32) as f32;
+                        let to_logical = |x: f32, y: f32| {
+                            [
+                                text.position.x.get_length() + x as f64 / scale,
+                                text.position.y.get_length() + y as f64 / scale,
+                            ]
+                        };
+                        let top_left = to_logical(left, glyph_top);
+                        let bottom_right = to_logical(
+                            left + region.width as f32,
+                            glyph_top + region.height as f32,
+                        );
+                        push_quad(
+                            &mut vertices,
+                            &mut indices,
+                            top_left,
+                            bottom_right,
+                            &region,
+                            [
+                                text.color.r as f32,
+                                text.color.g as f32,
+                                text.color.b as f32,
+                                text.color.a as f32,
+                            ],
+                        );
+                    }
+                    pen_x += metrics.advance_width;
+                }
+            }
+        }
+        (vertices, indices)
+    }
+
+    /// Measures the width of a line of text in physical pixels.
+    fn measure(&self, line: &str, px: f32) -> f32 {
+        let font = self.font.font();
+        let mut width = 0.;
+        let mut previous = None;
+        for character in line.chars() {
+            if let Some(previous) = previous {
+                width += font.horizontal_kern(previous, character, px).unwrap_or(0.);
+            }
+            width += font.metrics(character, px).advance_width;
+            previous = Some(character);
+        }
+        width
+    }
+}
+
+/// The offset that aligns content of the given length within an area.
+fn align(alignment: GUIAlignment, area: f32, content: f32) -> f32 {
+    match alignment {
+        GUIAlignment::Start => 0.,
+        GUIAlignment::Center => ((area - content) / 2.).round(),
+        GUIAlignment::End => area - content,
+    }
+}
+
+/// Adds a counter clockwise quad, in logical pixels, for a glyph.
+fn push_quad(
+    vertices: &mut Vec<TextVertex>,
+    indices: &mut Vec<u32>,
+    top_left: [f64; 2],
+    bottom_right: [f64; 2],
+    region: &AtlasRegion,
+    color: [f32; 4],
+) {
+    let position = |x: f64, y: f64| [x as f32, y as f32, 0.];
+    let atlas_size = ATLAS_SIZE as f32;
+    let (u0, v0) = (region.x as f32 / atlas_size, region.y as f32 / atlas_size);
+    let (u1, v1) = (
+        (region.x + region.width) as f32 / atlas_size,
+        (region.y + region.height) as f32 / atlas_size,
+    );
+    let offset = vertices.len() as u32;
+    vertices.extend([
+        TextVertex {
+            position: position(top_left[0], top_left[1]),
+            tex_coords: [u0, v0],
+            color,
+        },
+        TextVertex {
+            position: position(top_left[0], bottom_right[1]),
+            tex_coords: [u0, v1],
+            color,
+        },
+        TextVertex {
+            position: position(bottom_right[0], bottom_right[1]),
+            tex_coords: [u1, v1],
+            color,
+        },
+        TextVertex {
+            position: position(bottom_right[0], top_left[1]),
+            tex_coords: [u1, v0],
+            color,
+        },
+    ]);
+    indices.extend([
+        offset,
+        offset + 1,
+        offset + 2,
+        offset,
+        offset + 2,
+        offset + 3,
+    ]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allocate_packs_rows_and_reports_when_full() {
+        let mut atlas = GlyphAtlas::default();
+        let first = atlas.allocate(600, 10).unwrap();
+        let second = atlas.allocate(600, 20).unwrap();
+        let third = atlas.allocate(10, 5).unwrap();
+
+        assert_eq!((first.x, first.y), (0, 0));
+        // The second glyph doesn't fit beside the first, so it starts a new row.
+        assert_eq!((second.x, second.y), (0, 10 + ATLAS_PADDING));
+        assert_eq!(
+            (third.x, third.y),
+            (600 + ATLAS_PADDING, 10 + ATLAS_PADDING)
+        );
+        assert_eq!(atlas.allocate(10, ATLAS_SIZE), None);
+    }
+
+    #[test]
+    fn glyphs_that_dont_fit_are_left_out() {
+        let font = match GUIFont::system_default() {
+            Some(font) => font,
+            None => return,
+        };
+        let mut atlas = GlyphAtlas::default();
+        let (_, region) = atlas.glyph(&font, 'W', ATLAS_SIZE as f32 * 2.);
+        assert_eq!(region, None);
+        // Emptying the atlas wouldn't have made room, so it's left alone.
+        assert_eq!(atlas.generation, 0);
+
+        atlas.allocate(ATLAS_SIZE - ATLAS_PADDING, ATLAS_SIZE - ATLAS_PADDING);
+        atlas.evict = false;
+        assert_eq!(atlas.glyph(&font, 'W', 20.).1, None);
+        assert_eq!(atlas.generation, 0);
+        atlas.evict = true;
+        assert!(atlas.glyph(&font, 'A', 20.).1.is_some());
+        assert_eq!(atlas.generation, 1);
+    }
+
+    #[test]
+    fn align_places_content_in_area() {
+        assert_eq!(align(GUIAlignment::Start, 100., 40.), 0.);
+        assert_eq!(align(GUIAlignment::Center, 100., 40.), 30.);
+        assert_eq!(align(GUIAlignment::End, 100., 40.), 60.);
+    }
+}
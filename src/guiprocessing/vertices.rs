@@ -1,16 +1,31 @@
-use std::time::Instant;
-
-use uuid::Uuid;
-
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct Vertex {
     pub position: [f32; 3],
-    pub color: [f32; 3],
+    /// The straight, not premultiplied, RGBA color of the vertex.
+    pub color: [f32; 4],
+    /// Which of the widget's fills, counting from 1, colors the vertex instead
+    /// of `color`, or 0 to use `color`. Once the widget's geometry has been
+    /// added to a frame, it counts into the frame's fills instead.
+    pub fill: u32,
     pub id: u128,
 }
 
 impl Vertex {
+    pub fn new(position: [f32; 3], color: [f32; 4], id: u128) -> Self {
+        Self {
+            position,
+            color,
+            fill: 0,
+            id,
+        }
+    }
+
+    /// Returns the vertex colored by the fill with the given index.
+    pub fn with_fill(self, fill: u32) -> Self {
+        Self { fill, ..self }
+    }
+
     pub fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
         wgpu::VertexBufferLayout {
             array_stride: std::mem::size_of::<Vertex>() as wgpu::BufferAddress,
@@ -24,7 +39,12 @@ impl Vertex {
                 wgpu::VertexAttribute {
                     offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
                     shader_location: 1,
-                    format: wgpu::VertexFormat::Float32x3,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 7]>() as wgpu::BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Uint32,
                 },
             ],
         }
@@ -1,9 +1,15 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
 use winit::dpi::PhysicalSize;
 use winit::window::Window;
 
+use super::rects::{self, RectVertex};
+use super::text::{TextRenderer, TextVertex};
 use super::vertices::Vertex;
-use crate::guiproperties::guiposition::GUISize;
+use crate::guiproperties::guiposition::GUIPosition;
 use crate::guiproperties::guitraits::*;
+use crate::guiproperties::GUIFill;
 use crate::guiwidgets::GUIWindow;
 
 pub fn set_window_properties(window: Window, guiwindow: &GUIWindow) -> Window {
@@ -42,23 +48,286 @@ pub fn set_window_properties(window: Window, guiwindow: &GUIWindow) -> Window {
     window
 }
 
-pub fn make_vertices_and_indices(
-    parent_size: &GUISize,
-    children: &Vec<Box<dyn Family>>,
-) -> (Vec<Vertex>, Vec<u16>) {
-    let mut all_vertices: Vec<Vertex> = Vec::new();
-    let mut all_indices: Vec<u16> = Vec::new();
-    for child in children {
-        let (vertices, indices) = child.get_vertices_and_indices(&parent_size, all_vertices.len() as u16);
-        all_vertices.extend(vertices);
-        all_indices.extend(indices);
-        for child in child.get_children() {
-            let (vertices, indices) = child.get_vertices_and_indices(&parent_size, all_vertices.len() as u16);
-            all_vertices.extend(vertices);
-            all_indices.extend(indices);
+/// Tessellates the whole widget tree.
+/// Each child's position is relative to its parent's origin, and
+/// the tree is walked depth first so that every widget is drawn
+/// before its children, and siblings are drawn in the order in
+/// which they were added.
+pub fn make_vertices_and_indices(children: &[Box<dyn Family>]) -> (Vec<Vertex>, Vec<u32>) {
+    let geometry = GeometryCache::default().make_geometry(1., children, None);
+    (geometry.vertices, geometry.indices)
+}
+
+/// A range of indices that are all drawn with the same pipeline.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DrawBatch {
+    /// A range of the rounded rectangle indices.
+    Rects(Range<u32>),
+    /// A range of the shape indices.
+    Shapes(Range<u32>),
+    /// A range of the text indices.
+    Text(Range<u32>),
+}
+
+/// The tessellation of a widget tree, ready to be uploaded to the gpu.
+#[derive(Default)]
+pub struct Geometry {
+    pub vertices: Vec<Vertex>,
+    pub indices: Vec<u32>,
+    pub text_vertices: Vec<TextVertex>,
+    pub text_indices: Vec<u32>,
+    pub rect_vertices: Vec<RectVertex>,
+    pub rect_indices: Vec<u32>,
+    /// The fills that the vertices' `fill`s count into.
+    pub fills: Vec<GUIFill>,
+    /// The order in which the indices are drawn, so that a widget's rounded
+    /// rectangles are drawn under what it tessellates, its text is drawn over
+    /// both, and all of it is drawn under anything that's drawn after it.
+    pub batches: Vec<DrawBatch>,
+}
+
+impl Geometry {
+    fn append(&mut self, widget: &WidgetGeometry) {
+        let fill_offset = self.fills.len() as u32;
+        self.fills.extend_from_slice(&widget.fills);
+        let offset_fill = |fill: u32| match fill {
+            0 => 0,
+            fill => fill + fill_offset,
+        };
+        if !widget.rect_indices.is_empty() {
+            let offset = self.rect_vertices.len() as u32;
+            let start = self.rect_indices.len() as u32;
+            self.rect_vertices
+                .extend(widget.rect_vertices.iter().map(|vertex| RectVertex {
+                    fill: offset_fill(vertex.fill),
+                    ..*vertex
+                }));
+            self.rect_indices
+                .extend(widget.rect_indices.iter().map(|indice| indice + offset));
+            self.push_batch(DrawBatch::Rects(start..self.rect_indices.len() as u32));
+        }
+        if !widget.indices.is_empty() {
+            let offset = self.vertices.len() as u32;
+            let start = self.indices.len() as u32;
+            self.vertices.extend(
+                widget
+                    .vertices
+                    .iter()
+                    .map(|vertex| vertex.with_fill(offset_fill(vertex.fill))),
+            );
+            self.indices
+                .extend(widget.indices.iter().map(|indice| indice + offset));
+            self.push_batch(DrawBatch::Shapes(start..self.indices.len() as u32));
+        }
+        if !widget.text_indices.is_empty() {
+            let offset = self.text_vertices.len() as u32;
+            let start = self.text_indices.len() as u32;
+            self.text_vertices.extend_from_slice(&widget.text_vertices);
+            self.text_indices
+                .extend(widget.text_indices.iter().map(|indice| indice + offset));
+            self.push_batch(DrawBatch::Text(start..self.text_indices.len() as u32));
+        }
+    }
+
+    /// Adds a batch, merging it into the last batch if they're drawn the same way.
+    fn push_batch(&mut self, batch: DrawBatch) {
+        match (self.batches.last_mut(), batch) {
+            (Some(DrawBatch::Rects(last)), DrawBatch::Rects(next))
+            | (Some(DrawBatch::Shapes(last)), DrawBatch::Shapes(next))
+            | (Some(DrawBatch::Text(last)), DrawBatch::Text(next)) => last.end = next.end,
+            (_, batch) => self.batches.push(batch),
+        }
+    }
+}
+
+/// Everything that a single widget draws, with indices starting at zero.
+#[derive(Default)]
+struct WidgetGeometry {
+    vertices: Vec<Vertex>,
+    indices: Vec<u32>,
+    text_vertices: Vec<TextVertex>,
+    text_indices: Vec<u32>,
+    rect_vertices: Vec<RectVertex>,
+    rect_indices: Vec<u32>,
+    /// What the widget's fills are, followed by the gradients of its rounded rectangles.
+    fills: Vec<GUIFill>,
+}
+
+/// Holds on to the tessellation of each widget so that when
+/// some widgets change, only those widgets are re-tessellated.
+#[derive(Default)]
+pub struct GeometryCache {
+    /// Each widget's geometry, keyed by widget id.
+    geometry: HashMap<u128, WidgetGeometry>,
+}
+
+impl GeometryCache {
+    /// Forgets the tessellation of every dirty widget, and of all of their
+    /// descendants since their positions are relative to it, marking them
+    /// as clean. Returns whether or not any widget was dirty.
+    pub fn invalidate_dirty(&mut self, children: &mut [Box<dyn Family>]) -> bool {
+        let mut any_dirty = false;
+        for child in children.iter_mut() {
+            if child.is_dirty() {
+                self.invalidate_tree(child);
+                any_dirty = true;
+            } else if self.invalidate_dirty(child.get_children_mut()) {
+                any_dirty = true;
+            }
+        }
+        any_dirty
+    }
+
+    fn invalidate_tree(&mut self, widget: &mut Box<dyn Family>) {
+        self.geometry.remove(&widget.get_id());
+        widget.set_dirty(false);
+        for child in widget.get_children_mut().iter_mut() {
+            self.invalidate_tree(child);
+        }
+    }
+
+    /// Forgets the tessellation of every widget.
+    pub fn clear(&mut self) {
+        self.geometry.clear();
+    }
+
+    /// Tells every widget in the tree that the scale factor has changed, and
+    /// forgets their tessellation and the glyphs that were rasterized at the old
+    /// scale, so that the next make_geometry re-rasterizes them at the new one.
+    pub fn scale_factor_changed(
+        &mut self,
+        children: &mut [Box<dyn Family>],
+        scale_factor: f64,
+        text: Option<&mut TextRenderer>,
+    ) {
+        scale_factor_changed(children, scale_factor);
+        // Glyphs at the old density won't be drawn again.
+        if let Some(text) = text {
+            text.atlas.clear();
+        }
+        self.clear();
+    }
+
+    /// Tessellates the whole widget tree, as make_vertices_and_indices does,
+    /// but only tessellates the widgets that aren't already cached.
+    /// Text is only laid out if there's a text renderer to lay it out with.
+    pub fn make_geometry(
+        &mut self,
+        scale: f64,
+        children: &[Box<dyn Family>],
+        mut text: Option<&mut TextRenderer>,
+    ) -> Geometry {
+        let generation = text.as_ref().map(|text| text.atlas.generation);
+        let mut all_geometry = Geometry::default();
+        let mut geometry = HashMap::with_capacity(self.geometry.len());
+        self.append_geometry(
+            scale,
+            &GUIPosition::from_pixels(0., 0.),
+            children,
+            text.as_deref_mut(),
+            &mut geometry,
+            &mut all_geometry,
+        );
+        // Only keep the widgets that are still in the tree.
+        self.geometry = geometry;
+
+        // If the glyph atlas filled up and was emptied, then text that
+        // was laid out before that points at glyphs that are gone.
+        // Everything is laid out again into an empty atlas that isn't
+        // emptied again, so if the text still doesn't all fit then the
+        // glyphs that don't fit are left out instead of going round forever.
+        if let Some(text) = text.filter(|text| Some(text.atlas.generation) != generation) {
+            self.clear();
+            text.atlas.clear();
+            text.atlas.evict = false;
+            let mut geometry = HashMap::new();
+            all_geometry = Geometry::default();
+            self.append_geometry(
+                scale,
+                &GUIPosition::from_pixels(0., 0.),
+                children,
+                Some(&mut *text),
+                &mut geometry,
+                &mut all_geometry,
+            );
+            self.geometry = geometry;
+            text.atlas.evict = true;
+        }
+        all_geometry
+    }
+
+    fn append_geometry(
+        &mut self,
+        scale: f64,
+        parent_position: &GUIPosition,
+        children: &[Box<dyn Family>],
+        mut text: Option<&mut TextRenderer>,
+        geometry: &mut HashMap<u128, WidgetGeometry>,
+        all_geometry: &mut Geometry,
+    ) {
+        for child in children {
+            let widget_geometry = match self.geometry.remove(&child.get_id()) {
+                Some(widget_geometry) => widget_geometry,
+                None => {
+                    let (vertices, indices) = child.get_vertices_and_indices(parent_position, 0);
+                    let mut fills = child.get_fills(parent_position);
+                    let (rect_vertices, rect_indices) = rects::make_vertices_and_indices(
+                        &child.get_rects(parent_position),
+                        scale,
+                        &mut fills,
+                    );
+                    let (text_vertices, text_indices) = match text.as_deref_mut() {
+                        Some(text) => {
+                            text.make_vertices_and_indices(&child.get_text(parent_position), scale)
+                        }
+                        None => (Vec::new(), Vec::new()),
+                    };
+                    WidgetGeometry {
+                        vertices,
+                        indices,
+                        text_vertices,
+                        text_indices,
+                        rect_vertices,
+                        rect_indices,
+                        fills,
+                    }
+                }
+            };
+            all_geometry.append(&widget_geometry);
+            geometry.insert(child.get_id(), widget_geometry);
+
+            let child_position = *parent_position + child.get_position();
+            self.append_geometry(
+                scale,
+                &child_position,
+                child.get_children(),
+                text.as_deref_mut(),
+                geometry,
+                all_geometry,
+            );
+        }
+    }
+}
+
+/// Tells every widget in the tree that the scale factor has changed.
+pub fn scale_factor_changed(children: &mut [Box<dyn Family>], scale_factor: f64) {
+    for child in children.iter_mut() {
+        child.scale_factor_changed(scale_factor);
+        scale_factor_changed(child.get_children_mut(), scale_factor);
+    }
+}
+
+/// Finds the widget with the given id anywhere in the tree.
+pub fn find_child_mut(children: &mut [Box<dyn Family>], id: u128) -> Option<&mut Box<dyn Family>> {
+    for child in children.iter_mut() {
+        if child.get_id() == id {
+            return Some(child);
+        }
+        if let Some(found) = find_child_mut(child.get_children_mut(), id) {
+            return Some(found);
         }
-    };
-    (all_vertices, all_indices)
+    }
+    None
 }
 
 // pub fn set_widget_ids(mut children: Vec<Box<dyn Family>>, mut index: u16) -> (Vec<Box<dyn Family>>, u16) {
@@ -72,3 +341,197 @@ pub fn make_vertices_and_indices(
 
 //     (children, index)
 // }
+
+#[cfg(test)]
+mod tests {
+    use crate::guiprocessing::text::ATLAS_SIZE;
+    use crate::guiproperties::guiposition::GUILength;
+    use crate::guiproperties::GUIFont;
+    use crate::guiwidgets::{GUIButton, GUIButtonRendering, GUILabel};
+
+    use super::*;
+
+    fn button(x: f64, y: f64, radius: f64) -> GUIButton {
+        let mut button = GUIButton::default();
+        button.set_rendering(GUIButtonRendering::Tessellated);
+        button.set_position_from_pixels(x, y);
+        button.set_radius_from_pixels(radius);
+        button
+    }
+
+    #[test]
+    fn make_vertices_and_indices_nested_positions_are_relative() {
+        let mut grandchild = button(5., 5., 0.);
+        grandchild.id = 3;
+        let mut child = button(20., 30., 0.);
+        child.id = 2;
+        child.add_child(Box::new(grandchild));
+        let mut parent = button(100., 100., 0.);
+        parent.id = 1;
+        parent.add_child(Box::new(child));
+        let children: Vec<Box<dyn Family>> = vec![Box::new(parent)];
+
+        let (vertices, _) = make_vertices_and_indices(&children);
+
+        let expected =
+            button(125., 135., 0.).get_vertices_and_indices(&GUIPosition::from_pixels(0., 0.), 0);
+        let grandchild_vertices: Vec<&Vertex> = vertices.iter().filter(|v| v.id == 3).collect();
+        assert_eq!(grandchild_vertices.len(), expected.0.len());
+        for (actual, expected) in grandchild_vertices.iter().zip(expected.0.iter()) {
+            assert_eq!(actual.position, expected.position);
+        }
+    }
+
+    #[test]
+    fn make_vertices_and_indices_depth_first_in_add_order() {
+        let mut first = button(0., 0., 0.);
+        first.id = 1;
+        let mut nested = button(0., 0., 0.);
+        nested.id = 2;
+        let mut deeper = button(0., 0., 0.);
+        deeper.id = 3;
+        nested.add_child(Box::new(deeper));
+        first.add_child(Box::new(nested));
+        let mut second = button(0., 0., 0.);
+        second.id = 4;
+        let children: Vec<Box<dyn Family>> = vec![Box::new(first), Box::new(second)];
+
+        let (vertices, indices) = make_vertices_and_indices(&children);
+
+        let mut order: Vec<u128> = vertices.iter().map(|v| v.id).collect();
+        order.dedup();
+        assert_eq!(order, vec![1, 2, 3, 4]);
+        assert!(indices.iter().all(|i| (*i as usize) < vertices.len()));
+    }
+
+    #[test]
+    fn invalidate_dirty_re_tessellates_dirty_widgets_and_their_children() {
+        let mut child = button(10., 10., 0.);
+        child.id = 2;
+        let mut parent = button(0., 0., 0.);
+        parent.id = 1;
+        parent.add_child(Box::new(child));
+        let mut children: Vec<Box<dyn Family>> = vec![Box::new(parent)];
+        let mut cache = GeometryCache::default();
+        assert!(cache.invalidate_dirty(&mut children));
+        cache.make_geometry(1., &children, None);
+        assert!(!cache.invalidate_dirty(&mut children));
+
+        children[0].set_position_from_pixels(100., 100.);
+        assert!(cache.invalidate_dirty(&mut children));
+        let vertices = cache.make_geometry(1., &children, None).vertices;
+
+        let (expected, _) = make_vertices_and_indices(&children);
+        let positions = |vertices: &[Vertex]| -> Vec<[f32; 3]> {
+            vertices.iter().map(|vertex| vertex.position).collect()
+        };
+        assert_eq!(positions(&vertices), positions(&expected));
+        assert!(!children[0].is_dirty());
+        assert!(!children[0].get_children()[0].is_dirty());
+    }
+
+    #[test]
+    fn scale_factor_changed_re_rasterizes_glyphs_at_the_new_scale() {
+        let font = match GUIFont::system_default() {
+            Some(font) => font,
+            None => return,
+        };
+        let mut label = GUILabel::default();
+        label.set_text("H");
+        let mut parent = button(0., 0., 0.);
+        parent.add_child(Box::new(label));
+        let mut children: Vec<Box<dyn Family>> = vec![Box::new(parent)];
+        let mut cache = GeometryCache::default();
+        let mut text = TextRenderer::new(font);
+
+        // The width of the glyph in the atlas, and on the screen in logical pixels.
+        let glyph_widths = |vertices: &[TextVertex]| {
+            (
+                (vertices[2].tex_coords[0] - vertices[0].tex_coords[0]) * ATLAS_SIZE as f32,
+                vertices[2].position[0] - vertices[0].position[0],
+            )
+        };
+        let (texels, width) = glyph_widths(
+            &cache
+                .make_geometry(1., &children, Some(&mut text))
+                .text_vertices,
+        );
+
+        cache.scale_factor_changed(&mut children, 2., Some(&mut text));
+        let (scaled_texels, scaled_width) = glyph_widths(
+            &cache
+                .make_geometry(2., &children, Some(&mut text))
+                .text_vertices,
+        );
+        assert!((scaled_texels - texels * 2.).abs() <= 2.);
+        assert!((scaled_width - width).abs() <= 1.);
+    }
+
+    #[test]
+    fn frames_can_have_more_vertices_than_fit_in_sixteen_bits() {
+        let widget = WidgetGeometry {
+            vertices: vec![Vertex::new([0.; 3], [0.; 4], 0); 40_000],
+            indices: vec![0, 1, 39_999],
+            ..Default::default()
+        };
+        let mut geometry = Geometry::default();
+        geometry.append(&widget);
+        geometry.append(&widget);
+
+        assert_eq!(geometry.vertices.len(), 80_000);
+        assert_eq!(geometry.indices[3..], [40_000, 40_001, 79_999]);
+    }
+
+    #[test]
+    fn consecutive_batches_of_the_same_kind_are_merged() {
+        let mut geometry = Geometry::default();
+        geometry.push_batch(DrawBatch::Shapes(0..6));
+        geometry.push_batch(DrawBatch::Shapes(6..9));
+        geometry.push_batch(DrawBatch::Text(0..6));
+        geometry.push_batch(DrawBatch::Shapes(9..12));
+
+        assert_eq!(
+            geometry.batches,
+            vec![
+                DrawBatch::Shapes(0..9),
+                DrawBatch::Text(0..6),
+                DrawBatch::Shapes(9..12),
+            ]
+        );
+    }
+
+    #[test]
+    fn gradient_fills_are_counted_into_the_frames_fills() {
+        let gradient = GUIFill::RadialGradient {
+            center: GUIPosition::from_pixels(0., 0.),
+            radius: GUILength::from_pixels(10.),
+            stops: Vec::new(),
+        };
+        let children: Vec<Box<dyn Family>> = [Some(gradient.clone()), None, Some(gradient)]
+            .into_iter()
+            .enumerate()
+            .map(|(i, fill)| {
+                let mut child = button(100. * i as f64, 0., 0.);
+                if let Some(fill) = fill {
+                    child.set_background(fill);
+                }
+                Box::new(child) as Box<dyn Family>
+            })
+            .collect();
+        let geometry = GeometryCache::default().make_geometry(1., &children, None);
+
+        assert_eq!(geometry.fills.len(), 2);
+        // The second gradient is placed within the third button.
+        assert!(matches!(
+            geometry.fills[1],
+            GUIFill::RadialGradient { center, .. } if center == GUIPosition::from_pixels(200., 0.)
+        ));
+        let fills: Vec<u32> = geometry.vertices.iter().map(|vertex| vertex.fill).collect();
+        let per_button = fills.len() / 3;
+        assert!(fills[..per_button].iter().all(|fill| *fill == 1));
+        assert!(fills[per_button..2 * per_button]
+            .iter()
+            .all(|fill| *fill == 0));
+        assert!(fills[2 * per_button..].iter().all(|fill| *fill == 2));
+    }
+}
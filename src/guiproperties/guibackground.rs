@@ -0,0 +1,89 @@
+use super::guiposition::{GUILength, GUIRect};
+use super::{GUIColor, GUICornerRadii, GUIFill, GUIRoundedRect, GUIShadow};
+use crate::guiprocessing::vertices::Vertex;
+use crate::guiwidgets::widget_utils;
+
+/// What's painted behind a container's children, and the shadow under it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GUIBackground {
+    /// Nothing is drawn if it's fully transparent.
+    pub fill: GUIFill,
+    pub shadow: Option<GUIShadow>,
+}
+
+impl Default for GUIBackground {
+    // Returns a transparent background without a shadow.
+    fn default() -> GUIBackground {
+        GUIBackground {
+            fill: GUIFill::Solid(GUIColor {
+                r: 0.0,
+                g: 0.0,
+                b: 0.0,
+                a: 0.0,
+            }),
+            shadow: None,
+        }
+    }
+}
+
+impl GUIBackground {
+    /// Tessellates the fill over the rect, unless it's fully transparent.
+    pub fn get_vertices_and_indices(
+        &self,
+        rect: &GUIRect,
+        id: u128,
+        indice_offset: u32,
+    ) -> (Vec<Vertex>, Vec<u32>) {
+        if self.fill.is_transparent() {
+            return (Vec::new(), Vec::new());
+        }
+        widget_utils::make_rectangle(rect, &self.fill, id, indice_offset)
+    }
+
+    /// Returns what get_fills gives for a container that takes up the rect.
+    pub fn get_fills(&self, rect: &GUIRect) -> Vec<GUIFill> {
+        widget_utils::fills(&self.fill, rect)
+    }
+
+    /// Returns the shadow, if there is one, of a container
+    /// with square corners that takes up the rect.
+    pub fn get_rects(&self, rect: &GUIRect) -> Vec<GUIRoundedRect> {
+        let square = GUICornerRadii::uniform(GUILength::from_pixels(0.));
+        self.shadow
+            .iter()
+            .map(|shadow| shadow.rounded_rect(rect, &square))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::guiproperties::guiposition::GUIPosition;
+
+    #[test]
+    fn transparent_backgrounds_only_draw_their_shadow() {
+        let rect = GUIRect::from_pixels(0., 0., 20., 10.);
+        let mut background = GUIBackground::default();
+        assert!(background
+            .get_vertices_and_indices(&rect, 0, 0)
+            .0
+            .is_empty());
+        assert!(background.get_rects(&rect).is_empty());
+
+        background.shadow = Some(GUIShadow::new(
+            GUIPosition::from_pixels(0., 2.),
+            GUILength::from_pixels(4.),
+            GUILength::from_pixels(0.),
+            GUIColor::from_rgba_u8u8u8u8(0, 0, 0, 128),
+        ));
+        assert!(background
+            .get_vertices_and_indices(&rect, 0, 0)
+            .0
+            .is_empty());
+        assert_eq!(
+            background.get_rects(&rect)[0].rect,
+            GUIRect::from_pixels(0., 2., 20., 10.)
+        );
+    }
+}
@@ -1,5 +1,5 @@
 /// Represents a color and opacity.
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct GUIColor {
     pub r: f64,
     pub g: f64,
@@ -28,4 +28,15 @@ impl GUIColor {
             a: a as f64 / 255.,
         }
     }
+
+    /// Mixes this color with another color. An amount of 0 gives this
+    /// color and an amount of 1 gives the other color.
+    pub fn mix(&self, other: &GUIColor, amount: f64) -> Self {
+        Self {
+            r: self.r + (other.r - self.r) * amount,
+            g: self.g + (other.g - self.g) * amount,
+            b: self.b + (other.b - self.b) * amount,
+            a: self.a + (other.a - self.a) * amount,
+        }
+    }
 }
@@ -0,0 +1,97 @@
+use super::guiposition::{GUILength, GUISize};
+
+/// The radius of each corner of a rectangle.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GUICornerRadii {
+    pub top_left: GUILength,
+    pub top_right: GUILength,
+    pub bottom_right: GUILength,
+    pub bottom_left: GUILength,
+}
+
+impl GUICornerRadii {
+    /// Returns radii that are the same for every corner.
+    pub fn uniform(radius: GUILength) -> Self {
+        Self {
+            top_left: radius,
+            top_right: radius,
+            bottom_right: radius,
+            bottom_left: radius,
+        }
+    }
+
+    /// Returns radii in logical pixels, going clockwise from the top left corner.
+    pub fn from_pixels(top_left: f64, top_right: f64, bottom_right: f64, bottom_left: f64) -> Self {
+        Self {
+            top_left: GUILength::from_pixels(top_left),
+            top_right: GUILength::from_pixels(top_right),
+            bottom_right: GUILength::from_pixels(bottom_right),
+            bottom_left: GUILength::from_pixels(bottom_left),
+        }
+    }
+
+    /// Returns the radii scaled down, all by the same amount, just enough that
+    /// the corners along each side of a rectangle of the given size don't
+    /// overlap. Negative radii are treated as 0.
+    pub fn fit_within(&self, size: GUISize) -> Self {
+        let [top_left, top_right, bottom_right, bottom_left] = [
+            self.top_left,
+            self.top_right,
+            self.bottom_right,
+            self.bottom_left,
+        ]
+        .map(|radius| radius.get_length().max(0.));
+        let (width, height) = (size.width.get_length(), size.height.get_length());
+        let scale = [
+            (width, top_left + top_right),
+            (height, top_right + bottom_right),
+            (width, bottom_right + bottom_left),
+            (height, bottom_left + top_left),
+        ]
+        .iter()
+        .filter(|(_, radii)| *radii > 0.)
+        .map(|(side, radii)| (side.max(0.) / radii).min(1.))
+        .fold(1., f64::min);
+        Self::from_pixels(
+            top_left * scale,
+            top_right * scale,
+            bottom_right * scale,
+            bottom_left * scale,
+        )
+    }
+}
+
+/// The shape of a rectangle's corners.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum GUICornerStyle {
+    /// Each corner is a quarter of a circle.
+    #[default]
+    Round,
+    /// Each corner is cut off by a straight line.
+    Chamfer,
+    /// Each corner is a quarter of a superellipse with the given exponent.
+    /// 2 is a circle, and larger exponents get squarer, with 4 or 5 giving
+    /// a "squircle". Exponents below 1, which would bend the corners inwards,
+    /// are taken as 1, which cuts the corners off like Chamfer does.
+    Superellipse(f64),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn radii_are_scaled_down_to_fit() {
+        let radii = GUICornerRadii::from_pixels(30., 30., 0., 0.);
+        assert_eq!(radii.fit_within(GUISize::from_pixels(100., 100.)), radii);
+        assert_eq!(
+            radii.fit_within(GUISize::from_pixels(40., 100.)),
+            GUICornerRadii::from_pixels(20., 20., 0., 0.)
+        );
+        assert_eq!(
+            GUICornerRadii::uniform(GUILength::from_pixels(25.))
+                .fit_within(GUISize::from_pixels(40., 20.)),
+            GUICornerRadii::uniform(GUILength::from_pixels(10.))
+        );
+    }
+}
@@ -0,0 +1,175 @@
+use super::guiposition::{GUILength, GUIPosition, GUIRect, GUIUnit};
+use super::GUIColor;
+
+/// A color, and how far along a gradient it's reached.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GUIColorStop {
+    /// How far along the gradient the color is, from 0 at the start to 1 at the end.
+    pub offset: f64,
+    pub color: GUIColor,
+}
+
+impl GUIColorStop {
+    pub fn new(offset: f64, color: GUIColor) -> Self {
+        Self { offset, color }
+    }
+}
+
+/// What the inside of a widget is painted with.
+///
+/// The positions and lengths of gradients are relative to the widget's top
+/// left corner, in logical pixels or in percentages of the widget's size.
+/// Before the first stop and after the last, gradients carry on with the
+/// color of that stop. Colors between stops are blended as premultiplied
+/// alpha, so fading to a transparent color doesn't darken.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GUIFill {
+    Solid(GUIColor),
+    /// Blends between the stops along the line from start to end, and
+    /// is the same color all along each line perpendicular to it.
+    LinearGradient {
+        start: GUIPosition,
+        end: GUIPosition,
+        stops: Vec<GUIColorStop>,
+    },
+    /// Blends between the stops going out from the center in every direction.
+    /// A percentage radius is of the widget's diagonal divided by √2, which
+    /// is the widget's width or height when it's square.
+    RadialGradient {
+        center: GUIPosition,
+        radius: GUILength,
+        stops: Vec<GUIColorStop>,
+    },
+}
+
+impl Default for GUIFill {
+    fn default() -> Self {
+        Self::Solid(GUIColor::default())
+    }
+}
+
+impl From<GUIColor> for GUIFill {
+    fn from(color: GUIColor) -> Self {
+        Self::Solid(color)
+    }
+}
+
+impl GUIFill {
+    /// Whether or not painting with the fill would leave everything as it was.
+    pub fn is_transparent(&self) -> bool {
+        match self {
+            Self::Solid(color) => color.a <= 0.,
+            Self::LinearGradient { stops, .. } | Self::RadialGradient { stops, .. } => {
+                stops.iter().all(|stop| stop.color.a <= 0.)
+            }
+        }
+    }
+
+    /// Mixes every color of the fill with another color, as GUIColor::mix does.
+    pub fn mix(&self, other: &GUIColor, amount: f64) -> Self {
+        let mix_stops = |stops: &[GUIColorStop]| {
+            stops
+                .iter()
+                .map(|stop| GUIColorStop::new(stop.offset, stop.color.mix(other, amount)))
+                .collect()
+        };
+        match self {
+            Self::Solid(color) => Self::Solid(color.mix(other, amount)),
+            Self::LinearGradient { start, end, stops } => Self::LinearGradient {
+                start: *start,
+                end: *end,
+                stops: mix_stops(stops),
+            },
+            Self::RadialGradient {
+                center,
+                radius,
+                stops,
+            } => Self::RadialGradient {
+                center: *center,
+                radius: *radius,
+                stops: mix_stops(stops),
+            },
+        }
+    }
+
+    /// Returns the fill with its positions and lengths worked out, in
+    /// absolute logical pixels, for a widget that takes up the rect.
+    pub fn placed(&self, rect: &GUIRect) -> Self {
+        let (width, height) = (rect.size.width.get_length(), rect.size.height.get_length());
+        let place = |position: &GUIPosition| {
+            GUIPosition::from_pixels(
+                rect.left().get_length() + resolve(&position.x, width),
+                rect.top().get_length() + resolve(&position.y, height),
+            )
+        };
+        match self {
+            Self::Solid(color) => Self::Solid(*color),
+            Self::LinearGradient { start, end, stops } => Self::LinearGradient {
+                start: place(start),
+                end: place(end),
+                stops: stops.clone(),
+            },
+            Self::RadialGradient {
+                center,
+                radius,
+                stops,
+            } => Self::RadialGradient {
+                center: place(center),
+                radius: GUILength::from_pixels(resolve(
+                    radius,
+                    ((width * width + height * height) / 2.).sqrt(),
+                )),
+                stops: stops.clone(),
+            },
+        }
+    }
+}
+
+/// Returns a length in logical pixels, working out percentages of the extent.
+fn resolve(length: &GUILength, extent: f64) -> f64 {
+    match length.unit {
+        GUIUnit::Percent(percent) => extent * percent / 100.,
+        _ => length.get_length(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gradients_are_placed_within_the_widget() {
+        let black = GUIColor::from_rgba_u8u8u8u8(0, 0, 0, 255);
+        let stops = vec![GUIColorStop::new(0., black)];
+        let fill = GUIFill::LinearGradient {
+            start: GUIPosition::from_pixels(10., 0.),
+            end: GUIPosition::from_lengths(
+                GUILength::from_percent(100.),
+                GUILength::from_percent(50.),
+            ),
+            stops: stops.clone(),
+        };
+        assert_eq!(
+            fill.placed(&GUIRect::from_pixels(100., 50., 40., 20.)),
+            GUIFill::LinearGradient {
+                start: GUIPosition::from_pixels(110., 50.),
+                end: GUIPosition::from_pixels(140., 60.),
+                stops: stops.clone(),
+            }
+        );
+
+        let fill = GUIFill::RadialGradient {
+            center: GUIPosition::from_pixels(0., 0.),
+            radius: GUILength::from_percent(50.),
+            stops: stops.clone(),
+        };
+        assert_eq!(
+            fill.placed(&GUIRect::from_pixels(0., 0., 40., 40.)),
+            GUIFill::RadialGradient {
+                center: GUIPosition::from_pixels(0., 0.),
+                radius: GUILength::from_pixels(20.),
+                stops,
+            }
+        );
+    }
+}
@@ -0,0 +1,53 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::rc::Rc;
+
+/// Fonts that are commonly installed, in the order in which
+/// they're tried when no font has been given.
+const SYSTEM_FONT_PATHS: &[&str] = &[
+    "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
+    "/usr/share/fonts/TTF/DejaVuSans.ttf",
+    "/usr/share/fonts/dejavu/DejaVuSans.ttf",
+    "/usr/share/fonts/truetype/liberation/LiberationSans-Regular.ttf",
+    "/usr/share/fonts/liberation/LiberationSans-Regular.ttf",
+    "/usr/share/fonts/noto/NotoSans-Regular.ttf",
+    "/System/Library/Fonts/Supplemental/Arial.ttf",
+    "/Library/Fonts/Arial.ttf",
+    "C:\\Windows\\Fonts\\segoeui.ttf",
+    "C:\\Windows\\Fonts\\arial.ttf",
+];
+
+/// Represents a TrueType or OpenType font that text is drawn with.
+#[derive(Clone)]
+pub struct GUIFont {
+    font: Rc<fontdue::Font>,
+}
+
+impl GUIFont {
+    /// Loads a font from the contents of a TTF or OTF file.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, &'static str> {
+        let font = fontdue::Font::from_bytes(bytes, fontdue::FontSettings::default())?;
+        Ok(Self {
+            font: Rc::new(font),
+        })
+    }
+
+    /// Loads a font from a TTF or OTF file.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let bytes = fs::read(path)?;
+        Self::from_bytes(&bytes).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+    }
+
+    /// Loads the first of a handful of commonly installed fonts
+    /// that's found, or None if none of them are installed.
+    pub fn system_default() -> Option<Self> {
+        SYSTEM_FONT_PATHS
+            .iter()
+            .find_map(|path| Self::from_file(path).ok())
+    }
+
+    pub(crate) fn font(&self) -> &fontdue::Font {
+        &self.font
+    }
+}
@@ -0,0 +1,164 @@
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+/// An image made of rows of 8 bit sRGB RGBA pixels, starting at the top left.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GUIImage {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl GUIImage {
+    /// Makes an image out of its pixels, or returns an error if there
+    /// aren't exactly four bytes for every pixel.
+    pub fn from_pixels(width: u32, height: u32, pixels: Vec<u8>) -> Result<Self, &'static str> {
+        if pixels.len() != width as usize * height as usize * 4 {
+            return Err("the number of bytes doesn't match the size of the image");
+        }
+        Ok(Self {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Makes an image out of sRGB encoded pixels whose colors have been
+    /// premultiplied by their alpha, which is how they're stored in a texture
+    /// that's drawn to with premultiplied alpha blending.
+    pub(crate) fn from_premultiplied_srgb(
+        width: u32,
+        height: u32,
+        mut pixels: Vec<u8>,
+    ) -> Result<Self, &'static str> {
+        for pixel in pixels.chunks_mut(4) {
+            let alpha = pixel[3] as f32 / 255.;
+            if alpha > 0. && alpha < 1. {
+                for channel in pixel[..3].iter_mut() {
+                    *channel = linear_to_srgb(srgb_to_linear(*channel) / alpha);
+                }
+            }
+        }
+        Self::from_pixels(width, height, pixels)
+    }
+
+    /// Returns the RGBA bytes of every pixel, row by row.
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Returns the RGBA bytes of the pixel at x, y.
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let start = (y as usize * self.width as usize + x as usize) * 4;
+        let mut pixel = [0; 4];
+        pixel.copy_from_slice(&self.pixels[start..start + 4]);
+        pixel
+    }
+
+    /// Decodes an 8 bit RGB or RGBA PNG from the reader.
+    pub fn read_png<R: Read>(reader: R) -> io::Result<Self> {
+        let invalid = |error| io::Error::new(io::ErrorKind::InvalidData, error);
+        let mut decoder = png::Decoder::new(reader);
+        decoder.set_transformations(png::Transformations::EXPAND);
+        let mut reader = decoder.read_info()?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer)?;
+        buffer.truncate(info.buffer_size());
+        let pixels = match (info.color_type, info.bit_depth) {
+            (png::ColorType::Rgba, png::BitDepth::Eight) => buffer,
+            (png::ColorType::Rgb, png::BitDepth::Eight) => buffer
+                .chunks(3)
+                .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], 255])
+                .collect(),
+            _ => return Err(invalid("only 8 bit RGB and RGBA PNGs are supported")),
+        };
+        Self::from_pixels(info.width, info.height, pixels).map_err(invalid)
+    }
+
+    /// Opens a PNG file.
+    pub fn open_png<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::read_png(BufReader::new(File::open(path)?))
+    }
+
+    /// Encodes the image as a PNG into the writer.
+    pub fn write_png<W: Write>(&self, writer: W) -> io::Result<()> {
+        let mut encoder = png::Encoder::new(writer, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_source_srgb(png::SrgbRenderingIntent::Perceptual);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.pixels)?;
+        Ok(())
+    }
+
+    /// Saves the image as a PNG file.
+    pub fn save_png<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.write_png(BufWriter::new(File::create(path)?))
+    }
+}
+
+/// Converts a channel from 0 to 1 into a byte.
+pub(crate) fn to_u8(value: f32) -> u8 {
+    (value.clamp(0., 1.) * 255.).round() as u8
+}
+
+/// sRGB encodes a linear channel from 0 to 1.
+pub(crate) fn linear_to_srgb(value: f32) -> u8 {
+    let value = value.clamp(0., 1.);
+    to_u8(if value <= 0.003_130_8 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1. / 2.4) - 0.055
+    })
+}
+
+/// Decodes an sRGB encoded channel into a linear channel from 0 to 1.
+pub(crate) fn srgb_to_linear(value: u8) -> f32 {
+    let value = value as f32 / 255.;
+    if value <= 0.040_45 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pixel_reads_rows_from_the_top_left() {
+        let pixels = (0..16).collect::<Vec<u8>>();
+        let image = GUIImage::from_pixels(2, 2, pixels).unwrap();
+        assert_eq!(image.pixel(1, 0), [4, 5, 6, 7]);
+        assert_eq!(image.pixel(0, 1), [8, 9, 10, 11]);
+        assert!(GUIImage::from_pixels(2, 2, vec![0; 15]).is_err());
+    }
+
+    #[test]
+    fn premultiplied_colors_are_divided_by_their_alpha() {
+        // Half of linear red, premultiplied by an alpha of a half.
+        let half = linear_to_srgb(0.5);
+        let image = GUIImage::from_premultiplied_srgb(1, 1, vec![half, 0, 0, 128]).unwrap();
+        assert!(image.pixel(0, 0)[0] >= 254);
+        assert!((0..=255).all(|value| linear_to_srgb(srgb_to_linear(value)) == value));
+    }
+
+    #[test]
+    fn png_round_trips() {
+        let image = GUIImage::from_pixels(2, 1, vec![255, 0, 0, 255, 0, 0, 255, 128]).unwrap();
+        let mut bytes = Vec::new();
+        image.write_png(&mut bytes).unwrap();
+        assert_eq!(&bytes[..8], b"\x89PNG\r\n\x1a\n");
+        assert_eq!(GUIImage::read_png(&bytes[..]).unwrap(), image);
+    }
+}
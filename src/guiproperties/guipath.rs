@@ -0,0 +1,345 @@
+use super::guiposition::{GUILength, GUIPosition};
+use super::GUIPolygon;
+use crate::guiwidgets::widget_utils::{self, arcs};
+
+/// Which parts of a path with overlapping or nested outlines are filled.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GUIFillRule {
+    /// Points inside of an odd number of outlines are filled.
+    #[default]
+    EvenOdd,
+    /// Points that outlines wind around more times one way than the other are filled.
+    NonZero,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum GUIPathCommand {
+    MoveTo(GUIPosition),
+    LineTo(GUIPosition),
+    QuadTo(GUIPosition, GUIPosition),
+    CubicTo(GUIPosition, GUIPosition, GUIPosition),
+    ArcTo(GUIPosition, f64),
+    Close,
+}
+
+/// An outline made of straight lines and curves, which can be made up of several
+/// separate outlines. Curves are flattened into polygons as finely as the
+/// display needs, so a path should be kept around rather than its polygons.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GUIPath {
+    commands: Vec<GUIPathCommand>,
+    /// How overlapping and nested outlines are filled.
+    pub fill_rule: GUIFillRule,
+}
+
+/// An outline from a path that's been flattened.
+struct GUIContour {
+    vertices: Vec<GUIPosition>,
+    /// Twice the area of the outline, which is positive if it's clockwise on screen.
+    signed_area: f64,
+}
+
+impl GUIPath {
+    /// Returns an empty path that's filled with the given rule.
+    pub fn new(fill_rule: GUIFillRule) -> Self {
+        Self {
+            commands: Vec::new(),
+            fill_rule,
+        }
+    }
+
+    /// Starts a new outline at the point.
+    pub fn move_to(&mut self, to: GUIPosition) {
+        self.commands.push(GUIPathCommand::MoveTo(to));
+    }
+
+    /// Draws a straight line to the point.
+    pub fn line_to(&mut self, to: GUIPosition) {
+        self.commands.push(GUIPathCommand::LineTo(to));
+    }
+
+    /// Draws a quadratic Bézier curve to the point, bending towards the control point.
+    pub fn quad_to(&mut self, control: GUIPosition, to: GUIPosition) {
+        self.commands.push(GUIPathCommand::QuadTo(control, to));
+    }
+
+    /// Draws a cubic Bézier curve to the point, leaving along the first
+    /// control point and arriving along the second.
+    pub fn cubic_to(
+        &mut self,
+        first_control: GUIPosition,
+        second_control: GUIPosition,
+        to: GUIPosition,
+    ) {
+        self.commands
+            .push(GUIPathCommand::CubicTo(first_control, second_control, to));
+    }
+
+    /// Draws an arc of the circle around center that passes through the current
+    /// point, sweeping through the angle in radians. Positive angles go counter
+    /// clockwise on screen.
+    pub fn arc_to(&mut self, center: GUIPosition, sweep: f64) {
+        self.commands.push(GUIPathCommand::ArcTo(center, sweep));
+    }
+
+    /// Closes the current outline with a straight line back to where it started.
+    pub fn close(&mut self) {
+        self.commands.push(GUIPathCommand::Close);
+    }
+
+    /// Flattens the path into polygons, with curves straying no more than
+    /// arcs::TOLERANCE physical pixels at the scale factor from where they'd be.
+    /// Each polygon is an outline that's filled by the fill rule, with the
+    /// outlines within it that aren't filled as holes. Outlines that cross
+    /// each other aren't split up where they cross, so are filled as if they
+    /// didn't.
+    pub fn flatten(&self, scale_factor: f64) -> Vec<GUIPolygon> {
+        let contours: Vec<GUIContour> = self
+            .outlines(scale_factor)
+            .into_iter()
+            .filter(|(vertices, _)| vertices.len() >= 3)
+            .map(|(vertices, _)| GUIContour {
+                signed_area: signed_area(&vertices),
+                vertices,
+            })
+            .collect();
+
+        // Which contours are inside of each, going by their first vertex.
+        let containers: Vec<Vec<usize>> = contours
+            .iter()
+            .enumerate()
+            .map(|(i, contour)| {
+                (0..contours.len())
+                    .filter(|&j| {
+                        j != i
+                            && widget_utils::contains(&contours[j].vertices, &contour.vertices[0])
+                    })
+                    .collect()
+            })
+            .collect();
+
+        // A contour is an outline if the area inside of it is filled and
+        // the area around it isn't, and a hole if it's the other way around.
+        let winding = |contour: &GUIContour| match self.fill_rule {
+            GUIFillRule::EvenOdd => 1,
+            GUIFillRule::NonZero => contour.signed_area.signum() as i32,
+        };
+        let is_filled = |winding: i32| match self.fill_rule {
+            GUIFillRule::EvenOdd => winding % 2 != 0,
+            GUIFillRule::NonZero => winding != 0,
+        };
+        let mut polygons = Vec::new();
+        let mut outlines = Vec::new();
+        let mut holes = Vec::new();
+        for (i, contour) in contours.iter().enumerate() {
+            let outside: i32 = containers[i].iter().map(|&j| winding(&contours[j])).sum();
+            let inside = outside + winding(contour);
+            match (is_filled(outside), is_filled(inside)) {
+                (false, true) => {
+                    outlines.push(i);
+                    polygons.push(GUIPolygon {
+                        vertices: contour.vertices.clone(),
+                        holes: Vec::new(),
+                        indices: Vec::new(),
+                    });
+                }
+                (true, false) => holes.push(i),
+                _ => {}
+            }
+        }
+
+        // Holes are cut out of the smallest outline that they're inside of.
+        for hole in holes {
+            let outline = (0..outlines.len())
+                .filter(|&k| containers[hole].contains(&outlines[k]))
+                .min_by(|&a, &b| {
+                    let area = |k: usize| contours[outlines[k]].signed_area.abs();
+                    area(a).total_cmp(&area(b))
+                });
+            if let Some(k) = outline {
+                polygons[k].holes.push(contours[hole].vertices.clone());
+            }
+        }
+        for polygon in polygons.iter_mut() {
+            polygon.tessellate();
+        }
+        polygons
+    }
+
+    /// Flattens each of the path's outlines into a list of vertices, along
+    /// with whether or not it was closed, with curves straying no more than
+    /// arcs::TOLERANCE physical pixels at the scale factor. Outlines are
+    /// filled as if they're closed either way, but only closed ones are
+    /// stroked all of the way round.
+    pub fn outlines(&self, scale_factor: f64) -> Vec<(Vec<GUIPosition>, bool)> {
+        let tolerance = arcs::tolerance(scale_factor);
+        let mut outlines = Vec::new();
+        let mut outline: Vec<GUIPosition> = Vec::new();
+        // Where the current outline started, which is where drawing carries on from after it's closed.
+        let mut start = GUIPosition::from_pixels(0., 0.);
+        for command in self.commands.iter() {
+            let current = outline.last().copied().unwrap_or(start);
+            if outline.is_empty() {
+                outline.push(current);
+            }
+            match *command {
+                GUIPathCommand::MoveTo(to) => {
+                    finish_outline(&mut outlines, &mut outline, false);
+                    outline.push(to);
+                    start = to;
+                }
+                GUIPathCommand::LineTo(to) => outline.push(to),
+                GUIPathCommand::QuadTo(control, to) => {
+                    outline.extend(arcs::make_quadratic_curve(current, control, to, tolerance))
+                }
+                GUIPathCommand::CubicTo(first_control, second_control, to) => outline.extend(
+                    arcs::make_cubic_curve(current, first_control, second_control, to, tolerance),
+                ),
+                GUIPathCommand::ArcTo(center, sweep) => {
+                    let dx = current.x.get_length() - center.x.get_length();
+                    let dy = current.y.get_length() - center.y.get_length();
+                    let radius = GUILength::from_pixels(dx.hypot(dy));
+                    let start_angle = (-dy).atan2(dx);
+                    let arc = arcs::make_arc(center, radius, start_angle, sweep, tolerance);
+                    outline.extend(arc.into_iter().skip(1));
+                }
+                GUIPathCommand::Close => finish_outline(&mut outlines, &mut outline, true),
+            }
+        }
+        finish_outline(&mut outlines, &mut outline, false);
+        outlines
+    }
+}
+
+/// Moves the outline onto the list of outlines, unless it's only a point.
+fn finish_outline(
+    outlines: &mut Vec<(Vec<GUIPosition>, bool)>,
+    outline: &mut Vec<GUIPosition>,
+    closed: bool,
+) {
+    if outline.len() > 1 {
+        outlines.push((std::mem::take(outline), closed));
+    }
+    outline.clear();
+}
+
+/// Twice the area of an outline, which is positive if it's clockwise on screen.
+fn signed_area(vertices: &[GUIPosition]) -> f64 {
+    (0..vertices.len())
+        .map(|i| {
+            let a = vertices[i];
+            let b = vertices[(i + 1) % vertices.len()];
+            a.x.get_length() * b.y.get_length() - b.x.get_length() * a.y.get_length()
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    /// A square with its corners at the given distance from the origin,
+    /// going clockwise on screen unless it's reversed.
+    fn square(path: &mut GUIPath, half_width: f64, reversed: bool) {
+        let mut corners = [
+            [-half_width, -half_width],
+            [half_width, -half_width],
+            [half_width, half_width],
+            [-half_width, half_width],
+        ];
+        if reversed {
+            corners.reverse();
+        }
+        path.move_to(GUIPosition::from_pixels(corners[0][0], corners[0][1]));
+        for [x, y] in corners[1..].iter() {
+            path.line_to(GUIPosition::from_pixels(*x, *y));
+        }
+        path.close();
+    }
+
+    /// Returns the area that the polygons' triangles cover.
+    fn covered_area(polygons: &[GUIPolygon]) -> f64 {
+        polygons
+            .iter()
+            .map(|polygon| {
+                let positions = polygon.positions();
+                polygon
+                    .indices
+                    .chunks(3)
+                    .map(|triangle| {
+                        let triangle: Vec<GUIPosition> =
+                            triangle.iter().map(|&i| positions[i as usize]).collect();
+                        signed_area(&triangle).abs() / 2.
+                    })
+                    .sum::<f64>()
+            })
+            .sum()
+    }
+
+    #[test]
+    fn nested_outlines_are_filled_by_the_fill_rule() {
+        for (fill_rule, reversed, area) in [
+            (GUIFillRule::EvenOdd, false, 400. - 100.),
+            (GUIFillRule::EvenOdd, true, 400. - 100.),
+            (GUIFillRule::NonZero, false, 400.),
+            (GUIFillRule::NonZero, true, 400. - 100.),
+        ] {
+            let mut path = GUIPath::new(fill_rule);
+            square(&mut path, 10., false);
+            square(&mut path, 5., reversed);
+            let polygons = path.flatten(1.);
+            assert_eq!(polygons.len(), 1);
+            assert_eq!(
+                covered_area(&polygons),
+                area,
+                "{:?} {}",
+                fill_rule,
+                reversed
+            );
+        }
+    }
+
+    #[test]
+    fn curves_are_flattened_more_finely_at_higher_scale_factors() {
+        let mut path = GUIPath::new(GUIFillRule::NonZero);
+        path.move_to(GUIPosition::from_pixels(0., 0.));
+        path.line_to(GUIPosition::from_pixels(50., 0.));
+        path.arc_to(GUIPosition::from_pixels(0., 0.), -PI / 2.);
+        path.close();
+        let coarse = &path.flatten(1.)[0];
+        let fine = &path.flatten(2.)[0];
+        assert!(fine.vertices.len() > coarse.vertices.len());
+
+        // Every vertex stays on the arc, and the area approaches a quarter circle.
+        let quarter = PI * 50. * 50. / 4.;
+        for polygon in [coarse, fine] {
+            let area = covered_area(std::slice::from_ref(polygon));
+            assert!(area < quarter && quarter - area < 0.25 * 50. * PI / 2.);
+        }
+        assert_eq!(fine.vertices[1], GUIPosition::from_pixels(50., 0.));
+        assert!(fine.vertices.last().unwrap().y.get_length() > 49.99);
+    }
+
+    #[test]
+    fn bezier_curves_end_where_they_are_told_to() {
+        let from = GUIPosition::from_pixels(0., 0.);
+        let to = GUIPosition::from_pixels(30., 0.);
+        let quadratic =
+            arcs::make_quadratic_curve(from, GUIPosition::from_pixels(15., 30.), to, 0.25);
+        let cubic = arcs::make_cubic_curve(
+            from,
+            GUIPosition::from_pixels(0., 30.),
+            GUIPosition::from_pixels(30., 30.),
+            to,
+            0.25,
+        );
+        for curve in [&quadratic, &cubic] {
+            assert!(curve.len() > 2);
+            assert_eq!(*curve.last().unwrap(), to);
+        }
+        // The middle of the quadratic curve is half way to its control point.
+        let middle = quadratic[quadratic.len() / 2 - 1];
+        assert!(middle.y.get_length() <= 15. && middle.y.get_length() > 14.5);
+    }
+}
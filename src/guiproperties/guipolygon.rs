@@ -1,7 +1,68 @@
-use super::guiposition::GUIPosition;
+use super::guiposition::{GUIPosition, GUIRect};
+use crate::guiprocessing::tessellation;
+use crate::guiwidgets::widget_utils;
 
+/// An outline with any number of holes cut out of it, along with the
+/// triangles that fill it.
 #[derive(Clone)]
 pub struct GUIPolygon {
+    /// The outline of the polygon. It can be concave, and wound either way.
     pub vertices: Vec<GUIPosition>,
-    pub indices: Vec<u16>,
+    /// The outlines of the areas cut out of the polygon.
+    pub holes: Vec<Vec<GUIPosition>>,
+    /// Triangles that index into the outline's vertices followed by each hole's, in order.
+    pub indices: Vec<u32>,
+}
+
+impl GUIPolygon {
+    /// Returns a tessellated polygon with the given outline.
+    pub fn new(vertices: Vec<GUIPosition>) -> Self {
+        let mut polygon = Self {
+            vertices,
+            holes: Vec::new(),
+            indices: Vec::new(),
+        };
+        polygon.tessellate();
+        polygon
+    }
+
+    /// Cuts a hole out of the polygon, and tessellates it again.
+    pub fn add_hole(&mut self, hole: Vec<GUIPosition>) {
+        self.holes.push(hole);
+        self.tessellate();
+    }
+
+    /// Fills the indices with triangles covering the outline, less the holes.
+    /// This needs calling after the vertices or holes are changed directly.
+    pub fn tessellate(&mut self) {
+        self.indices = tessellation::tessellate_polygon(&self.vertices, &self.holes);
+    }
+
+    /// Returns the positions that the indices point into.
+    pub fn positions(&self) -> Vec<GUIPosition> {
+        let mut positions = self.vertices.clone();
+        positions.extend(self.holes.iter().flatten().copied());
+        positions
+    }
+
+    /// Returns the smallest rectangle that contains the outline.
+    pub fn bounds(&self) -> GUIRect {
+        let Some(first) = self.vertices.first() else {
+            return GUIRect::from_pixels(0., 0., 0., 0.);
+        };
+        self.vertices
+            .iter()
+            .fold(GUIRect::from_corners(*first, *first), |bounds, vertex| {
+                bounds.union(&GUIRect::from_corners(*vertex, *vertex))
+            })
+    }
+
+    /// Whether or not the point is inside of the outline and outside of every hole.
+    pub fn contains(&self, point: &GUIPosition) -> bool {
+        widget_utils::contains(&self.vertices, point)
+            && !self
+                .holes
+                .iter()
+                .any(|hole| widget_utils::contains(hole, point))
+    }
 }
@@ -1,19 +1,100 @@
-pub use guilengths::GUILength;
+pub use guilengths::{GUIAxis, GUILayoutContext, GUILength, GUIUnit};
 pub use guiposition::GUIPosition;
+pub use guirect::GUIRect;
 pub use guisize::GUISize;
 
 /// A module of structs and tools for representing linear lengths.
 pub mod guilengths {
+    use std::ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign};
+
+    use super::guisize::GUISize;
+
+    /// The size of an em when nothing sets a font size, in logical pixels.
+    pub const DEFAULT_FONT_SIZE: f64 = 16.;
+
+    /// The number of physical pixels in an inch at a scale factor of 1.
+    /// Displays don't report their physical size, so this is assumed to be
+    /// the same as on the web unless the window is given the real value.
+    pub const DEFAULT_PIXELS_PER_INCH: f64 = 96.;
+
+    /// What a length is measured in. Every unit other than pixels is relative
+    /// to something, and is turned into logical pixels during layout.
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum GUIUnit {
+        /// Logical pixels.
+        Pixels,
+        /// A percentage of the parent's width or height, whichever
+        /// is along the same axis as the length.
+        Percent(f64),
+        /// A percentage of the window's width.
+        ViewportWidth(f64),
+        /// A percentage of the window's height.
+        ViewportHeight(f64),
+        /// A multiple of the current font size.
+        Em(f64),
+        /// Physical millimetres.
+        Millimetres(f64),
+        /// Physical points, 72 to an inch.
+        Points(f64),
+    }
+
+    /// Whether a length is a horizontal or a vertical one.
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum GUIAxis {
+        Horizontal,
+        Vertical,
+    }
+
+    /// What relative lengths are resolved against.
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct GUILayoutContext {
+        /// The size of the area that the widget was given, in logical pixels.
+        pub parent_size: GUISize,
+        /// The size of the window, in logical pixels.
+        pub viewport_size: GUISize,
+        /// The size of an em, in logical pixels.
+        pub font_size: f64,
+        /// The number of physical pixels in a logical pixel.
+        pub scale_factor: f64,
+        /// The number of physical pixels in an inch.
+        pub pixels_per_inch: f64,
+    }
+
+    impl GUILayoutContext {
+        /// Returns the context for the children of a window of the given size.
+        pub fn new(viewport_size: GUISize, scale_factor: f64) -> Self {
+            Self {
+                parent_size: viewport_size,
+                viewport_size,
+                font_size: DEFAULT_FONT_SIZE,
+                scale_factor,
+                pixels_per_inch: DEFAULT_PIXELS_PER_INCH * scale_factor,
+            }
+        }
+
+        /// Returns the context for widgets inside of an area of the given size,
+        /// and that use the given font size if one is given.
+        pub fn with_parent(&self, parent_size: GUISize, font_size: Option<f64>) -> Self {
+            Self {
+                parent_size,
+                font_size: font_size.unwrap_or(self.font_size),
+                ..*self
+            }
+        }
+    }
 
     /// Represents a linear dimension (height or width).
     #[derive(Clone, Copy, Debug)]
     pub struct GUILength {
+        /// The length in logical pixels. For relative units, this is
+        /// what the length was last resolved to.
         pub length: f64,
+        pub unit: GUIUnit,
     }
 
     impl Default for GUILength {
         fn default() -> Self {
-            Self { length: 0. }
+            Self::from_pixels(0.)
         }
     }
 
@@ -23,14 +104,13 @@ pub mod guilengths {
             let multiplyer = (10f64).powf(decimal_places);
 
             (self.length * multiplyer).round() == (other.length * multiplyer).round()
+                && self.unit == other.unit
         }
     }
 
     impl GUILength {
         pub fn negative(&self) -> Self {
-            Self {
-                length: -self.length,
-            }
+            -*self
         }
 
         pub fn get_length(&self) -> f64 {
@@ -42,44 +122,155 @@ pub mod guilengths {
         }
 
         pub fn from_pixels(pixels: f64) -> Self {
-            Self { length: pixels }
+            Self {
+                length: pixels,
+                unit: GUIUnit::Pixels,
+            }
         }
 
         pub fn from_physical_pixels(pixels: f64, scale: &f64) -> Self {
+            Self::from_pixels(pixels / scale)
+        }
+
+        /// A length that's unresolved until it's laid out.
+        fn from_unit(unit: GUIUnit) -> Self {
+            Self { length: 0., unit }
+        }
+
+        /// A percentage of the parent's width or height.
+        pub fn from_percent(percent: f64) -> Self {
+            Self::from_unit(GUIUnit::Percent(percent))
+        }
+
+        /// A percentage of the window's width.
+        pub fn from_viewport_width(percent: f64) -> Self {
+            Self::from_unit(GUIUnit::ViewportWidth(percent))
+        }
+
+        /// A percentage of the window's height.
+        pub fn from_viewport_height(percent: f64) -> Self {
+            Self::from_unit(GUIUnit::ViewportHeight(percent))
+        }
+
+        /// A multiple of the current font size.
+        pub fn from_em(em: f64) -> Self {
+            Self::from_unit(GUIUnit::Em(em))
+        }
+
+        pub fn from_millimetres(millimetres: f64) -> Self {
+            Self::from_unit(GUIUnit::Millimetres(millimetres))
+        }
+
+        pub fn from_points(points: f64) -> Self {
+            Self::from_unit(GUIUnit::Points(points))
+        }
+
+        /// Returns the length with its logical pixels worked out from its unit.
+        /// The unit is kept, so the length can be resolved again when the
+        /// context changes.
+        pub fn resolve(&self, axis: GUIAxis, context: &GUILayoutContext) -> Self {
+            let along = |size: &GUISize| match axis {
+                GUIAxis::Horizontal => size.width.length,
+                GUIAxis::Vertical => size.height.length,
+            };
+            let inches_to_pixels = context.pixels_per_inch / context.scale_factor;
+            let length = match self.unit {
+                GUIUnit::Pixels => self.length,
+                GUIUnit::Percent(percent) => along(&context.parent_size) * percent / 100.,
+                GUIUnit::ViewportWidth(percent) => {
+                    context.viewport_size.width.length * percent / 100.
+                }
+                GUIUnit::ViewportHeight(percent) => {
+                    context.viewport_size.height.length * percent / 100.
+                }
+                GUIUnit::Em(em) => context.font_size * em,
+                GUIUnit::Millimetres(millimetres) => millimetres / 25.4 * inches_to_pixels,
+                GUIUnit::Points(points) => points / 72. * inches_to_pixels,
+            };
             Self {
-                length: pixels / scale,
+                length,
+                unit: self.unit,
             }
         }
 
         pub fn add(&self, other: &GUILength) -> Self {
-            Self {
-                length: self.length + other.length,
-            }
+            *self + *other
         }
 
         pub fn subtract(&self, other: &GUILength) -> Self {
-            Self {
-                length: self.length - other.length,
-            }
+            *self - *other
         }
 
         pub fn multiply(&self, other: &GUILength) -> Self {
-            Self {
-                length: self.length * other.length,
-            }
+            Self::from_pixels(self.length * other.length)
         }
 
         pub fn devide_by(&self, other: &GUILength) -> Self {
-            Self {
-                length: self.length / other.length,
-            }
+            Self::from_pixels(self.length / other.length)
+        }
+    }
+
+    // Arithmetic is done on the resolved logical pixels,
+    // so the results are always in pixels.
+
+    impl Add for GUILength {
+        type Output = GUILength;
+
+        fn add(self, other: GUILength) -> GUILength {
+            GUILength::from_pixels(self.length + other.length)
+        }
+    }
+
+    impl Sub for GUILength {
+        type Output = GUILength;
+
+        fn sub(self, other: GUILength) -> GUILength {
+            GUILength::from_pixels(self.length - other.length)
+        }
+    }
+
+    impl Neg for GUILength {
+        type Output = GUILength;
+
+        fn neg(self) -> GUILength {
+            GUILength::from_pixels(-self.length)
+        }
+    }
+
+    impl Mul<f64> for GUILength {
+        type Output = GUILength;
+
+        fn mul(self, factor: f64) -> GUILength {
+            GUILength::from_pixels(self.length * factor)
+        }
+    }
+
+    impl Div<f64> for GUILength {
+        type Output = GUILength;
+
+        fn div(self, divisor: f64) -> GUILength {
+            GUILength::from_pixels(self.length / divisor)
+        }
+    }
+
+    impl AddAssign for GUILength {
+        fn add_assign(&mut self, other: GUILength) {
+            *self = *self + other;
+        }
+    }
+
+    impl SubAssign for GUILength {
+        fn sub_assign(&mut self, other: GUILength) {
+            *self = *self - other;
         }
     }
 }
 
 /// A module of structs and tools for representing areas, width and height.
 mod guisize {
-    use super::guilengths::GUILength;
+    use std::ops::{Add, Div, Mul, Sub};
+
+    use super::guilengths::{GUIAxis, GUILayoutContext, GUILength};
 
     /// Represents an area (width and height)
     #[derive(Copy, Clone, Debug)]
@@ -88,6 +279,12 @@ mod guisize {
         pub height: GUILength,
     }
 
+    impl PartialEq for GUISize {
+        fn eq(&self, other: &GUISize) -> bool {
+            self.width == other.width && self.height == other.height
+        }
+    }
+
     impl Default for GUISize {
         fn default() -> GUISize {
             GUISize::from_pixels(500., 500.)
@@ -108,15 +305,59 @@ mod guisize {
 
         pub fn from_physical_pixels(width: f64, height: f64, scale: &f64) -> Self {
             Self {
-                width: GUILength::from_physical_pixels(width, &scale),
-                height: GUILength::from_physical_pixels(height, &scale),
+                width: GUILength::from_physical_pixels(width, scale),
+                height: GUILength::from_physical_pixels(height, scale),
             }
         }
+
+        /// Returns the size with any relative lengths resolved against the context.
+        pub fn resolve(&self, context: &GUILayoutContext) -> Self {
+            Self {
+                width: self.width.resolve(GUIAxis::Horizontal, context),
+                height: self.height.resolve(GUIAxis::Vertical, context),
+            }
+        }
+    }
+
+    impl Add for GUISize {
+        type Output = GUISize;
+
+        fn add(self, other: GUISize) -> GUISize {
+            GUISize::from_lengths(self.width + other.width, self.height + other.height)
+        }
+    }
+
+    impl Sub for GUISize {
+        type Output = GUISize;
+
+        fn sub(self, other: GUISize) -> GUISize {
+            GUISize::from_lengths(self.width - other.width, self.height - other.height)
+        }
+    }
+
+    impl Mul<f64> for GUISize {
+        type Output = GUISize;
+
+        fn mul(self, factor: f64) -> GUISize {
+            GUISize::from_lengths(self.width * factor, self.height * factor)
+        }
+    }
+
+    impl Div<f64> for GUISize {
+        type Output = GUISize;
+
+        fn div(self, divisor: f64) -> GUISize {
+            GUISize::from_lengths(self.width / divisor, self.height / divisor)
+        }
     }
 }
 
+#[allow(clippy::module_inception)]
 mod guiposition {
-    use super::guilengths::GUILength;
+    use std::ops::{Add, AddAssign, Neg, Sub, SubAssign};
+
+    use super::guilengths::{GUIAxis, GUILayoutContext, GUILength};
+    use super::guisize::GUISize;
 
     #[derive(Clone, Copy, Debug)]
     pub struct GUIPosition {
@@ -144,9 +385,339 @@ mod guiposition {
 
         pub fn from_physical_pixels(x: f64, y: f64, scale: &f64) -> Self {
             Self {
-                x: GUILength::from_physical_pixels(x, &scale),
-                y: GUILength::from_physical_pixels(y, &scale),
+                x: GUILength::from_physical_pixels(x, scale),
+                y: GUILength::from_physical_pixels(y, scale),
+            }
+        }
+
+        /// Returns the position with any relative lengths resolved against the context.
+        pub fn resolve(&self, context: &GUILayoutContext) -> Self {
+            Self {
+                x: self.x.resolve(GUIAxis::Horizontal, context),
+                y: self.y.resolve(GUIAxis::Vertical, context),
+            }
+        }
+    }
+
+    impl Add for GUIPosition {
+        type Output = GUIPosition;
+
+        fn add(self, other: GUIPosition) -> GUIPosition {
+            GUIPosition::from_lengths(self.x + other.x, self.y + other.y)
+        }
+    }
+
+    impl Sub for GUIPosition {
+        type Output = GUIPosition;
+
+        fn sub(self, other: GUIPosition) -> GUIPosition {
+            GUIPosition::from_lengths(self.x - other.x, self.y - other.y)
+        }
+    }
+
+    impl Neg for GUIPosition {
+        type Output = GUIPosition;
+
+        fn neg(self) -> GUIPosition {
+            GUIPosition::from_lengths(-self.x, -self.y)
+        }
+    }
+
+    /// Moves a position right by the width and down by the height.
+    impl Add<GUISize> for GUIPosition {
+        type Output = GUIPosition;
+
+        fn add(self, size: GUISize) -> GUIPosition {
+            GUIPosition::from_lengths(self.x + size.width, self.y + size.height)
+        }
+    }
+
+    /// Moves a position left by the width and up by the height.
+    impl Sub<GUISize> for GUIPosition {
+        type Output = GUIPosition;
+
+        fn sub(self, size: GUISize) -> GUIPosition {
+            GUIPosition::from_lengths(self.x - size.width, self.y - size.height)
+        }
+    }
+
+    impl AddAssign for GUIPosition {
+        fn add_assign(&mut self, other: GUIPosition) {
+            *self = *self + other;
+        }
+    }
+
+    impl SubAssign for GUIPosition {
+        fn sub_assign(&mut self, other: GUIPosition) {
+            *self = *self - other;
+        }
+    }
+}
+
+mod guirect {
+    use super::guilengths::GUILength;
+    use super::guiposition::GUIPosition;
+    use super::guisize::GUISize;
+
+    /// Represents a rectangle by its top left corner and its size.
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct GUIRect {
+        pub position: GUIPosition,
+        pub size: GUISize,
+    }
+
+    impl GUIRect {
+        pub fn new(position: GUIPosition, size: GUISize) -> Self {
+            Self { position, size }
+        }
+
+        pub fn from_pixels(x: f64, y: f64, width: f64, height: f64) -> Self {
+            Self::new(
+                GUIPosition::from_pixels(x, y),
+                GUISize::from_pixels(width, height),
+            )
+        }
+
+        /// Returns the smallest rectangle with both corners in it.
+        pub fn from_corners(a: GUIPosition, b: GUIPosition) -> Self {
+            let (left, right) = min_max(a.x, b.x);
+            let (top, bottom) = min_max(a.y, b.y);
+            Self::new(
+                GUIPosition::from_lengths(left, top),
+                GUISize::from_lengths(right - left, bottom - top),
+            )
+        }
+
+        pub fn left(&self) -> GUILength {
+            self.position.x
+        }
+
+        pub fn top(&self) -> GUILength {
+            self.position.y
+        }
+
+        pub fn right(&self) -> GUILength {
+            self.position.x + self.size.width
+        }
+
+        pub fn bottom(&self) -> GUILength {
+            self.position.y + self.size.height
+        }
+
+        pub fn center(&self) -> GUIPosition {
+            self.position + self.size / 2.
+        }
+
+        /// Returns the corners in counter clockwise order on screen,
+        /// starting from the top left.
+        pub fn corners(&self) -> [GUIPosition; 4] {
+            [
+                GUIPosition::from_lengths(self.left(), self.top()),
+                GUIPosition::from_lengths(self.left(), self.bottom()),
+                GUIPosition::from_lengths(self.right(), self.bottom()),
+                GUIPosition::from_lengths(self.right(), self.top()),
+            ]
+        }
+
+        /// Whether or not the rectangle has no area.
+        pub fn is_empty(&self) -> bool {
+            self.size.width.get_length() <= 0. || self.size.height.get_length() <= 0.
+        }
+
+        /// Whether or not a point lies within the rectangle. Points on the left
+        /// and top edges are inside, and points on the right and bottom edges
+        /// aren't, so that rectangles that share an edge don't overlap.
+        pub fn contains(&self, point: &GUIPosition) -> bool {
+            let (x, y) = (point.x.get_length(), point.y.get_length());
+            self.left().get_length() <= x
+                && x < self.right().get_length()
+                && self.top().get_length() <= y
+                && y < self.bottom().get_length()
+        }
+
+        /// Returns the area that's in both rectangles, or None if they don't overlap.
+        pub fn intersect(&self, other: &GUIRect) -> Option<GUIRect> {
+            let rect = Self::from_edges(
+                max(self.left(), other.left()),
+                max(self.top(), other.top()),
+                min(self.right(), other.right()),
+                min(self.bottom(), other.bottom()),
+            );
+            if rect.is_empty() {
+                None
+            } else {
+                Some(rect)
             }
         }
+
+        /// Returns the smallest rectangle that both rectangles fit in.
+        pub fn union(&self, other: &GUIRect) -> GUIRect {
+            Self::from_edges(
+                min(self.left(), other.left()),
+                min(self.top(), other.top()),
+                max(self.right(), other.right()),
+                max(self.bottom(), other.bottom()),
+            )
+        }
+
+        /// Returns the rectangle shrunk by the amount on every side, or grown if
+        /// it's negative. It won't shrink past nothing, staying centered instead.
+        pub fn inset(&self, amount: GUILength) -> GUIRect {
+            let amount = amount.get_length();
+            let inset = |length: GUILength| amount.min(length.get_length() / 2.);
+            let (dx, dy) = (inset(self.size.width), inset(self.size.height));
+            Self::from_pixels(
+                self.left().get_length() + dx,
+                self.top().get_length() + dy,
+                self.size.width.get_length() - 2. * dx,
+                self.size.height.get_length() - 2. * dy,
+            )
+        }
+
+        /// Returns the rectangle moved by the offset.
+        pub fn translate(&self, offset: GUIPosition) -> GUIRect {
+            Self::new(self.position + offset, self.size)
+        }
+
+        fn from_edges(
+            left: GUILength,
+            top: GUILength,
+            right: GUILength,
+            bottom: GUILength,
+        ) -> Self {
+            Self::new(
+                GUIPosition::from_lengths(left, top),
+                GUISize::from_lengths(
+                    GUILength::from_pixels((right - left).get_length().max(0.)),
+                    GUILength::from_pixels((bottom - top).get_length().max(0.)),
+                ),
+            )
+        }
+    }
+
+    fn min(a: GUILength, b: GUILength) -> GUILength {
+        GUILength::from_pixels(a.get_length().min(b.get_length()))
+    }
+
+    fn max(a: GUILength, b: GUILength) -> GUILength {
+        GUILength::from_pixels(a.get_length().max(b.get_length()))
+    }
+
+    fn min_max(a: GUILength, b: GUILength) -> (GUILength, GUILength) {
+        (min(a, b), max(a, b))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relative_lengths_resolve_against_the_context() {
+        let window = GUILayoutContext::new(GUISize::from_pixels(800., 600.), 2.);
+        let context = window.with_parent(GUISize::from_pixels(200., 100.), Some(20.));
+        let resolve = |length: GUILength, axis| length.resolve(axis, &context).get_length();
+
+        assert_eq!(resolve(GUILength::from_pixels(7.), GUIAxis::Vertical), 7.);
+        assert_eq!(
+            resolve(GUILength::from_percent(50.), GUIAxis::Horizontal),
+            100.
+        );
+        assert_eq!(
+            resolve(GUILength::from_percent(50.), GUIAxis::Vertical),
+            50.
+        );
+        assert_eq!(
+            resolve(GUILength::from_viewport_width(10.), GUIAxis::Vertical),
+            80.
+        );
+        assert_eq!(
+            resolve(GUILength::from_viewport_height(10.), GUIAxis::Horizontal),
+            60.
+        );
+        assert_eq!(resolve(GUILength::from_em(2.), GUIAxis::Vertical), 40.);
+        assert_eq!(
+            resolve(GUILength::from_millimetres(25.4), GUIAxis::Horizontal),
+            96.
+        );
+        assert_eq!(
+            resolve(GUILength::from_points(72.), GUIAxis::Horizontal),
+            96.
+        );
+
+        // A display with twice as many physical pixels per inch as was assumed.
+        let dense = GUILayoutContext {
+            pixels_per_inch: 384.,
+            ..context
+        };
+        let inch = GUILength::from_points(72.).resolve(GUIAxis::Horizontal, &dense);
+        assert_eq!(inch.get_length(), 192.);
+    }
+
+    #[test]
+    fn resolving_keeps_the_unit() {
+        let size = GUISize::from_lengths(GUILength::from_percent(50.), GUILength::from_em(2.));
+        let small = GUILayoutContext::new(GUISize::from_pixels(100., 100.), 1.);
+        let large = GUILayoutContext::new(GUISize::from_pixels(300., 100.), 1.);
+
+        let resolved = size.resolve(&small).resolve(&large);
+        assert_eq!(resolved.width.get_length(), 150.);
+        assert_eq!(resolved.height.get_length(), 32.);
+        assert_ne!(resolved, GUISize::from_pixels(150., 32.));
+    }
+
+    #[test]
+    fn lengths_positions_and_sizes_support_arithmetic() {
+        let mut length = GUILength::from_pixels(10.) + GUILength::from_pixels(5.);
+        length -= GUILength::from_pixels(3.);
+        assert_eq!(length * 2. / 4., GUILength::from_pixels(6.));
+        assert_eq!(-length, GUILength::from_pixels(-12.));
+
+        let position = GUIPosition::from_pixels(10., 20.) - GUIPosition::from_pixels(5., 5.);
+        let size = GUISize::from_pixels(30., 40.) * 0.5;
+        assert_eq!(position + size, GUIPosition::from_pixels(20., 35.));
+        assert_eq!(size - size / 2., GUISize::from_pixels(7.5, 10.));
+    }
+
+    #[test]
+    fn rects_contain_intersect_and_union() {
+        let a = GUIRect::from_pixels(0., 0., 100., 50.);
+        let b = GUIRect::from_pixels(50., 25., 100., 50.);
+
+        assert!(a.contains(&GUIPosition::from_pixels(0., 0.)));
+        assert!(!a.contains(&GUIPosition::from_pixels(100., 10.)));
+        assert_eq!(
+            a.intersect(&b),
+            Some(GUIRect::from_pixels(50., 25., 50., 25.))
+        );
+        assert_eq!(
+            a.intersect(&a.translate(GUIPosition::from_pixels(100., 0.))),
+            None
+        );
+        assert_eq!(a.union(&b), GUIRect::from_pixels(0., 0., 150., 75.));
+        assert_eq!(
+            GUIRect::from_corners(
+                GUIPosition::from_pixels(150., 75.),
+                GUIPosition::from_pixels(50., 25.)
+            ),
+            b
+        );
+        assert_eq!(b.center(), GUIPosition::from_pixels(100., 50.));
+    }
+
+    #[test]
+    fn rects_inset_without_turning_inside_out() {
+        let rect = GUIRect::from_pixels(0., 0., 100., 50.);
+        assert_eq!(
+            rect.inset(GUILength::from_pixels(10.)),
+            GUIRect::from_pixels(10., 10., 80., 30.)
+        );
+        assert_eq!(
+            rect.inset(GUILength::from_pixels(-10.)),
+            GUIRect::from_pixels(-10., -10., 120., 70.)
+        );
+        let collapsed = rect.inset(GUILength::from_pixels(40.));
+        assert_eq!(collapsed, GUIRect::from_pixels(40., 25., 20., 0.));
+        assert!(collapsed.is_empty());
     }
 }
@@ -0,0 +1,22 @@
+use super::guiposition::{GUILength, GUIRect};
+use super::{GUIColor, GUICornerRadii, GUIFill};
+
+/// A rectangle with rounded corners and a border, drawn as a single quad.
+/// Its fragments work out how much of them is inside of the rectangle, and
+/// of its border, from a signed distance function, so its edges are anti
+/// aliased however big its corners are.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GUIRoundedRect {
+    /// Where the rectangle is, in absolute logical pixels.
+    pub rect: GUIRect,
+    /// They're scaled down to fit if they're too big for the rectangle.
+    pub corner_radii: GUICornerRadii,
+    /// What the rectangle is filled with. Gradients are placed within the rectangle.
+    pub background: GUIFill,
+    /// Width of the border drawn over the fill, just inside of the rectangle's edge.
+    pub border_width: GUILength,
+    pub border_color: GUIColor,
+    /// The standard deviation of the Gaussian blur that the rectangle's edge
+    /// is blurred by, or 0 for a sharp edge. Blurred rectangles have no border.
+    pub blur: GUILength,
+}
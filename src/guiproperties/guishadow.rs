@@ -0,0 +1,87 @@
+use super::guiposition::{GUILength, GUIPosition, GUIRect};
+use super::{GUIColor, GUICornerRadii, GUIFill, GUIRoundedRect};
+
+/// A blurred copy of a widget's rectangle that's drawn under the widget.
+/// It's a drop shadow when it's offset, and an outer glow when it isn't and
+/// has a light color. Like the widget's rectangle, it shows through the
+/// widget if the widget is translucent.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GUIShadow {
+    /// How far the shadow is moved from the widget.
+    pub offset: GUIPosition,
+    /// How far the shadow's edge is blurred. As in CSS, the standard
+    /// deviation of the blur is half of this.
+    pub blur_radius: GUILength,
+    /// How much bigger than the widget the shadow is on every side,
+    /// before it's blurred. It's smaller if this is negative.
+    pub spread: GUILength,
+    pub color: GUIColor,
+}
+
+impl GUIShadow {
+    pub fn new(
+        offset: GUIPosition,
+        blur_radius: GUILength,
+        spread: GUILength,
+        color: GUIColor,
+    ) -> Self {
+        Self {
+            offset,
+            blur_radius,
+            spread,
+            color,
+        }
+    }
+
+    /// Returns the shadow of a rectangle with the given corners, all in
+    /// absolute logical pixels. The corners grow along with the spread,
+    /// except for square ones, which stay square.
+    pub fn rounded_rect(&self, rect: &GUIRect, corner_radii: &GUICornerRadii) -> GUIRoundedRect {
+        let spread = self.spread.get_length();
+        let radii = [
+            corner_radii.top_left,
+            corner_radii.top_right,
+            corner_radii.bottom_right,
+            corner_radii.bottom_left,
+        ]
+        .map(|radius| match radius.get_length() {
+            radius if radius > 0. => (radius + spread).max(0.),
+            _ => 0.,
+        });
+        GUIRoundedRect {
+            rect: rect.translate(self.offset).inset(-self.spread),
+            corner_radii: GUICornerRadii::from_pixels(radii[0], radii[1], radii[2], radii[3]),
+            background: GUIFill::Solid(self.color),
+            border_width: GUILength::from_pixels(0.),
+            border_color: self.color,
+            blur: GUILength::from_pixels(self.blur_radius.get_length().max(0.) / 2.),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shadows_are_offset_and_spread() {
+        let black = GUIColor::from_rgba_u8u8u8u8(0, 0, 0, 128);
+        let shadow = GUIShadow::new(
+            GUIPosition::from_pixels(0., 4.),
+            GUILength::from_pixels(8.),
+            GUILength::from_pixels(2.),
+            black,
+        );
+        let rounded_rect = shadow.rounded_rect(
+            &GUIRect::from_pixels(10., 10., 40., 20.),
+            &GUICornerRadii::from_pixels(5., 0., 5., 0.),
+        );
+        assert_eq!(rounded_rect.rect, GUIRect::from_pixels(8., 12., 44., 24.));
+        assert_eq!(
+            rounded_rect.corner_radii,
+            GUICornerRadii::from_pixels(7., 0., 7., 0.)
+        );
+        assert_eq!(rounded_rect.blur, GUILength::from_pixels(4.));
+        assert_eq!(rounded_rect.background, GUIFill::Solid(black));
+    }
+}
@@ -0,0 +1,60 @@
+use super::guiposition::GUILength;
+use super::GUIColor;
+
+/// How the outside of a corner in a stroked outline is filled in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GUILineJoin {
+    /// The edges are extended until they meet in a point, unless that's
+    /// further than the miter limit allows, when the corner is beveled instead.
+    #[default]
+    Miter,
+    /// The corner is rounded off by a circle as wide as the stroke.
+    Round,
+    /// The corner is cut off by a straight line.
+    Bevel,
+}
+
+/// How the ends of an open outline, or of the dashes of any outline, are drawn.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GUILineCap {
+    /// The stroke stops square at the end.
+    #[default]
+    Butt,
+    /// The end is capped by a half circle as wide as the stroke.
+    Round,
+    /// The stroke carries on square for half of its width past the end.
+    Square,
+}
+
+/// How an outline is stroked.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GUIStroke {
+    /// The width of the stroke, which is centered on the outline.
+    pub width: GUILength,
+    pub color: GUIColor,
+    pub join: GUILineJoin,
+    pub cap: GUILineCap,
+    /// How many times longer than half of the stroke's width a miter can
+    /// stick out from a corner before it's beveled.
+    pub miter_limit: f64,
+    /// The lengths of the dashes and the gaps between them, alternately.
+    /// The stroke is solid if this is empty.
+    pub dashes: Vec<GUILength>,
+    /// How far into the dash pattern the stroke starts.
+    pub dash_offset: GUILength,
+}
+
+impl GUIStroke {
+    /// Returns a solid stroke with mitered corners and butt ends.
+    pub fn new(width: GUILength, color: GUIColor) -> Self {
+        Self {
+            width,
+            color,
+            join: GUILineJoin::Miter,
+            cap: GUILineCap::Butt,
+            miter_limit: 4.,
+            dashes: Vec::new(),
+            dash_offset: GUILength::from_pixels(0.),
+        }
+    }
+}
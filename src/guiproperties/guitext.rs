@@ -0,0 +1,29 @@
+use super::guiposition::{GUILength, GUIPosition, GUISize};
+use super::GUIColor;
+
+/// Where text sits within the area that it's drawn in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GUIAlignment {
+    /// Left or top aligned.
+    #[default]
+    Start,
+    Center,
+    /// Right or bottom aligned.
+    End,
+}
+
+/// A run of text, and the area that it's drawn in.
+/// Lines are broken at new lines, but not wrapped.
+#[derive(Clone, Debug)]
+pub struct GUIText {
+    pub text: String,
+    /// The height of the text in logical pixels.
+    pub font_size: GUILength,
+    pub color: GUIColor,
+    /// The top left corner of the area in absolute logical pixels.
+    pub position: GUIPosition,
+    /// The size of the area.
+    pub size: GUISize,
+    pub horizontal_alignment: GUIAlignment,
+    pub vertical_alignment: GUIAlignment,
+}
@@ -1,5 +1,5 @@
-use super::guiposition::{GUISize, GUILength, GUIPosition};
-use super::{GUIColor, GUIIcon};
+use super::guiposition::{GUILayoutContext, GUILength, GUIPosition, GUISize};
+use super::{GUIBackground, GUIColor, GUIFill, GUIIcon, GUIRoundedRect, GUIShadow, GUIText};
 use crate::guiprocessing::vertices::Vertex;
 
 pub trait Widget {
@@ -8,12 +8,31 @@ pub trait Widget {
     /// Set the size (width and height) of the window in units of logical pixels.
     fn set_size(&mut self, size: GUISize);
 
+    /// Gets the size (width and height) of the widget in units of logical pixels.
+    fn get_size(&self) -> GUISize;
+
+    /// Gets the font size that ems are relative to within the widget,
+    /// or None if it uses the same font size as its parent.
+    fn get_font_size(&self) -> Option<GUILength> {
+        None
+    }
+
     // Set background color of the window.
     #[allow(non_snake_case)]
     fn set_background_color(&mut self, color: GUIColor);
 
     fn set_id(&mut self, id: u128);
 
+    fn get_id(&self) -> u128;
+
+    /// Whether or not the widget has changed since it was last drawn.
+    /// Setters mark widgets as dirty, so anything that's changed
+    /// directly through a widget's fields needs to be marked by hand.
+    fn is_dirty(&self) -> bool;
+
+    /// Marks the widget as needing to be redrawn, or as having been redrawn.
+    fn set_dirty(&mut self, dirty: bool);
+
     // fn get_name(&self) -> &'static str;
 }
 
@@ -34,6 +53,23 @@ pub trait Wind: Widget {
     fn set_window_icon(&mut self, icon: GUIIcon);
 }
 
+/// A widget that paints a GUIBackground behind its children.
+pub trait Container: Widget {
+    fn get_background_mut(&mut self) -> &mut GUIBackground;
+
+    /// Sets what's painted behind the children.
+    fn set_background(&mut self, fill: GUIFill) {
+        self.get_background_mut().fill = fill;
+        self.set_dirty(true);
+    }
+
+    /// Sets the shadow drawn under the widget, or takes it away.
+    fn set_shadow(&mut self, shadow: Option<GUIShadow>) {
+        self.get_background_mut().shadow = shadow;
+        self.set_dirty(true);
+    }
+}
+
 pub trait Parent: Widget {
     fn add_child(&mut self, child: Box<dyn Family>);
 
@@ -41,17 +77,101 @@ pub trait Parent: Widget {
 
     fn get_children(&self) -> &Vec<Box<dyn Family>>;
 
-    // fn get_children_mut(&mut self) -> &mut Vec<Box<dyn Family>>;
+    /// Gets the children mutably. Changes to the children themselves
+    /// mark them as dirty, but adding or removing children through this
+    /// doesn't mark the parent as dirty.
+    fn get_children_mut(&mut self) -> &mut Vec<Box<dyn Family>>;
+
+    /// Positions and sizes the widget's descendants, resolving their relative
+    /// lengths. The context's parent_size is the size of the area the widget
+    /// was given: the window for a top level widget, or whatever a layout
+    /// container allotted it. Containers that arrange their children override
+    /// this, everything else resolves its children's lengths against its own
+    /// size. Runs before every update, so it should only call setters when
+    /// something has actually moved.
+    fn layout(&mut self, context: &GUILayoutContext) {
+        let font_size = self.get_font_size().map(|font_size| font_size.get_length());
+        let context = context.with_parent(self.get_size(), font_size);
+        for child in self.get_children_mut().iter_mut() {
+            child.resolve_lengths(&context);
+            child.layout(&context);
+        }
+    }
 
     // fn give_children(&mut self) -> Vec<Box<dyn Family>>;
 }
 
 pub trait Child: Widget {
+    /// Tessellates the widget in logical pixels. `parent_position` is
+    /// the absolute position of the parent's origin in logical pixels.
     fn get_vertices_and_indices(
         &self,
-        parent_size: &GUISize,
-        indice_offset: u16
-    ) -> (Vec<Vertex>, Vec<u16>);
+        parent_position: &GUIPosition,
+        indice_offset: u32,
+    ) -> (Vec<Vertex>, Vec<u32>);
+
+    /// Gets the fills that the widget's vertices are colored by, in absolute
+    /// logical pixels. A vertex whose `fill` is n is colored by the nth of
+    /// these, counting from 1, rather than by its own color.
+    #[allow(unused_variables)]
+    fn get_fills(&self, parent_position: &GUIPosition) -> Vec<GUIFill> {
+        Vec::new()
+    }
+
+    /// Gets the rounded rectangles that the widget draws, under whatever it
+    /// tessellates. `parent_position` is the absolute position of the
+    /// parent's origin in logical pixels.
+    #[allow(unused_variables)]
+    fn get_rects(&self, parent_position: &GUIPosition) -> Vec<GUIRoundedRect> {
+        Vec::new()
+    }
+
+    /// Gets the text that the widget draws over itself. `parent_position`
+    /// is the absolute position of the parent's origin in logical pixels.
+    #[allow(unused_variables)]
+    fn get_text(&self, parent_position: &GUIPosition) -> Vec<GUIText> {
+        Vec::new()
+    }
+
+    /// Gets the position of the widget relative to its parent's origin.
+    fn get_position(&self) -> GUIPosition;
+
+    /// Whether or not a point, in absolute logical pixels, lies within
+    /// the widget. Widgets that don't react to the mouse can leave this
+    /// as is so that pointer events fall through to what's beneath them.
+    #[allow(unused_variables)]
+    fn contains_point(&self, parent_position: &GUIPosition, point: &GUIPosition) -> bool {
+        false
+    }
+
+    /// Called when the widget is pressed and released with the mouse.
+    fn click(&mut self) {}
+
+    /// Called when the mouse moves onto or off of the widget.
+    #[allow(unused_variables)]
+    fn set_hovered(&mut self, hovered: bool) {}
+
+    /// Called when the mouse is pressed on the widget, and when it's released.
+    #[allow(unused_variables)]
+    fn set_pressed(&mut self, pressed: bool) {}
+
+    /// Called when the window moves to a display with a different scale
+    /// factor, before everything is re-tessellated at the new density.
+    /// Widgets that rasterize anything themselves can re-rasterize it here.
+    #[allow(unused_variables)]
+    fn scale_factor_changed(&mut self, scale_factor: f64) {}
+
+    /// Resolves the widget's size and position when they're in relative units.
+    fn resolve_lengths(&mut self, context: &GUILayoutContext) {
+        let size = self.get_size().resolve(context);
+        if size != self.get_size() {
+            self.set_size(size);
+        }
+        let position = self.get_position().resolve(context);
+        if position != self.get_position() {
+            self.set_position_from_position(position);
+        }
+    }
 
     fn set_position_from_pixels(&mut self, x: f64, y: f64);
 
@@ -1,12 +1,42 @@
+mod guibackground;
+pub use guibackground::GUIBackground;
+
 mod guicolor;
 pub use guicolor::GUIColor;
 
+mod guicorners;
+pub use guicorners::{GUICornerRadii, GUICornerStyle};
+
+mod guifill;
+pub use guifill::{GUIColorStop, GUIFill};
+
+mod guifont;
+pub use guifont::GUIFont;
+
 mod guiicon;
 pub use guiicon::GUIIcon;
 
+pub(crate) mod guiimage;
+pub use guiimage::GUIImage;
+
+mod guipath;
+pub use guipath::{GUIFillRule, GUIPath};
+
 mod guipolygon;
 pub use guipolygon::GUIPolygon;
 
 pub mod guiposition;
 
+mod guiroundedrect;
+pub use guiroundedrect::GUIRoundedRect;
+
+mod guishadow;
+pub use guishadow::GUIShadow;
+
+mod guistroke;
+pub use guistroke::{GUILineCap, GUILineJoin, GUIStroke};
+
+mod guitext;
+pub use guitext::{GUIAlignment, GUIText};
+
 pub mod guitraits;
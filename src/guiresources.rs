@@ -2,6 +2,8 @@ use std::env;
 
 use wgpu::{Backends, PowerPreference};
 
+use crate::guiproperties::GUIFont;
+
 /// Represents the backends that gui-rs will use.
 #[derive(Debug)]
 pub enum GUIBackend {
@@ -17,6 +19,9 @@ pub enum GUIBackend {
     Gl,
     /// Supported when targeting the web through webassembly
     BrowserWebGpu,
+    /// Rasterizes on the cpu, so it works without a gpu, but it
+    /// can only render offscreen, with render_to_image. run returns an error for it.
+    Software,
 }
 
 impl Default for GUIBackend {
@@ -41,25 +46,59 @@ pub enum GUIPowerPreference {
     HighPerformance,
 }
 
+#[allow(clippy::derivable_impls)]
 impl Default for GUIPowerPreference {
     fn default() -> Self {
         Self::LowPower
     }
 }
 
+/// How many samples are taken of each pixel when the widgets are drawn.
+/// Taking more smooths the edges of tessellated shapes and text, at a cost
+/// in memory and fill rate. Rounded rectangles are anti aliased either way.
+/// wgpu 0.12 only renders with 1 or 4 samples, so on the gpu Two falls back
+/// to One and Eight to Four. The software rasterizer takes every count.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GUISampleCount {
+    #[default]
+    One,
+    Two,
+    Four,
+    Eight,
+}
+
+impl GUISampleCount {
+    /// Returns the number of samples.
+    pub fn count(&self) -> u32 {
+        match self {
+            Self::One => 1,
+            Self::Two => 2,
+            Self::Four => 4,
+            Self::Eight => 8,
+        }
+    }
+}
+
 /// Represents the the resourcess that are to be used while processing the gui.
 pub struct GUIResources {
     /// Power Preference when choosing a physical adapter.
     power_preference: GUIPowerPreference,
     /// Represents the backends that gui-rs will use.
     backend: GUIBackend,
+    /// The font that text is drawn with.
+    font: Option<GUIFont>,
+    /// How many samples are taken of each pixel.
+    sample_count: GUISampleCount,
 }
 
+#[allow(clippy::derivable_impls)]
 impl Default for GUIResources {
     fn default() -> Self {
         GUIResources {
             power_preference: GUIPowerPreference::default(),
             backend: GUIBackend::default(),
+            font: None,
+            sample_count: GUISampleCount::default(),
         }
     }
 }
@@ -84,6 +123,44 @@ impl GUIResources {
             Dx11 => Backends::DX11,
             Gl => Backends::GL,
             BrowserWebGpu => Backends::BROWSER_WEBGPU,
+            Software => Backends::empty(),
         }
     }
+
+    /// Whether or not the software rasterizer was selected instead of a gpu backend.
+    pub fn is_software(&self) -> bool {
+        matches!(self.backend, GUIBackend::Software)
+    }
+
+    /// Sets the power preference used when choosing a physical adapter.
+    pub fn set_power_preference(&mut self, power_preference: GUIPowerPreference) {
+        self.power_preference = power_preference;
+    }
+
+    /// Sets the backend that gui-rs will use.
+    pub fn set_backend(&mut self, backend: GUIBackend) {
+        self.backend = backend;
+    }
+
+    /// Sets how many samples are taken of each pixel. Counts that wgpu 0.12
+    /// can't render with fall back to the highest lower one that it can.
+    pub fn set_sample_count(&mut self, sample_count: GUISampleCount) {
+        self.sample_count = sample_count;
+    }
+
+    /// Returns how many samples were asked to be taken of each pixel.
+    pub fn sample_count(&self) -> GUISampleCount {
+        self.sample_count
+    }
+
+    /// Sets the font that text is drawn with.
+    pub fn set_font(&mut self, font: GUIFont) {
+        self.font = Some(font);
+    }
+
+    /// Returns the font that text is drawn with, which is either the font
+    /// that was set or, failing that, a commonly installed system font.
+    pub fn font(&self) -> Option<GUIFont> {
+        self.font.clone().or_else(GUIFont::system_default)
+    }
 }
@@ -0,0 +1,203 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::guiprocessing::render_to_image;
+use crate::guiproperties::GUIImage;
+use crate::guiresources::{GUIBackend, GUIResources};
+use crate::guiwidgets::GUIWindow;
+
+/// When this environment variable is set, goldens are overwritten
+/// with what's rendered instead of being compared against it.
+pub const UPDATE_GOLDENS_VAR: &str = "GUI_RS_UPDATE_GOLDENS";
+
+/// How much each channel of a pixel can differ from the golden
+/// by default, so that rounding differences don't fail a test.
+const DEFAULT_TOLERANCE: u8 = 2;
+
+/// Compares rendered windows against golden PNGs that are stored in a directory.
+/// When an image doesn't match its golden, it's written next to the golden as
+/// `<name>.actual.png`, along with a `<name>.diff.png` that shows the pixels that
+/// differ in red. Running the tests with `GUI_RS_UPDATE_GOLDENS=1` saves what's
+/// rendered as the new goldens.
+pub struct GUIGoldens {
+    /// The directory that the goldens are stored in.
+    directory: PathBuf,
+    /// How much each channel of a pixel can differ from the golden.
+    tolerance: u8,
+}
+
+impl GUIGoldens {
+    /// Makes a set of goldens that are stored in the given directory.
+    pub fn new<P: AsRef<Path>>(directory: P) -> Self {
+        Self {
+            directory: directory.as_ref().to_path_buf(),
+            tolerance: DEFAULT_TOLERANCE,
+        }
+    }
+
+    /// Sets how much each channel of a pixel can differ from the golden.
+    pub fn set_tolerance(&mut self, tolerance: u8) {
+        self.tolerance = tolerance;
+    }
+
+    /// Returns the path of the golden with the given name.
+    pub fn path(&self, name: &str) -> PathBuf {
+        self.directory.join(format!("{}.png", name))
+    }
+
+    /// Compares an image against the golden with the given name, or saves it
+    /// as the golden if UPDATE_GOLDENS_VAR is set. Returns a description of
+    /// the mismatch if it doesn't match.
+    pub fn check(&self, name: &str, image: &GUIImage) -> Result<(), String> {
+        self.check_or_update(name, image, env::var_os(UPDATE_GOLDENS_VAR).is_some())
+    }
+
+    fn check_or_update(&self, name: &str, image: &GUIImage, update: bool) -> Result<(), String> {
+        let golden_path = self.path(name);
+        let actual_path = self.directory.join(format!("{}.actual.png", name));
+        let diff_path = self.directory.join(format!("{}.diff.png", name));
+        // Don't leave the output of an earlier failure lying around.
+        let _ = fs::remove_file(&actual_path);
+        let _ = fs::remove_file(&diff_path);
+
+        if update {
+            fs::create_dir_all(&self.directory).map_err(|error| error.to_string())?;
+            return image
+                .save_png(&golden_path)
+                .map_err(|error| format!("couldn't save {}: {}", golden_path.display(), error));
+        }
+
+        let golden = GUIImage::open_png(&golden_path).map_err(|error| {
+            let _ = image.save_png(&actual_path);
+            format!(
+                "couldn't open {}: {}, run with {}=1 to create it",
+                golden_path.display(),
+                error,
+                UPDATE_GOLDENS_VAR
+            )
+        })?;
+        let mismatch = if (golden.width(), golden.height()) != (image.width(), image.height()) {
+            format!(
+                "{} is {}x{} but the image is {}x{}",
+                golden_path.display(),
+                golden.width(),
+                golden.height(),
+                image.width(),
+                image.height()
+            )
+        } else {
+            match diff_images(&golden, image, self.tolerance) {
+                None => return Ok(()),
+                Some((count, diff)) => {
+                    let _ = diff.save_png(&diff_path);
+                    format!(
+                        "{} pixels differ from {} by more than {}, see {}",
+                        count,
+                        golden_path.display(),
+                        self.tolerance,
+                        diff_path.display()
+                    )
+                }
+            }
+        };
+        let _ = image.save_png(&actual_path);
+        Err(mismatch)
+    }
+
+    /// Renders a window with the software rasterizer and compares it against
+    /// the golden with the given name, panicking if it doesn't match.
+    /// The window is rendered at a scale of 1 unless it has a logical_scale.
+    pub fn assert_window(&self, name: &str, guiwindow: GUIWindow) {
+        let mut guiresources = GUIResources::default();
+        guiresources.set_backend(GUIBackend::Software);
+        self.assert_window_with(name, guiwindow, guiresources);
+    }
+
+    /// Renders a window with the given resources, which can select a gpu
+    /// backend, and compares it against the golden with the given name,
+    /// panicking if it doesn't match.
+    pub fn assert_window_with(&self, name: &str, guiwindow: GUIWindow, guiresources: GUIResources) {
+        let image = render_to_image(guiwindow, guiresources)
+            .unwrap_or_else(|error| panic!("couldn't render {}: {}", name, error));
+        if let Err(mismatch) = self.check(name, &image) {
+            panic!("{}", mismatch);
+        }
+    }
+}
+
+/// Compares two images of the same size. If any channel of any pixel differs by
+/// more than the tolerance, returns the number of such pixels and an image that
+/// shows them in red over a faded copy of the expected image.
+pub fn diff_images(
+    expected: &GUIImage,
+    actual: &GUIImage,
+    tolerance: u8,
+) -> Option<(usize, GUIImage)> {
+    let mut count = 0;
+    let pixels = expected
+        .pixels()
+        .chunks(4)
+        .zip(actual.pixels().chunks(4))
+        .flat_map(|(expected, actual)| {
+            let differs = expected
+                .iter()
+                .zip(actual)
+                .any(|(expected, actual)| expected.abs_diff(*actual) > tolerance);
+            if differs {
+                count += 1;
+                [255, 0, 0, 255]
+            } else {
+                let gray = (expected[0] as u32 + expected[1] as u32 + expected[2] as u32) / 3;
+                let faded = (gray / 4 + 191) as u8;
+                [faded, faded, faded, 255]
+            }
+        })
+        .collect();
+    if count == 0 {
+        return None;
+    }
+    let diff = GUIImage::from_pixels(expected.width(), expected.height(), pixels)
+        .expect("the diff is the same size as the images");
+    Some((count, diff))
+}
+
+/// The goldens for the crate's own tests.
+#[cfg(test)]
+pub(crate) fn crate_goldens() -> GUIGoldens {
+    GUIGoldens::new(Path::new(env!("CARGO_MANIFEST_DIR")).join("goldens"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff_images_reports_pixels_outside_of_the_tolerance() {
+        let expected = GUIImage::from_pixels(2, 1, vec![100, 100, 100, 255, 0, 0, 0, 255]).unwrap();
+        let actual = GUIImage::from_pixels(2, 1, vec![102, 99, 100, 255, 0, 3, 0, 255]).unwrap();
+
+        assert!(diff_images(&expected, &actual, 3).is_none());
+        let (count, diff) = diff_images(&expected, &actual, 2).unwrap();
+        assert_eq!(count, 1);
+        assert_eq!(diff.pixel(1, 0), [255, 0, 0, 255]);
+        assert_ne!(diff.pixel(0, 0), [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn check_writes_the_actual_image_and_a_diff_on_mismatch() {
+        let directory = env::temp_dir().join(format!("gui_rs_goldens_{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let goldens = GUIGoldens::new(&directory);
+        let golden = GUIImage::from_pixels(1, 1, vec![0, 0, 0, 255]).unwrap();
+        golden.save_png(goldens.path("square")).unwrap();
+
+        assert!(goldens.check_or_update("square", &golden, false).is_ok());
+        let image = GUIImage::from_pixels(1, 1, vec![255, 255, 255, 255]).unwrap();
+        assert!(goldens.check_or_update("square", &image, false).is_err());
+        assert!(directory.join("square.actual.png").exists());
+        assert!(directory.join("square.diff.png").exists());
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,370 @@
+use uuid::Uuid;
+
+use crate::guiprocessing::vertices::Vertex;
+use crate::guiproperties::guiposition::{
+    GUIAxis, GUILayoutContext, GUILength, GUIPosition, GUIRect, GUISize,
+};
+use crate::guiproperties::guitraits::{Child, Container, Family, Parent, Widget};
+use crate::guiproperties::{GUIBackground, GUIColor, GUIFill, GUIRoundedRect};
+use crate::guiwidgets::widget_utils;
+
+/// Which edges of its parent a child sticks to, and how far from them it stays.
+/// A child that's anchored to both edges along an axis stretches between them,
+/// one that's anchored to one edge keeps its base length along that axis, and
+/// one that isn't anchored along an axis is centered along it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GUIAnchors {
+    /// The margin between the left edges of the parent and child.
+    pub left: Option<GUILength>,
+    /// The margin between the top edges of the parent and child.
+    pub top: Option<GUILength>,
+    /// The margin between the right edges of the parent and child.
+    pub right: Option<GUILength>,
+    /// The margin between the bottom edges of the parent and child.
+    pub bottom: Option<GUILength>,
+    /// The size the child has along any axis that it doesn't stretch along.
+    pub base_size: GUISize,
+}
+
+impl GUIAnchors {
+    /// Returns anchors that center a child of the given size.
+    pub fn new(base_size: GUISize) -> Self {
+        Self {
+            left: None,
+            top: None,
+            right: None,
+            bottom: None,
+            base_size,
+        }
+    }
+
+    /// Returns anchors that keep a child where it is relative to the top left of its parent.
+    pub fn from_position(position: GUIPosition, base_size: GUISize) -> Self {
+        Self {
+            left: Some(position.x),
+            top: Some(position.y),
+            ..Self::new(base_size)
+        }
+    }
+
+    /// Returns the position and size of a child within the context's parent,
+    /// resolving any relative margins and base lengths against the parent.
+    pub fn resolve(&self, context: &GUILayoutContext) -> (GUIPosition, GUISize) {
+        let (x, width) = resolve_axis(
+            self.left,
+            self.right,
+            self.base_size.width,
+            GUIAxis::Horizontal,
+            context,
+        );
+        let (y, height) = resolve_axis(
+            self.top,
+            self.bottom,
+            self.base_size.height,
+            GUIAxis::Vertical,
+            context,
+        );
+        (
+            GUIPosition::from_lengths(x, y),
+            GUISize::from_lengths(width, height),
+        )
+    }
+}
+
+/// Returns the offset and length of a child along one axis of its parent.
+fn resolve_axis(
+    start: Option<GUILength>,
+    end: Option<GUILength>,
+    base: GUILength,
+    axis: GUIAxis,
+    context: &GUILayoutContext,
+) -> (GUILength, GUILength) {
+    let resolve = |length: GUILength| length.resolve(axis, context).get_length();
+    let parent = match axis {
+        GUIAxis::Horizontal => context.parent_size.width.get_length(),
+        GUIAxis::Vertical => context.parent_size.height.get_length(),
+    };
+    let base = resolve(base);
+    let (offset, length) = match (start.map(resolve), end.map(resolve)) {
+        (Some(start), Some(end)) => (start, (parent - start - end).max(0.)),
+        (Some(start), None) => (start, base),
+        (None, Some(end)) => (parent - end - base, base),
+        (None, None) => ((parent - base) / 2., base),
+    };
+    (
+        GUILength::from_pixels(offset),
+        GUILength::from_pixels(length),
+    )
+}
+
+/// Positions and sizes each of its children by anchoring them to its edges,
+/// so that they follow the edges around when it's resized.
+pub struct GUIAnchorLayout {
+    /// The size of the layout. Ignored if fill_parent is set.
+    pub size: GUISize,
+    /// The location of the layout.
+    pub position: GUIPosition,
+    /// Whether the layout takes on the size of the area it's given: the window
+    /// for a top level layout, or the space that its parent allots it.
+    pub fill_parent: bool,
+    /// What's painted behind the children, and the shadow under them.
+    pub background: GUIBackground,
+    /// A list of child widgets
+    pub children: Vec<Box<dyn Family>>,
+    /// The anchors of each child, in the same order as the children.
+    pub anchors: Vec<GUIAnchors>,
+    /// The human readable name of the layout
+    pub name: &'static str,
+    pub id: u128,
+    dirty: bool,
+}
+
+const DEFAULT_ANCHOR_LAYOUT_NAME: &str = "this is the default name of the anchor layout";
+
+impl Default for GUIAnchorLayout {
+    // Returns a layout with all of the default values.
+    fn default() -> GUIAnchorLayout {
+        GUIAnchorLayout {
+            size: GUISize::from_pixels(200., 200.),
+            position: GUIPosition::from_pixels(0., 0.),
+            fill_parent: true,
+            background: GUIBackground::default(),
+            children: Vec::new(),
+            anchors: Vec::new(),
+            name: DEFAULT_ANCHOR_LAYOUT_NAME,
+            id: Uuid::new_v4().as_u128(),
+            dirty: true,
+        }
+    }
+}
+
+impl Widget for GUIAnchorLayout {
+    fn is_rendered(&self) -> bool {
+        true
+    }
+
+    /// Set the size (width and height) of the layout in units of logical pixels.
+    fn set_size(&mut self, size: GUISize) {
+        self.size = size;
+        self.dirty = true;
+    }
+
+    fn get_size(&self) -> GUISize {
+        self.size
+    }
+
+    // Set background color of the layout.
+    fn set_background_color(&mut self, color: GUIColor) {
+        self.background.fill = GUIFill::Solid(color);
+        self.dirty = true;
+    }
+
+    fn set_id(&mut self, id: u128) {
+        self.id = id;
+    }
+
+    fn get_id(&self) -> u128 {
+        self.id
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    fn set_dirty(&mut self, dirty: bool) {
+        self.dirty = dirty;
+    }
+}
+
+impl Parent for GUIAnchorLayout {
+    /// Adds a child that stays where it is relative to the top left of the layout.
+    fn add_child(&mut self, child: Box<dyn Family>) {
+        let anchors = GUIAnchors::from_position(child.get_position(), child.get_size());
+        self.add_child_with_anchors(anchors, child);
+    }
+
+    fn set_children(&mut self, children: Vec<Box<dyn Family>>) {
+        self.children.clear();
+        self.anchors.clear();
+        for child in children {
+            self.add_child(child);
+        }
+        self.dirty = true;
+    }
+
+    /// Gets the children.
+    fn get_children(&self) -> &Vec<Box<dyn Family>> {
+        &self.children
+    }
+
+    /// Gets the children mutably.
+    fn get_children_mut(&mut self) -> &mut Vec<Box<dyn Family>> {
+        &mut self.children
+    }
+
+    /// Positions and sizes the children from their anchors,
+    /// then lays out their own children.
+    fn layout(&mut self, context: &GUILayoutContext) {
+        if self.fill_parent && self.size != context.parent_size {
+            self.set_size(context.parent_size);
+        }
+        let inner_context = context.with_parent(self.size, None);
+
+        // Children that were added through get_children_mut stay where they are.
+        self.anchors.truncate(self.children.len());
+        while self.anchors.len() < self.children.len() {
+            let child = &self.children[self.anchors.len()];
+            let anchors = GUIAnchors::from_position(child.get_position(), child.get_size());
+            self.anchors.push(anchors);
+        }
+
+        for (child, anchors) in self.children.iter_mut().zip(self.anchors.iter()) {
+            let (position, size) = anchors.resolve(&inner_context);
+            widget_utils::place_child(child, position, size, context);
+        }
+    }
+}
+
+impl Child for GUIAnchorLayout {
+    /// Tessellates the layout's background, if it has one.
+    fn get_vertices_and_indices(
+        &self,
+        parent_position: &GUIPosition,
+        indice_offset: u32,
+    ) -> (Vec<Vertex>, Vec<u32>) {
+        let rect = GUIRect::new(*parent_position + self.position, self.size);
+        self.background
+            .get_vertices_and_indices(&rect, self.id, indice_offset)
+    }
+
+    fn get_fills(&self, parent_position: &GUIPosition) -> Vec<GUIFill> {
+        let rect = GUIRect::new(*parent_position + self.position, self.size);
+        self.background.get_fills(&rect)
+    }
+
+    /// Gets the layout's shadow, if it has one.
+    fn get_rects(&self, parent_position: &GUIPosition) -> Vec<GUIRoundedRect> {
+        let rect = GUIRect::new(*parent_position + self.position, self.size);
+        self.background.get_rects(&rect)
+    }
+
+    fn get_position(&self) -> GUIPosition {
+        self.position
+    }
+
+    fn set_position_from_pixels(&mut self, x: f64, y: f64) {
+        self.set_position_from_position(GUIPosition::from_pixels(x, y));
+    }
+
+    fn set_position_from_lengths(&mut self, x: GUILength, y: GUILength) {
+        self.set_position_from_position(GUIPosition::from_lengths(x, y));
+    }
+
+    fn set_position_from_position(&mut self, position: GUIPosition) {
+        self.position = position;
+        self.dirty = true;
+    }
+}
+
+impl Container for GUIAnchorLayout {
+    fn get_background_mut(&mut self) -> &mut GUIBackground {
+        &mut self.background
+    }
+}
+
+impl Family for GUIAnchorLayout {}
+
+impl GUIAnchorLayout {
+    /// Adds a child that's positioned by the given anchors.
+    pub fn add_child_with_anchors(&mut self, anchors: GUIAnchors, child: Box<dyn Family>) {
+        self.anchors.truncate(self.children.len());
+        for child in self.children[self.anchors.len()..].iter() {
+            self.anchors.push(GUIAnchors::from_position(
+                child.get_position(),
+                child.get_size(),
+            ));
+        }
+        self.anchors.push(anchors);
+        self.children.push(child);
+        self.dirty = true;
+    }
+
+    /// Sets whether the layout takes on the size of the area it's given.
+    pub fn set_fill_parent(&mut self, fill_parent: bool) {
+        self.fill_parent = fill_parent;
+        self.dirty = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::guiwidgets::{GUIButton, GUIWindow};
+
+    fn button(width: f64, height: f64) -> Box<dyn Family> {
+        let mut button = GUIButton::default();
+        button.set_size(GUISize::from_pixels(width, height));
+        Box::new(button)
+    }
+
+    #[test]
+    fn anchors_resolve_against_the_parent_size() {
+        let parent = GUILayoutContext::new(GUISize::from_pixels(300., 200.), 1.);
+        let margin = Some(GUILength::from_pixels(16.));
+        let base_size = GUISize::from_pixels(100., 40.);
+
+        let bottom_right = GUIAnchors {
+            right: margin,
+            bottom: margin,
+            ..GUIAnchors::new(base_size)
+        };
+        assert_eq!(
+            bottom_right.resolve(&parent),
+            (GUIPosition::from_pixels(184., 144.), base_size)
+        );
+
+        let stretched = GUIAnchors {
+            left: margin,
+            right: margin,
+            top: margin,
+            ..GUIAnchors::new(base_size)
+        };
+        assert_eq!(
+            stretched.resolve(&parent),
+            (
+                GUIPosition::from_pixels(16., 16.),
+                GUISize::from_pixels(268., 40.)
+            )
+        );
+
+        assert_eq!(
+            GUIAnchors::new(base_size).resolve(&parent),
+            (GUIPosition::from_pixels(100., 80.), base_size)
+        );
+    }
+
+    #[test]
+    fn anchored_children_follow_the_window_when_it_resizes() {
+        let mut anchor_layout = GUIAnchorLayout::default();
+        let margin = Some(GUILength::from_pixels(16.));
+        let child = button(100., 40.);
+        let anchors = GUIAnchors {
+            right: margin,
+            bottom: margin,
+            ..GUIAnchors::new(child.get_size())
+        };
+        anchor_layout.add_child_with_anchors(anchors, child);
+        let mut window = GUIWindow::default();
+        window.add_child(Box::new(anchor_layout));
+
+        for (width, height) in [(300., 200.), (640., 480.)] {
+            window.set_size(GUISize::from_pixels(width, height));
+            window.layout_children();
+            let child = &window.children[0].get_children()[0];
+            assert_eq!(
+                child.get_position(),
+                GUIPosition::from_pixels(width - 116., height - 56.)
+            );
+        }
+    }
+}
@@ -1,58 +1,180 @@
-use std::time::Instant;
+use std::f64::consts::PI;
 
 use uuid::Uuid;
 
+use crate::guiprocessing::tessellation;
 use crate::guiprocessing::vertices::Vertex;
-use crate::guiproperties::guiposition::{GUILength, GUIPosition, GUISize};
+use crate::guiproperties::guiposition::{
+    GUILayoutContext, GUILength, GUIPosition, GUIRect, GUISize,
+};
 use crate::guiproperties::guitraits::{Child, Family, Parent, Widget};
-use crate::guiproperties::GUIColor;
-use crate::guiwidgets::{widget_utils, widget_utils::arcs};
+use crate::guiproperties::{
+    GUIAlignment, GUIColor, GUICornerRadii, GUICornerStyle, GUIFill, GUIRoundedRect, GUIShadow,
+    GUIStroke, GUIText,
+};
+use crate::guiwidgets::{widget_utils, widget_utils::arcs, GUILabel};
+
+/// The states that a button can be in, each of which has its own style.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GUIButtonState {
+    Normal,
+    /// The mouse is over the button.
+    Hovered,
+    /// The mouse was pressed on the button and is still over it.
+    Pressed,
+    /// The button doesn't react to the mouse.
+    Disabled,
+}
+
+/// How a button's rectangle is drawn.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GUIButtonRendering {
+    /// As a single quad whose corners, border and anti aliased edge are worked
+    /// out for every pixel. Only round corners can be drawn this way, so
+    /// buttons with any other corner style are tessellated instead.
+    #[default]
+    Sdf,
+    /// Tessellated into triangles, with the corners flattened into line segments.
+    Tessellated,
+}
+
+/// How a button looks while it's in a given state.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GUIButtonStyle {
+    /// What the inside of the button is painted with.
+    pub background: GUIFill,
+    /// Radius of each of the button's corners. They're scaled down
+    /// to fit if they're too big for the button.
+    pub corner_radii: GUICornerRadii,
+    /// The shape of the button's corners.
+    pub corner_style: GUICornerStyle,
+    /// Width of the border drawn over the fill, just inside of the button's edge.
+    pub border_width: GUILength,
+    /// Color of the border.
+    pub border_color: GUIColor,
+    /// Color of the button's text.
+    pub text_color: GUIColor,
+}
+
+impl GUIButtonStyle {
+    /// Makes the styles for the normal, hovered, pressed, and disabled
+    /// states from the normal style by lightening, darkening,
+    /// and graying out its colors.
+    fn derive_states(normal: GUIButtonStyle) -> [GUIButtonStyle; 4] {
+        let white = GUIColor::from_rgba_u8u8u8u8(255, 255, 255, 255);
+        let black = GUIColor::from_rgba_u8u8u8u8(0, 0, 0, 255);
+        let gray = GUIColor::from_rgba_u8u8u8u8(128, 128, 128, 255);
+        let shade = |color: &GUIColor, amount: f64| GUIButtonStyle {
+            background: normal.background.mix(color, amount),
+            border_color: normal.border_color.mix(color, amount),
+            text_color: normal.text_color.mix(color, amount),
+            ..normal.clone()
+        };
+        let [hovered, pressed, disabled] =
+            [shade(&white, 0.2), shade(&black, 0.25), shade(&gray, 0.7)];
+        [normal, hovered, pressed, disabled]
+    }
+}
 
 /// Represents a gui button.
 // #[derive(Clone, Copy)]
 pub struct GUIButton {
     /// The tile of the button.
     pub text: &'static str,
+    /// The height of the button's text in logical pixels.
+    pub font_size: GUILength,
     /// The size of the button.
     pub size: GUISize,
     /// The location of the button.
     pub position: GUIPosition,
-    /// Radius of the button corners.
-    pub radius: GUILength,
-    /// The background color for the button.
-    pub background_color: GUIColor,
+    /// How the button looks normally.
+    pub style: GUIButtonStyle,
+    /// How the button looks while the mouse is over it.
+    pub hovered_style: GUIButtonStyle,
+    /// How the button looks while it's being pressed.
+    pub pressed_style: GUIButtonStyle,
+    /// How the button looks while it's disabled.
+    pub disabled_style: GUIButtonStyle,
+    /// Whether or not the button reacts to the mouse.
+    pub enabled: bool,
+    /// How the button's rectangle is drawn.
+    pub rendering: GUIButtonRendering,
+    /// The shadow drawn under the button. It has round corners whatever the
+    /// style of the button's corners is.
+    pub shadow: Option<GUIShadow>,
     /// A list of child widgets
     pub children: Vec<Box<dyn Family>>,
     /// The human readable name of the button
     pub name: &'static str,
     pub id: u128,
+    /// Called when the button is clicked.
+    pub on_click: Option<Box<dyn FnMut()>>,
+    hovered: bool,
+    pressed: bool,
+    /// Whether the hovered, pressed, and disabled styles were set by hand,
+    /// rather than derived from the normal style.
+    overridden: [bool; 3],
+    /// The scale factor that the button was last laid out at, which
+    /// decides how finely its corners are tessellated.
+    scale_factor: f64,
+    dirty: bool,
 }
 
 const DEFAULT_BUTTON_NAME: &str = "this is the default name of the window";
 
-// #[cfg(feature = "v4")] 
+// #[cfg(feature = "v4")]
 impl Default for GUIButton {
     // Returns a button with all of the default values.
-    // 
+    //
     fn default() -> GUIButton {
-
+        let [style, hovered_style, pressed_style, disabled_style] =
+            GUIButtonStyle::derive_states(GUIButtonStyle {
+                background: GUIFill::Solid(GUIColor {
+                    r: 0.7,
+                    g: 0.1,
+                    b: 0.4,
+                    a: 1.0,
+                }),
+                corner_radii: GUICornerRadii::uniform(GUILength::from_pixels(25.)),
+                corner_style: GUICornerStyle::Round,
+                border_width: GUILength::from_pixels(0.),
+                border_color: GUIColor {
+                    r: 0.3,
+                    g: 0.05,
+                    b: 0.15,
+                    a: 1.0,
+                },
+                text_color: GUIColor {
+                    r: 1.0,
+                    g: 1.0,
+                    b: 1.0,
+                    a: 1.0,
+                },
+            });
         GUIButton {
             text: "Button",
+            font_size: GUILength::from_pixels(20.),
             size: GUISize {
                 width: GUILength::from_pixels(200.),
                 height: GUILength::from_pixels(100.),
             },
             position: GUIPosition::from_pixels(0., 0.),
-            radius: GUILength::from_pixels(25.),
-            background_color: GUIColor {
-                r: 0.7,
-                g: 0.1,
-                b: 0.4,
-                a: 1.0,
-            },
+            style,
+            hovered_style,
+            pressed_style,
+            disabled_style,
+            enabled: true,
+            rendering: GUIButtonRendering::Sdf,
+            shadow: None,
             children: Vec::new(),
             name: DEFAULT_BUTTON_NAME,
             id: Uuid::new_v4().as_u128(),
+            on_click: None,
+            hovered: false,
+            pressed: false,
+            overridden: [false; 3],
+            scale_factor: 1.,
+            dirty: true,
         }
     }
 }
@@ -65,16 +187,39 @@ impl Widget for GUIButton {
     /// Set the size (width and height) of the window in units of logical pixels.
     fn set_size(&mut self, size: GUISize) {
         self.size = size;
+        self.dirty = true;
+    }
+
+    fn get_size(&self) -> GUISize {
+        self.size
+    }
+
+    /// Ems within the button are relative to the size of its text.
+    fn get_font_size(&self) -> Option<GUILength> {
+        Some(self.font_size)
     }
 
-    // Set background color of the window.
+    // Set background color of the button's normal style.
+    // The other states follow it unless they were set by hand.
     fn set_background_color(&mut self, color: GUIColor) {
-        self.background_color = color;
+        self.set_background(GUIFill::Solid(color));
     }
 
     fn set_id(&mut self, id: u128) {
         self.id = id;
     }
+
+    fn get_id(&self) -> u128 {
+        self.id
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    fn set_dirty(&mut self, dirty: bool) {
+        self.dirty = dirty;
+    }
 }
 
 impl Parent for GUIButton {
@@ -85,10 +230,12 @@ impl Parent for GUIButton {
     /// before the obscuring children.
     fn add_child(&mut self, child: Box<dyn Family>) {
         self.children.push(child);
+        self.dirty = true;
     }
 
     fn set_children(&mut self, children: Vec<Box<dyn Family>>) {
         self.children = children;
+        self.dirty = true;
     }
 
     /// Gets the children.
@@ -96,10 +243,24 @@ impl Parent for GUIButton {
         &self.children
     }
 
-    // /// Gets the children.
-    // fn get_children_mut(&mut self) -> &mut Vec<Box<dyn Family>> {
-    //     &mut self.children
-    // }
+    /// Gets the children mutably.
+    fn get_children_mut(&mut self) -> &mut Vec<Box<dyn Family>> {
+        &mut self.children
+    }
+
+    /// Keeps track of the scale factor, so that the corners are tessellated
+    /// again more or less finely when it changes.
+    fn layout(&mut self, context: &GUILayoutContext) {
+        if self.scale_factor != context.scale_factor {
+            self.scale_factor = context.scale_factor;
+            self.dirty = true;
+        }
+        let context = context.with_parent(self.size, Some(self.font_size.get_length()));
+        for child in self.children.iter_mut() {
+            child.resolve_lengths(&context);
+            child.layout(&context);
+        }
+    }
 
     // fn give_children(&mut self) -> Vec<Box<dyn Family>> {
     //     self.children
@@ -109,98 +270,761 @@ impl Parent for GUIButton {
 impl Child for GUIButton {
     fn get_vertices_and_indices(
         &self,
-        parent_size: &GUISize,
-        indice_offset: u16,
-    ) -> (Vec<Vertex>, Vec<u16>) {
-        const FASCET_COUNT: usize = 7;
-        let mut top_left_radius = arcs::make_top_left_arc(self.radius, FASCET_COUNT);
-        top_left_radius = widget_utils::translate(
-            top_left_radius,
-            &self.radius.add(&self.position.x),
-            &self.radius.add(&self.position.y),
-        );
+        parent_position: &GUIPosition,
+        indice_offset: u32,
+    ) -> (Vec<Vertex>, Vec<u32>) {
+        if self.is_drawn_as_rect() {
+            return (Vec::new(), Vec::new());
+        }
+        let style = self.current_style();
+        let fill = self.make_outline(parent_position, &GUILength::from_pixels(0.));
+        let mut vertices = widget_utils::make_filled_vertices(&fill, &style.background, self.id);
+        let mut indices = Vec::new();
+        for i in 1..fill.len() as u32 - 1 {
+            indices.extend([indice_offset, indice_offset + i, indice_offset + i + 1]);
+        }
 
-        let top_right_radius = arcs::make_top_right_arc(self.radius, FASCET_COUNT);
-        let top_right_radius = widget_utils::translate(
-            top_right_radius,
-            &self.size.width.subtract(&self.radius).add(&self.position.x),
-            &self.radius.add(&self.position.y),
-        );
+        // The border is stroked along an outline inset by half of its width,
+        // so that it lies just inside of the button's edge.
+        if style.border_width.get_length() > 0. {
+            let outline = self.make_outline(parent_position, &(style.border_width / 2.));
+            let stroke = GUIStroke::new(style.border_width, style.border_color);
+            let (positions, border_indices) =
+                tessellation::tessellate_stroke(&outline, true, &stroke, self.tolerance());
+            let border_offset = indice_offset + vertices.len() as u32;
+            indices.extend(border_indices.iter().map(|index| index + border_offset));
+            vertices.extend(widget_utils::make_vertices(
+                &positions,
+                &style.border_color,
+                self.id,
+            ));
+        }
 
-        let bottom_left_radius = arcs::make_bottom_left_arc(self.radius, FASCET_COUNT);
-        let bottom_left_radius = widget_utils::translate(
-            bottom_left_radius,
-            &self.radius.add(&self.position.x),
-            &self
-                .size
-                .height
-                .subtract(&self.radius)
-                .add(&self.position.y),
-        );
+        (vertices, indices)
+    }
 
-        let bottom_right_radius = arcs::make_bottom_right_arc(self.radius, FASCET_COUNT);
-        let bottom_right_radius = widget_utils::translate(
-            bottom_right_radius,
-            &self.size.width.subtract(&self.radius).add(&self.position.x),
-            &self
-                .size
-                .height
-                .subtract(&self.radius)
-                .add(&self.position.y),
-        );
+    fn get_fills(&self, parent_position: &GUIPosition) -> Vec<GUIFill> {
+        if self.is_drawn_as_rect() {
+            return Vec::new();
+        }
+        widget_utils::fills(
+            &self.current_style().background,
+            &self.rect(parent_position),
+        )
+    }
 
-        top_left_radius.extend(bottom_left_radius);
-        top_left_radius.extend(bottom_right_radius);
-        top_left_radius.extend(top_right_radius);
-
-        let mut vertices = Vec::with_capacity(top_left_radius.len());
-        for position in top_left_radius.iter() {
-            vertices.push(Vertex {
-                position: [
-                    (position.x.get_length() / parent_size.width.get_length() - 1.) as f32,
-                    (-position.y.get_length() / parent_size.height.get_length() + 1.) as f32,
-                    0.,
-                ],
-                color: [
-                    self.background_color.r as f32,
-                    self.background_color.g as f32,
-                    self.background_color.b as f32,
-                ],
-                id: self.id
+    /// Gets the button's shadow, followed by its rectangle unless it's
+    /// tessellated. Rects are drawn before shapes, so the shadow is under
+    /// the button either way.
+    fn get_rects(&self, parent_position: &GUIPosition) -> Vec<GUIRoundedRect> {
+        let style = self.current_style();
+        let rect = self.rect(parent_position);
+        let mut rects: Vec<GUIRoundedRect> = self
+            .shadow
+            .iter()
+            .map(|shadow| shadow.rounded_rect(&rect, &style.corner_radii))
+            .collect();
+        if self.is_drawn_as_rect() {
+            rects.push(GUIRoundedRect {
+                rect,
+                corner_radii: style.corner_radii,
+                background: style.background.clone(),
+                border_width: style.border_width,
+                border_color: style.border_color,
+                blur: GUILength::from_pixels(0.),
             });
         }
-        let number_of_triangles = vertices.len() - 2;
-        let mut indices = Vec::with_capacity(number_of_triangles * 3);
-        for i in 0..number_of_triangles {
-            indices.push(indice_offset + 0);
-            indices.push(indice_offset + (i + 1) as u16);
-            indices.push(indice_offset + (i + 2) as u16);
+        rects
+    }
+
+    /// Gets the button's text, centered in the button.
+    fn get_text(&self, parent_position: &GUIPosition) -> Vec<GUIText> {
+        let mut label = GUILabel::default();
+        label.text = self.text.to_string();
+        label.size = self.size;
+        label.position = self.position;
+        label.font_size = self.font_size;
+        label.color = self.current_style().text_color;
+        label.horizontal_alignment = GUIAlignment::Center;
+        label.vertical_alignment = GUIAlignment::Center;
+        label.get_text(parent_position)
+    }
+
+    fn get_position(&self) -> GUIPosition {
+        self.position
+    }
+
+    /// Whether or not the point lies within the button's rounded rectangle.
+    fn contains_point(&self, parent_position: &GUIPosition, point: &GUIPosition) -> bool {
+        if !self.rect(parent_position).contains(point) {
+            return false;
         }
+        let outline = self.make_outline(parent_position, &GUILength::from_pixels(0.));
+        widget_utils::contains(&outline, point)
+    }
 
-        (vertices, indices)
+    /// Calls the button's on_click closure, if it has one and it's enabled.
+    fn click(&mut self) {
+        if !self.enabled {
+            return;
+        }
+        if let Some(on_click) = self.on_click.as_mut() {
+            on_click();
+        }
+    }
+
+    fn set_hovered(&mut self, hovered: bool) {
+        let state = self.state();
+        self.hovered = hovered;
+        self.dirty |= state != self.state();
+    }
+
+    fn set_pressed(&mut self, pressed: bool) {
+        let state = self.state();
+        self.pressed = pressed;
+        self.dirty |= state != self.state();
     }
 
     fn set_position_from_pixels(&mut self, x: f64, y: f64) {
-        self.position = GUIPosition::from_pixels(x, y);
+        self.set_position_from_position(GUIPosition::from_pixels(x, y));
     }
 
     fn set_position_from_lengths(&mut self, x: GUILength, y: GUILength) {
-        self.position = GUIPosition::from_lengths(x, y);
+        self.set_position_from_position(GUIPosition::from_lengths(x, y));
     }
 
     fn set_position_from_position(&mut self, position: GUIPosition) {
         self.position = position;
+        self.dirty = true;
     }
 }
 
 impl Family for GUIButton {}
 
 impl GUIButton {
+    /// Gets the button's rectangle in absolute logical pixels.
+    fn rect(&self, parent_position: &GUIPosition) -> GUIRect {
+        GUIRect::new(*parent_position + self.position, self.size)
+    }
+
+    /// Makes the outline of the button's rounded rectangle in absolute
+    /// logical pixels, with the vertices in counter clockwise order.
+    /// The outline is shrunk by `inset` on every side, with the corner
+    /// radius shrinking to match.
+    /// This is shared by tessellation and hit-testing so that what's
+    /// clickable is exactly what's drawn.
+    fn make_outline(&self, parent_position: &GUIPosition, inset: &GUILength) -> Vec<GUIPosition> {
+        let tolerance = self.tolerance();
+        let style = self.current_style();
+        let rect = self.rect(parent_position);
+        let radii = style.corner_radii.fit_within(rect.size);
+        let inset = inset.get_length();
+        let (left, top) = (rect.left().get_length(), rect.top().get_length());
+        let (right, bottom) = (rect.right().get_length(), rect.bottom().get_length());
+
+        // Each corner, counter clockwise from the top left, with its radius, the
+        // corner of the rectangle it rounds off, the angle it starts from, and
+        // the quarter circle that it is when it's round.
+        type QuarterArc = fn(GUILength, f64) -> Vec<GUIPosition>;
+        let corners: [(GUILength, [f64; 2], f64, QuarterArc); 4] = [
+            (
+                radii.top_left,
+                [left, top],
+                PI / 2.,
+                arcs::make_top_left_arc,
+            ),
+            (
+                radii.bottom_left,
+                [left, bottom],
+                PI,
+                arcs::make_bottom_left_arc,
+            ),
+            (
+                radii.bottom_right,
+                [right, bottom],
+                1.5 * PI,
+                arcs::make_bottom_right_arc,
+            ),
+            (radii.top_right, [right, top], 0., arcs::make_top_right_arc),
+        ];
+        let mut outline = Vec::new();
+        for (radius, [x, y], start_angle, quarter_arc) in corners {
+            // The centers of the corners are inset from the edges by the radius,
+            // towards the middle of the rectangle.
+            let radius = (radius.get_length() - inset).max(0.);
+            let inward = |edge: f64, opposite: f64| (opposite - edge).signum() * (inset + radius);
+            let center_x = GUILength::from_pixels(x + inward(x, left + right - x));
+            let center_y = GUILength::from_pixels(y + inward(y, top + bottom - y));
+            let radius = GUILength::from_pixels(radius);
+            let corner = match style.corner_style {
+                GUICornerStyle::Round => quarter_arc(radius, tolerance),
+                GUICornerStyle::Chamfer => {
+                    let mut arc = arcs::make_superellipse_arc(radius, start_angle, 1., tolerance);
+                    arc.drain(1..arc.len() - 1);
+                    arc
+                }
+                // Exponents below 1 would make concave corners,
+                // which the fill's fan and hit testing can't handle.
+                GUICornerStyle::Superellipse(exponent) => {
+                    arcs::make_superellipse_arc(radius, start_angle, exponent.max(1.), tolerance)
+                }
+            };
+            outline.extend(widget_utils::translate(corner, &center_x, &center_y));
+        }
+        outline
+    }
+
+    /// Whether the button is drawn as a rounded rectangle rather than tessellated.
+    fn is_drawn_as_rect(&self) -> bool {
+        self.rendering == GUIButtonRendering::Sdf
+            && self.current_style().corner_style == GUICornerStyle::Round
+    }
+
+    /// How far, in logical pixels, the corners can stray from true circles.
+    fn tolerance(&self) -> f64 {
+        arcs::tolerance(self.scale_factor)
+    }
+
+    /// Gets the state that the button is currently in.
+    pub fn state(&self) -> GUIButtonState {
+        if !self.enabled {
+            GUIButtonState::Disabled
+        } else if self.pressed && self.hovered {
+            GUIButtonState::Pressed
+        } else if self.hovered {
+            GUIButtonState::Hovered
+        } else {
+            GUIButtonState::Normal
+        }
+    }
+
+    /// Gets the style for the state that the button is currently in.
+    pub fn current_style(&self) -> &GUIButtonStyle {
+        match self.state() {
+            GUIButtonState::Normal => &self.style,
+            GUIButtonState::Hovered => &self.hovered_style,
+            GUIButtonState::Pressed => &self.pressed_style,
+            GUIButtonState::Disabled => &self.disabled_style,
+        }
+    }
+
+    /// Sets the normal style, and derives the hovered, pressed,
+    /// and disabled styles from it, even those that were set by hand.
+    pub fn set_style(&mut self, style: GUIButtonStyle) {
+        [
+            self.style,
+            self.hovered_style,
+            self.pressed_style,
+            self.disabled_style,
+        ] = GUIButtonStyle::derive_states(style);
+        self.overridden = [false; 3];
+        self.dirty = true;
+    }
+
+    /// Sets how the button looks while the mouse is over it. It no longer
+    /// follows changes to the normal style's colors.
+    pub fn set_hovered_style(&mut self, style: GUIButtonStyle) {
+        self.hovered_style = style;
+        self.overridden[0] = true;
+        self.dirty = true;
+    }
+
+    /// Sets how the button looks while it's being pressed. It no longer
+    /// follows changes to the normal style's colors.
+    pub fn set_pressed_style(&mut self, style: GUIButtonStyle) {
+        self.pressed_style = style;
+        self.overridden[1] = true;
+        self.dirty = true;
+    }
+
+    /// Sets how the button looks while it's disabled. It no longer
+    /// follows changes to the normal style's colors.
+    pub fn set_disabled_style(&mut self, style: GUIButtonStyle) {
+        self.disabled_style = style;
+        self.overridden[2] = true;
+        self.dirty = true;
+    }
+
+    /// Sets how the button's rectangle is drawn.
+    pub fn set_rendering(&mut self, rendering: GUIButtonRendering) {
+        self.rendering = rendering;
+        self.dirty = true;
+    }
+
+    /// Sets the shadow drawn under the button, or takes it away.
+    pub fn set_shadow(&mut self, shadow: Option<GUIShadow>) {
+        self.shadow = shadow;
+        self.dirty = true;
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        self.dirty = true;
+    }
+
+    /// Sets the text of the button.
+    pub fn set_text(&mut self, text: &'static str) {
+        self.text = text;
+        self.dirty = true;
+    }
+
+    /// Sets the height of the button's text.
+    pub fn set_font_size_from_pixels(&mut self, pixels: f64) {
+        self.font_size = GUILength::from_pixels(pixels);
+        self.dirty = true;
+    }
+
+    /// Sets the closure that's called when the button is pressed and
+    /// released with the mouse inside of the button.
+    pub fn set_on_click(&mut self, on_click: impl FnMut() + 'static) {
+        self.on_click = Some(Box::new(on_click));
+    }
+
+    /// Sets the corner radius for all of the button's states.
     pub fn set_radius_from_pixels(&mut self, pixels: f64) {
-        self.radius = GUILength::from_pixels(pixels);
+        self.set_radius_from_length(GUILength::from_pixels(pixels));
     }
 
+    /// Sets the corner radius for all of the button's states.
     pub fn set_radius_from_length(&mut self, length: GUILength) {
-        self.radius = length;
+        self.set_corner_radii(GUICornerRadii::uniform(length));
+    }
+
+    /// Sets the radius of each corner for all of the button's states.
+    pub fn set_corner_radii(&mut self, corner_radii: GUICornerRadii) {
+        for style in self.styles_mut() {
+            style.corner_radii = corner_radii;
+        }
+        self.dirty = true;
+    }
+
+    /// Sets the shape of the corners for all of the button's states.
+    pub fn set_corner_style(&mut self, corner_style: GUICornerStyle) {
+        for style in self.styles_mut() {
+            style.corner_style = corner_style;
+        }
+        self.dirty = true;
+    }
+
+    /// Sets the border width for all of the button's states.
+    pub fn set_border_width_from_pixels(&mut self, pixels: f64) {
+        for style in self.styles_mut() {
+            style.border_width = GUILength::from_pixels(pixels);
+        }
+        self.dirty = true;
+    }
+
+    /// Sets what the button is painted with in its normal style. The hovered,
+    /// pressed, and disabled styles are derived from it again, except for
+    /// those that were set by hand.
+    pub fn set_background(&mut self, fill: GUIFill) {
+        self.style.background = fill;
+        self.derive_states();
+    }
+
+    /// Sets the border color of the button's normal style. The hovered,
+    /// pressed, and disabled styles are derived from it again, except for
+    /// those that were set by hand.
+    pub fn set_border_color(&mut self, color: GUIColor) {
+        self.style.border_color = color;
+        self.derive_states();
+    }
+
+    /// Derives the styles of the states that weren't set by hand from the normal style.
+    fn derive_states(&mut self) {
+        let [_, hovered, pressed, disabled] = GUIButtonStyle::derive_states(self.style.clone());
+        let overridden = self.overridden;
+        let states = [
+            &mut self.hovered_style,
+            &mut self.pressed_style,
+            &mut self.disabled_style,
+        ];
+        for ((state, derived), overridden) in states
+            .into_iter()
+            .zip([hovered, pressed, disabled])
+            .zip(overridden)
+        {
+            if !overridden {
+                *state = derived;
+            }
+        }
+        self.dirty = true;
+    }
+
+    fn styles_mut(&mut self) -> [&mut GUIButtonStyle; 4] {
+        [
+            &mut self.style,
+            &mut self.hovered_style,
+            &mut self.pressed_style,
+            &mut self.disabled_style,
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::guiproperties::GUIColorStop;
+    use crate::guitesting::crate_goldens;
+    use crate::guiwidgets::GUIWindow;
+
+    #[test]
+    fn state_follows_the_mouse() {
+        let mut button = GUIButton::default();
+        assert_eq!(button.state(), GUIButtonState::Normal);
+
+        button.set_hovered(true);
+        assert_eq!(button.state(), GUIButtonState::Hovered);
+        button.set_pressed(true);
+        assert_eq!(button.state(), GUIButtonState::Pressed);
+        // Dragging off of a pressed button shows it as not pressed.
+        button.set_hovered(false);
+        assert_eq!(button.state(), GUIButtonState::Normal);
+
+        button.set_enabled(false);
+        button.set_dirty(false);
+        button.set_hovered(true);
+        assert_eq!(button.state(), GUIButtonState::Disabled);
+        assert!(!button.is_dirty());
+    }
+
+    #[test]
+    fn relative_sizes_are_resolved_when_the_window_is_laid_out() {
+        let mut inner = GUIButton::default();
+        inner.set_size(GUISize::from_lengths(
+            GUILength::from_em(1.),
+            GUILength::from_percent(25.),
+        ));
+        let mut button = GUIButton::default();
+        button.set_font_size_from_pixels(10.);
+        button.set_size(GUISize::from_lengths(
+            GUILength::from_percent(50.),
+            GUILength::from_em(2.),
+        ));
+        button.add_child(Box::new(inner));
+        let mut window = GUIWindow::default();
+        window.add_child(Box::new(button));
+
+        window.set_size(GUISize::from_pixels(400., 300.));
+        window.layout_children();
+        let size = |widget: &dyn Family| {
+            let size = widget.get_size();
+            (size.width.get_length(), size.height.get_length())
+        };
+        // The button's ems are the window's, and the inner button's are the button's.
+        assert_eq!(size(window.children[0].as_ref()), (200., 32.));
+        assert_eq!(
+            size(window.children[0].get_children()[0].as_ref()),
+            (10., 8.)
+        );
+
+        window.set_size(GUISize::from_pixels(600., 300.));
+        window.layout_children();
+        assert_eq!(size(window.children[0].as_ref()), (300., 32.));
+    }
+
+    #[test]
+    fn current_style_is_used_for_tessellation() {
+        let mut button = GUIButton::default();
+        button.hovered_style.background =
+            GUIFill::Solid(GUIColor::from_rgba_u8u8u8u8(0, 255, 0, 255));
+        button.set_hovered(true);
+        let origin = GUIPosition::from_pixels(0., 0.);
+
+        assert_eq!(
+            button.get_rects(&origin)[0].background,
+            button.hovered_style.background
+        );
+        button.set_rendering(GUIButtonRendering::Tessellated);
+        let (vertices, _) = button.get_vertices_and_indices(&origin, 0);
+        assert!(!vertices.is_empty());
+        assert!(vertices.iter().all(|v| v.color == [0., 1., 0., 1.]));
+    }
+
+    #[test]
+    fn buttons_are_drawn_as_one_rect_unless_they_cant_be() {
+        let mut button = GUIButton::default();
+        let origin = GUIPosition::from_pixels(0., 0.);
+        assert_eq!(button.get_rects(&origin).len(), 1);
+        assert!(button.get_vertices_and_indices(&origin, 0).0.is_empty());
+
+        // Only round corners can be drawn as a rect.
+        button.set_corner_style(GUICornerStyle::Chamfer);
+        assert!(button.get_rects(&origin).is_empty());
+        assert!(!button.get_vertices_and_indices(&origin, 0).0.is_empty());
+    }
+
+    #[test]
+    fn setting_colors_keeps_the_other_states_styles() {
+        let mut button = GUIButton::default();
+        let green = GUIColor::from_rgba_u8u8u8u8(0, 255, 0, 255);
+        button.set_hovered_style(GUIButtonStyle {
+            background: GUIFill::Solid(green),
+            ..button.hovered_style.clone()
+        });
+
+        let blue = GUIColor::from_rgba_u8u8u8u8(0, 0, 255, 255);
+        button.set_background_color(blue);
+        button.set_border_color(blue);
+        assert_eq!(button.style.background, GUIFill::Solid(blue));
+        assert_eq!(button.style.border_color, blue);
+        assert_eq!(button.hovered_style.background, GUIFill::Solid(green));
+        // The pressed style was never set by hand, so it follows the new color.
+        let [_, _, pressed, _] = GUIButtonStyle::derive_states(button.style.clone());
+        assert_eq!(button.pressed_style, pressed);
+        assert_ne!(
+            button.pressed_style.background,
+            GUIButton::default().pressed_style.background
+        );
+    }
+
+    #[test]
+    fn text_is_centered_in_the_button() {
+        let mut button = GUIButton::default();
+        button.set_position_from_pixels(10., 20.);
+
+        let texts = button.get_text(&GUIPosition::from_pixels(5., 5.));
+
+        assert_eq!(texts.len(), 1);
+        assert_eq!(texts[0].text, "Button");
+        assert_eq!(texts[0].position, GUIPosition::from_pixels(15., 25.));
+        assert_eq!(texts[0].horizontal_alignment, GUIAlignment::Center);
+        assert_eq!(texts[0].vertical_alignment, GUIAlignment::Center);
+    }
+
+    #[test]
+    fn border_is_drawn_around_the_fill() {
+        let mut button = GUIButton::default();
+        button.set_rendering(GUIButtonRendering::Tessellated);
+        button.set_border_width_from_pixels(2.);
+        let origin = GUIPosition::from_pixels(0., 0.);
+
+        let (vertices, indices) = button.get_vertices_and_indices(&origin, 10);
+        let outline_length = button
+            .make_outline(&origin, &GUILength::from_pixels(0.))
+            .len();
+
+        // The fill comes first, with the border stroked over it.
+        let (fill, border) = vertices.split_at(outline_length);
+        assert!(fill.iter().all(|v| v.color == vertices[0].color));
+        assert!(!border.is_empty());
+        assert!(border.iter().all(|v| v.color != vertices[0].color));
+        assert!(indices
+            .iter()
+            .all(|i| *i >= 10 && ((*i - 10) as usize) < vertices.len()));
+    }
+
+    #[test]
+    fn corners_are_tessellated_finely_enough_for_the_display() {
+        let vertex_count = |radius: f64, scale_factor: f64| {
+            let mut button = GUIButton::default();
+            button.set_rendering(GUIButtonRendering::Tessellated);
+            button.set_radius_from_pixels(radius);
+            let mut guiwindow = GUIWindow::default();
+            guiwindow.logical_scale = Some(scale_factor);
+            guiwindow.add_child(Box::new(button));
+            guiwindow.layout_children();
+            guiwindow.children[0]
+                .get_vertices_and_indices(&GUIPosition::from_pixels(0., 0.), 0)
+                .0
+                .len()
+        };
+        assert!(vertex_count(4., 1.) < vertex_count(25., 1.));
+        assert!(vertex_count(25., 1.) < vertex_count(50., 1.));
+        assert!(vertex_count(25., 1.) < vertex_count(25., 2.));
+    }
+
+    #[test]
+    fn corners_can_each_have_their_own_radius() {
+        // A tab, which only rounds its top corners.
+        let mut button = GUIButton::default();
+        button.set_size(GUISize::from_pixels(100., 40.));
+        button.set_corner_radii(GUICornerRadii::from_pixels(20., 20., 0., 0.));
+        let origin = GUIPosition::from_pixels(0., 0.);
+        let hit = |button: &GUIButton, x, y| {
+            button.contains_point(&origin, &GUIPosition::from_pixels(x, y))
+        };
+        assert!(!hit(&button, 2., 2.));
+        assert!(!hit(&button, 98., 2.));
+        assert!(hit(&button, 1., 39.));
+        assert!(hit(&button, 99., 39.));
+
+        button.set_corner_style(GUICornerStyle::Chamfer);
+        let outline = button.make_outline(&origin, &GUILength::from_pixels(0.));
+        assert_eq!(outline.len(), 8);
+        assert!(!hit(&button, 9., 9.));
+        assert!(hit(&button, 11., 11.));
+
+        // A squircle's corners are squarer than a circle's.
+        button.set_corner_style(GUICornerStyle::Superellipse(4.));
+        assert!(hit(&button, 4., 4.));
+    }
+
+    #[test]
+    fn concave_superellipses_are_drawn_as_chamfers() {
+        let mut button = GUIButton::default();
+        button.set_rendering(GUIButtonRendering::Tessellated);
+        button.set_corner_style(GUICornerStyle::Superellipse(0.5));
+        let (vertices, indices) =
+            button.get_vertices_and_indices(&GUIPosition::from_pixels(0., 0.), 0);
+
+        // The area that the fan's triangles cover, counting overlaps twice.
+        let covered: f64 = indices
+            .chunks(3)
+            .map(|triangle| {
+                let [a, b, c] = [0, 1, 2].map(|i| vertices[triangle[i] as usize].position);
+                ((b[0] - a[0]) * (c[1] - a[1]) - (c[0] - a[0]) * (b[1] - a[1])).abs() as f64 / 2.
+            })
+            .sum();
+        // The 200 by 100 button with its 25 pixel corners cut off.
+        assert!((covered - (200. * 100. - 4. * 25. * 25. / 2.)).abs() < 1.);
+        let origin = GUIPosition::from_pixels(0., 0.);
+        assert!(!button.contains_point(&origin, &GUIPosition::from_pixels(5., 5.)));
+        assert!(button.contains_point(&origin, &GUIPosition::from_pixels(15., 15.)));
+    }
+
+    #[test]
+    fn corner_styles_match_golden() {
+        let mut guiwindow = GUIWindow::default();
+        guiwindow.set_size(GUISize::from_pixels(160., 80.));
+        for (i, corner_style) in [
+            GUICornerStyle::Round,
+            GUICornerStyle::Chamfer,
+            GUICornerStyle::Superellipse(5.),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let mut button = GUIButton::default();
+            button.set_text("");
+            button.set_size(GUISize::from_pixels(44., 60.));
+            button.set_position_from_pixels(8. + 50. * i as f64, 10.);
+            button.set_corner_radii(GUICornerRadii::from_pixels(22., 22., 8., 0.));
+            button.set_corner_style(corner_style);
+            guiwindow.add_child(Box::new(button));
+        }
+        crate_goldens().assert_window("button_corner_styles", guiwindow);
+    }
+
+    #[test]
+    fn gradient_backgrounds_match_golden() {
+        let mut guiwindow = GUIWindow::default();
+        guiwindow.set_size(GUISize::from_pixels(160., 80.));
+        let color = GUIColor::from_rgba_u8u8u8u8;
+        let stops = vec![
+            GUIColorStop::new(0., color(230, 60, 40, 255)),
+            GUIColorStop::new(0.5, color(250, 210, 40, 255)),
+            GUIColorStop::new(1., color(40, 90, 200, 0)),
+        ];
+        let fills = [
+            GUIFill::LinearGradient {
+                start: GUIPosition::from_pixels(0., 0.),
+                end: GUIPosition::from_lengths(
+                    GUILength::from_percent(100.),
+                    GUILength::from_percent(0.),
+                ),
+                stops: stops.clone(),
+            },
+            GUIFill::RadialGradient {
+                center: GUIPosition::from_lengths(
+                    GUILength::from_percent(50.),
+                    GUILength::from_percent(50.),
+                ),
+                radius: GUILength::from_pixels(30.),
+                stops,
+            },
+        ];
+        for (i, fill) in fills.into_iter().enumerate() {
+            let mut button = GUIButton::default();
+            button.set_text("");
+            button.set_size(GUISize::from_pixels(64., 60.));
+            button.set_position_from_pixels(10. + 76. * i as f64, 10.);
+            button.set_radius_from_pixels(30.);
+            button.set_background(fill);
+            guiwindow.add_child(Box::new(button));
+        }
+        crate_goldens().assert_window("button_gradients", guiwindow);
+    }
+
+    #[test]
+    fn shadows_match_golden() {
+        let mut guiwindow = GUIWindow::default();
+        guiwindow.set_size(GUISize::from_pixels(160., 80.));
+        let shadows = [
+            // A drop shadow under a button drawn as a rect.
+            (
+                GUICornerStyle::Round,
+                GUIShadow::new(
+                    GUIPosition::from_pixels(3., 6.),
+                    GUILength::from_pixels(10.),
+                    GUILength::from_pixels(0.),
+                    GUIColor::from_rgba_u8u8u8u8(0, 0, 0, 160),
+                ),
+            ),
+            // A glow around a tessellated button.
+            (
+                GUICornerStyle::Chamfer,
+                GUIShadow::new(
+                    GUIPosition::from_pixels(0., 0.),
+                    GUILength::from_pixels(12.),
+                    GUILength::from_pixels(2.),
+                    GUIColor::from_rgba_u8u8u8u8(255, 220, 60, 255),
+                ),
+            ),
+        ];
+        for (i, (corner_style, shadow)) in shadows.into_iter().enumerate() {
+            let mut button = GUIButton::default();
+            button.set_text("");
+            button.set_size(GUISize::from_pixels(50., 44.));
+            button.set_position_from_pixels(20. + 76. * i as f64, 14.);
+            button.set_radius_from_pixels(12.);
+            button.set_corner_style(corner_style);
+            button.set_shadow(Some(shadow));
+            guiwindow.add_child(Box::new(button));
+        }
+        crate_goldens().assert_window("button_shadows", guiwindow);
+    }
+
+    /// A window with a single button that has no text, so that the
+    /// goldens don't depend on which fonts are installed.
+    fn window_with_button(button: GUIButton) -> GUIWindow {
+        let mut guiwindow = GUIWindow::default();
+        guiwindow.set_size(GUISize::from_pixels(160., 80.));
+        guiwindow.add_child(Box::new(button));
+        guiwindow
+    }
+
+    #[test]
+    fn default_button_matches_golden() {
+        let mut button = GUIButton::default();
+        button.set_text("");
+        button.set_position_from_pixels(20., 10.);
+        crate_goldens().assert_window("button_default", window_with_button(button));
+    }
+
+    #[test]
+    fn round_bordered_button_matches_golden() {
+        let mut button = GUIButton::default();
+        button.set_text("");
+        button.set_size(GUISize::from_pixels(120., 60.));
+        button.set_position_from_pixels(20., 10.);
+        button.set_radius_from_pixels(30.);
+        button.set_border_width_from_pixels(4.);
+        crate_goldens().assert_window("button_round_bordered", window_with_button(button));
+    }
+
+    #[test]
+    fn overlapping_translucent_buttons_match_golden() {
+        let translucent = |x: f64, r: u8, g: u8, b: u8| {
+            let mut button = GUIButton::default();
+            button.set_text("");
+            button.set_size(GUISize::from_pixels(90., 60.));
+            button.set_position_from_pixels(x, 10.);
+            button.set_background_color(GUIColor::from_rgba_u8u8u8u8(r, g, b, 128));
+            button
+        };
+        let mut guiwindow = window_with_button(translucent(10., 255, 0, 0));
+        guiwindow.add_child(Box::new(translucent(50., 0, 0, 255)));
+        crate_goldens().assert_window("button_translucent_overlap", guiwindow);
     }
 }
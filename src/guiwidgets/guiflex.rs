@@ -0,0 +1,532 @@
+use uuid::Uuid;
+
+use crate::guiprocessing::vertices::Vertex;
+use crate::guiproperties::guiposition::{
+    GUIAxis, GUILayoutContext, GUILength, GUIPosition, GUIRect, GUISize,
+};
+use crate::guiproperties::guitraits::{Child, Container, Family, Parent, Widget};
+use crate::guiproperties::{GUIBackground, GUIColor, GUIFill, GUIRoundedRect};
+use crate::guiwidgets::widget_utils;
+
+/// The axis that a flex lays its children out along.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GUIFlexDirection {
+    /// Left to right.
+    Row,
+    /// Top to bottom.
+    Column,
+}
+
+/// How the space that's left over along the main axis is shared out.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GUIJustifyContent {
+    Start,
+    Center,
+    End,
+    /// All of the space goes between the children, none at the ends.
+    SpaceBetween,
+    /// Each child gets the same space on either side of it.
+    SpaceAround,
+    /// The space between the children and at the ends is the same.
+    SpaceEvenly,
+}
+
+/// Where children sit along the cross axis.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GUIAlignItems {
+    Start,
+    Center,
+    End,
+    /// Children are as long as the flex is, less its padding.
+    Stretch,
+}
+
+/// How a child of a flex is sized along the main axis.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GUIFlexItem {
+    /// The size the child has before it grows or shrinks.
+    pub base_size: GUISize,
+    /// How much of the left over space the child takes, relative to its siblings.
+    pub grow: f64,
+    /// How much the child gives up when there isn't enough space, relative
+    /// to its siblings. Larger children give up proportionally more.
+    pub shrink: f64,
+}
+
+impl GUIFlexItem {
+    /// Returns an item that neither grows nor shrinks away from the given size.
+    pub fn new(base_size: GUISize) -> Self {
+        Self {
+            base_size,
+            grow: 0.,
+            shrink: 1.,
+        }
+    }
+}
+
+/// Lays its children out in a row or a column. Children are positioned
+/// and sized by the flex, so their own positions are overwritten.
+pub struct GUIFlex {
+    /// The size of the flex. Ignored if fill_parent is set.
+    pub size: GUISize,
+    /// The location of the flex.
+    pub position: GUIPosition,
+    /// Whether the flex takes on the size of the area it's given: the window
+    /// for a top level flex, or the space that its parent allots it.
+    pub fill_parent: bool,
+    /// The axis that the children are laid out along.
+    pub direction: GUIFlexDirection,
+    /// The space between neighbouring children.
+    pub gap: GUILength,
+    /// The space between the edges of the flex and its children.
+    pub padding: GUILength,
+    pub justify_content: GUIJustifyContent,
+    pub align_items: GUIAlignItems,
+    /// What's painted behind the children, and the shadow under them.
+    pub background: GUIBackground,
+    /// A list of child widgets
+    pub children: Vec<Box<dyn Family>>,
+    /// How each child is sized, in the same order as the children.
+    /// Children without an item are left at their own size.
+    pub items: Vec<GUIFlexItem>,
+    /// The human readable name of the flex
+    pub name: &'static str,
+    pub id: u128,
+    dirty: bool,
+}
+
+const DEFAULT_FLEX_NAME: &str = "this is the default name of the flex";
+
+impl Default for GUIFlex {
+    // Returns a flex with all of the default values.
+    fn default() -> GUIFlex {
+        GUIFlex {
+            size: GUISize::from_pixels(200., 200.),
+            position: GUIPosition::from_pixels(0., 0.),
+            fill_parent: false,
+            direction: GUIFlexDirection::Row,
+            gap: GUILength::from_pixels(0.),
+            padding: GUILength::from_pixels(0.),
+            justify_content: GUIJustifyContent::Start,
+            align_items: GUIAlignItems::Start,
+            background: GUIBackground::default(),
+            children: Vec::new(),
+            items: Vec::new(),
+            name: DEFAULT_FLEX_NAME,
+            id: Uuid::new_v4().as_u128(),
+            dirty: true,
+        }
+    }
+}
+
+impl Widget for GUIFlex {
+    fn is_rendered(&self) -> bool {
+        true
+    }
+
+    /// Set the size (width and height) of the flex in units of logical pixels.
+    fn set_size(&mut self, size: GUISize) {
+        self.size = size;
+        self.dirty = true;
+    }
+
+    fn get_size(&self) -> GUISize {
+        self.size
+    }
+
+    // Set background color of the flex.
+    fn set_background_color(&mut self, color: GUIColor) {
+        self.background.fill = GUIFill::Solid(color);
+        self.dirty = true;
+    }
+
+    fn set_id(&mut self, id: u128) {
+        self.id = id;
+    }
+
+    fn get_id(&self) -> u128 {
+        self.id
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    fn set_dirty(&mut self, dirty: bool) {
+        self.dirty = dirty;
+    }
+}
+
+impl Parent for GUIFlex {
+    /// Adds a child to the end of the flex. The size that it has
+    /// now is the size it'll have before growing or shrinking.
+    fn add_child(&mut self, child: Box<dyn Family>) {
+        self.add_child_with_item(GUIFlexItem::new(child.get_size()), child);
+    }
+
+    fn set_children(&mut self, children: Vec<Box<dyn Family>>) {
+        self.items = children
+            .iter()
+            .map(|child| GUIFlexItem::new(child.get_size()))
+            .collect();
+        self.children = children;
+        self.dirty = true;
+    }
+
+    /// Gets the children.
+    fn get_children(&self) -> &Vec<Box<dyn Family>> {
+        &self.children
+    }
+
+    /// Gets the children mutably.
+    fn get_children_mut(&mut self) -> &mut Vec<Box<dyn Family>> {
+        &mut self.children
+    }
+
+    /// Positions and sizes the children, then lays out their own children.
+    /// Relative padding is resolved against the flex's width, and relative
+    /// gaps and base sizes are resolved against the space inside the padding.
+    fn layout(&mut self, context: &GUILayoutContext) {
+        if self.fill_parent && self.size != context.parent_size {
+            self.set_size(context.parent_size);
+        }
+
+        let row = self.direction == GUIFlexDirection::Row;
+        // Splits a size into its length along the main axis and along the cross axis.
+        let axes = |size: &GUISize| {
+            let (width, height) = (size.width.get_length(), size.height.get_length());
+            if row {
+                (width, height)
+            } else {
+                (height, width)
+            }
+        };
+        let padding = self
+            .padding
+            .resolve(GUIAxis::Horizontal, &context.with_parent(self.size, None));
+        // The area inside of the padding, relative to the flex.
+        let content = GUIRect::new(GUIPosition::from_pixels(0., 0.), self.size).inset(padding);
+        let inner_size = content.size;
+        let inner_context = context.with_parent(inner_size, None);
+        let main_axis = if row {
+            GUIAxis::Horizontal
+        } else {
+            GUIAxis::Vertical
+        };
+        let gap = self.gap.resolve(main_axis, &inner_context).get_length();
+        let (main, cross) = axes(&inner_size);
+
+        let items: Vec<GUIFlexItem> = self
+            .children
+            .iter()
+            .enumerate()
+            .map(|(index, child)| {
+                self.items
+                    .get(index)
+                    .copied()
+                    .unwrap_or_else(|| GUIFlexItem::new(child.get_size()))
+            })
+            .collect();
+        let count = items.len();
+        if count == 0 {
+            return;
+        }
+
+        let base_sizes: Vec<(f64, f64)> = items
+            .iter()
+            .map(|item| axes(&item.base_size.resolve(&inner_context)))
+            .collect();
+        let mut lengths: Vec<f64> = base_sizes.iter().map(|base_size| base_size.0).collect();
+        let gaps = gap * (count - 1) as f64;
+        let free = main - gaps - lengths.iter().sum::<f64>();
+        let total_grow: f64 = items.iter().map(|item| item.grow).sum();
+        let total_shrink: f64 = items
+            .iter()
+            .zip(lengths.iter())
+            .map(|(item, length)| item.shrink * length)
+            .sum();
+        if free > 0. && total_grow > 0. {
+            for (length, item) in lengths.iter_mut().zip(items.iter()) {
+                *length += free * item.grow / total_grow;
+            }
+        } else if free < 0. && total_shrink > 0. {
+            for (length, item) in lengths.iter_mut().zip(items.iter()) {
+                *length = (*length + free * item.shrink * *length / total_shrink).max(0.);
+            }
+        }
+
+        let left_over = (main - gaps - lengths.iter().sum::<f64>()).max(0.);
+        let (start, spacing) = match self.justify_content {
+            GUIJustifyContent::Start => (0., 0.),
+            GUIJustifyContent::Center => (left_over / 2., 0.),
+            GUIJustifyContent::End => (left_over, 0.),
+            GUIJustifyContent::SpaceBetween if count > 1 => (0., left_over / (count - 1) as f64),
+            GUIJustifyContent::SpaceBetween => (0., 0.),
+            GUIJustifyContent::SpaceAround => {
+                let space = left_over / count as f64;
+                (space / 2., space)
+            }
+            GUIJustifyContent::SpaceEvenly => {
+                let space = left_over / (count + 1) as f64;
+                (space, space)
+            }
+        };
+
+        let mut offset = start;
+        let children = self.children.iter_mut().zip(base_sizes).zip(lengths);
+        for ((child, (_, base_cross)), length) in children {
+            let (cross_length, cross_offset) = match self.align_items {
+                GUIAlignItems::Start => (base_cross, 0.),
+                GUIAlignItems::Center => (base_cross, (cross - base_cross) / 2.),
+                GUIAlignItems::End => (base_cross, cross - base_cross),
+                GUIAlignItems::Stretch => (cross, 0.),
+            };
+            let (size, position) = if row {
+                (
+                    GUISize::from_pixels(length, cross_length),
+                    GUIPosition::from_pixels(offset, cross_offset),
+                )
+            } else {
+                (
+                    GUISize::from_pixels(cross_length, length),
+                    GUIPosition::from_pixels(cross_offset, offset),
+                )
+            };
+            widget_utils::place_child(child, content.position + position, size, context);
+            offset += length + gap + spacing;
+        }
+    }
+}
+
+impl Child for GUIFlex {
+    /// Tessellates the flex's background, if it has one.
+    fn get_vertices_and_indices(
+        &self,
+        parent_position: &GUIPosition,
+        indice_offset: u32,
+    ) -> (Vec<Vertex>, Vec<u32>) {
+        let rect = GUIRect::new(*parent_position + self.position, self.size);
+        self.background
+            .get_vertices_and_indices(&rect, self.id, indice_offset)
+    }
+
+    fn get_fills(&self, parent_position: &GUIPosition) -> Vec<GUIFill> {
+        let rect = GUIRect::new(*parent_position + self.position, self.size);
+        self.background.get_fills(&rect)
+    }
+
+    /// Gets the flex's shadow, if it has one.
+    fn get_rects(&self, parent_position: &GUIPosition) -> Vec<GUIRoundedRect> {
+        let rect = GUIRect::new(*parent_position + self.position, self.size);
+        self.background.get_rects(&rect)
+    }
+
+    fn get_position(&self) -> GUIPosition {
+        self.position
+    }
+
+    fn set_position_from_pixels(&mut self, x: f64, y: f64) {
+        self.set_position_from_position(GUIPosition::from_pixels(x, y));
+    }
+
+    fn set_position_from_lengths(&mut self, x: GUILength, y: GUILength) {
+        self.set_position_from_position(GUIPosition::from_lengths(x, y));
+    }
+
+    fn set_position_from_position(&mut self, position: GUIPosition) {
+        self.position = position;
+        self.dirty = true;
+    }
+}
+
+impl Container for GUIFlex {
+    fn get_background_mut(&mut self) -> &mut GUIBackground {
+        &mut self.background
+    }
+}
+
+impl Family for GUIFlex {}
+
+impl GUIFlex {
+    /// Returns an empty flex that lays its children out along the given axis.
+    pub fn new(direction: GUIFlexDirection) -> Self {
+        Self {
+            direction,
+            ..Default::default()
+        }
+    }
+
+    /// Adds a child to the end of the flex, sized by the given item.
+    pub fn add_child_with_item(&mut self, item: GUIFlexItem, child: Box<dyn Family>) {
+        // Keep the items lined up with the children, even if children
+        // were added through get_children_mut.
+        self.items.truncate(self.children.len());
+        for child in self.children[self.items.len()..].iter() {
+            self.items.push(GUIFlexItem::new(child.get_size()));
+        }
+        self.items.push(item);
+        self.children.push(child);
+        self.dirty = true;
+    }
+
+    /// Adds a child to the end of the flex that takes the given share of the
+    /// left over space, and gives up the given share when there isn't enough.
+    pub fn add_child_with_grow(&mut self, child: Box<dyn Family>, grow: f64, shrink: f64) {
+        let item = GUIFlexItem {
+            base_size: child.get_size(),
+            grow,
+            shrink,
+        };
+        self.add_child_with_item(item, child);
+    }
+
+    /// Sets the space between neighbouring children in logical pixels.
+    pub fn set_gap_from_pixels(&mut self, pixels: f64) {
+        self.gap = GUILength::from_pixels(pixels);
+        self.dirty = true;
+    }
+
+    /// Sets the space between the edges of the flex and its children in logical pixels.
+    pub fn set_padding_from_pixels(&mut self, pixels: f64) {
+        self.padding = GUILength::from_pixels(pixels);
+        self.dirty = true;
+    }
+
+    /// Sets how the left over space along the main axis is shared out.
+    pub fn set_justify_content(&mut self, justify_content: GUIJustifyContent) {
+        self.justify_content = justify_content;
+        self.dirty = true;
+    }
+
+    /// Sets where children sit along the cross axis.
+    pub fn set_align_items(&mut self, align_items: GUIAlignItems) {
+        self.align_items = align_items;
+        self.dirty = true;
+    }
+
+    /// Sets whether the flex takes on the size of the area it's given.
+    pub fn set_fill_parent(&mut self, fill_parent: bool) {
+        self.fill_parent = fill_parent;
+        self.dirty = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::guiwidgets::{GUIButton, GUIWindow};
+
+    fn button(width: f64, height: f64) -> Box<dyn Family> {
+        let mut button = GUIButton::default();
+        button.set_size(GUISize::from_pixels(width, height));
+        Box::new(button)
+    }
+
+    /// Returns the x, y, width and height of each child.
+    fn frames(flex: &GUIFlex) -> Vec<[f64; 4]> {
+        flex.children
+            .iter()
+            .map(|child| {
+                let (position, size) = (child.get_position(), child.get_size());
+                [
+                    position.x.get_length(),
+                    position.y.get_length(),
+                    size.width.get_length(),
+                    size.height.get_length(),
+                ]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn rows_are_laid_out_with_gap_padding_and_justification() {
+        let mut flex = GUIFlex::new(GUIFlexDirection::Row);
+        flex.set_size(GUISize::from_pixels(200., 100.));
+        flex.set_padding_from_pixels(10.);
+        flex.set_gap_from_pixels(20.);
+        flex.set_align_items(GUIAlignItems::Center);
+        flex.add_child(button(40., 20.));
+        flex.add_child(button(60., 40.));
+
+        flex.layout(&GUILayoutContext::new(GUISize::from_pixels(500., 500.), 1.));
+        assert_eq!(
+            frames(&flex),
+            vec![[10., 40., 40., 20.], [70., 30., 60., 40.]]
+        );
+
+        flex.set_justify_content(GUIJustifyContent::SpaceBetween);
+        flex.layout(&GUILayoutContext::new(GUISize::from_pixels(500., 500.), 1.));
+        assert_eq!(
+            frames(&flex),
+            vec![[10., 40., 40., 20.], [130., 30., 60., 40.]]
+        );
+
+        flex.set_justify_content(GUIJustifyContent::SpaceEvenly);
+        flex.layout(&GUILayoutContext::new(GUISize::from_pixels(500., 500.), 1.));
+        assert_eq!(
+            frames(&flex),
+            vec![[30., 40., 40., 20.], [110., 30., 60., 40.]]
+        );
+    }
+
+    #[test]
+    fn columns_grow_shrink_and_stretch() {
+        let mut flex = GUIFlex::new(GUIFlexDirection::Column);
+        flex.set_size(GUISize::from_pixels(100., 200.));
+        flex.set_align_items(GUIAlignItems::Stretch);
+        flex.add_child(button(10., 50.));
+        flex.add_child_with_grow(button(10., 50.), 1., 1.);
+        flex.add_child_with_grow(button(10., 50.), 3., 1.);
+
+        flex.layout(&GUILayoutContext::new(GUISize::from_pixels(500., 500.), 1.));
+        assert_eq!(
+            frames(&flex),
+            vec![
+                [0., 0., 100., 50.],
+                [0., 50., 100., 62.5],
+                [0., 112.5, 100., 87.5]
+            ]
+        );
+
+        // Growing doesn't change the base sizes, so they shrink from where they started.
+        flex.set_size(GUISize::from_pixels(100., 120.));
+        flex.layout(&GUILayoutContext::new(GUISize::from_pixels(500., 500.), 1.));
+        assert_eq!(
+            frames(&flex),
+            vec![
+                [0., 0., 100., 40.],
+                [0., 40., 100., 40.],
+                [0., 80., 100., 40.]
+            ]
+        );
+    }
+
+    #[test]
+    fn flexes_that_fill_the_window_are_laid_out_again_when_it_resizes() {
+        let mut flex = GUIFlex::new(GUIFlexDirection::Row);
+        flex.set_fill_parent(true);
+        flex.set_justify_content(GUIJustifyContent::End);
+        flex.add_child(button(50., 20.));
+        let mut window = GUIWindow::default();
+        window.add_child(Box::new(flex));
+
+        window.set_size(GUISize::from_pixels(300., 100.));
+        window.layout_children();
+        let child = &window.children[0].get_children()[0];
+        assert_eq!(child.get_position(), GUIPosition::from_pixels(250., 0.));
+
+        window.set_size(GUISize::from_pixels(400., 100.));
+        window.layout_children();
+        let flex = &window.children[0];
+        assert_eq!(flex.get_size(), GUISize::from_pixels(400., 100.));
+        let child = &flex.get_children()[0];
+        assert_eq!(child.get_position(), GUIPosition::from_pixels(350., 0.));
+
+        // Laying out again without a change doesn't dirty anything.
+        window.children[0].set_dirty(false);
+        window.children[0].get_children_mut()[0].set_dirty(false);
+        window.layout_children();
+        assert!(!window.children[0].is_dirty());
+        assert!(!window.children[0].get_children()[0].is_dirty());
+    }
+}
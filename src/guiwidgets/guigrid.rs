@@ -0,0 +1,522 @@
+use uuid::Uuid;
+
+use crate::guiprocessing::vertices::Vertex;
+use crate::guiproperties::guiposition::{
+    GUIAxis, GUILayoutContext, GUILength, GUIPosition, GUIRect, GUISize,
+};
+use crate::guiproperties::guitraits::{Child, Container, Family, Parent, Widget};
+use crate::guiproperties::{GUIBackground, GUIColor, GUIFill, GUIRoundedRect};
+use crate::guiwidgets::widget_utils;
+
+/// How long a row or column of a grid is.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GUITrack {
+    /// Always the given length.
+    Fixed(GUILength),
+    /// A share of whatever's left once the other tracks and gaps are taken
+    /// out, relative to the other fractional tracks.
+    Fraction(f64),
+    /// As long as the largest child that sits in only this track.
+    Auto,
+}
+
+/// The cells of a grid that a child covers.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GUIGridCell {
+    /// The row of the top left cell, starting from 0.
+    pub row: usize,
+    /// The column of the top left cell, starting from 0.
+    pub column: usize,
+    /// The number of rows that the child covers.
+    pub row_span: usize,
+    /// The number of columns that the child covers.
+    pub column_span: usize,
+    /// The size the child has before it's stretched to fill its cells,
+    /// which is what auto tracks are sized to fit.
+    pub base_size: GUISize,
+}
+
+impl GUIGridCell {
+    /// Returns a cell that covers a single row and column.
+    pub fn new(row: usize, column: usize, base_size: GUISize) -> Self {
+        Self {
+            row,
+            column,
+            row_span: 1,
+            column_span: 1,
+            base_size,
+        }
+    }
+
+    /// The index of the row after the last row that the cell covers.
+    fn row_end(&self) -> usize {
+        self.row + self.row_span.max(1)
+    }
+
+    /// The index of the column after the last column that the cell covers.
+    fn column_end(&self) -> usize {
+        self.column + self.column_span.max(1)
+    }
+}
+
+/// Lays its children out in rows and columns. Each child is stretched to
+/// fill the cells it covers, so their own sizes and positions are overwritten.
+/// Children in rows or columns past the ones that are given sit in auto tracks.
+pub struct GUIGrid {
+    /// The size of the grid. Ignored if fill_parent is set.
+    pub size: GUISize,
+    /// The location of the grid.
+    pub position: GUIPosition,
+    /// Whether the grid takes on the size of the area it's given: the window
+    /// for a top level grid, or the space that its parent allots it.
+    pub fill_parent: bool,
+    /// The rows, from top to bottom.
+    pub rows: Vec<GUITrack>,
+    /// The columns, from left to right.
+    pub columns: Vec<GUITrack>,
+    /// The space between neighbouring rows.
+    pub row_gap: GUILength,
+    /// The space between neighbouring columns.
+    pub column_gap: GUILength,
+    /// The space between the edges of the grid and its cells.
+    pub padding: GUILength,
+    /// What's painted behind the cells, and the shadow under them.
+    pub background: GUIBackground,
+    /// A list of child widgets
+    pub children: Vec<Box<dyn Family>>,
+    /// The cells that each child covers, in the same order as the children.
+    pub cells: Vec<GUIGridCell>,
+    /// The human readable name of the grid
+    pub name: &'static str,
+    pub id: u128,
+    dirty: bool,
+}
+
+const DEFAULT_GRID_NAME: &str = "this is the default name of the grid";
+
+impl Default for GUIGrid {
+    // Returns a grid with all of the default values.
+    fn default() -> GUIGrid {
+        GUIGrid {
+            size: GUISize::from_pixels(200., 200.),
+            position: GUIPosition::from_pixels(0., 0.),
+            fill_parent: false,
+            rows: Vec::new(),
+            columns: vec![GUITrack::Fraction(1.)],
+            row_gap: GUILength::from_pixels(0.),
+            column_gap: GUILength::from_pixels(0.),
+            padding: GUILength::from_pixels(0.),
+            background: GUIBackground::default(),
+            children: Vec::new(),
+            cells: Vec::new(),
+            name: DEFAULT_GRID_NAME,
+            id: Uuid::new_v4().as_u128(),
+            dirty: true,
+        }
+    }
+}
+
+impl Widget for GUIGrid {
+    fn is_rendered(&self) -> bool {
+        true
+    }
+
+    /// Set the size (width and height) of the grid in units of logical pixels.
+    fn set_size(&mut self, size: GUISize) {
+        self.size = size;
+        self.dirty = true;
+    }
+
+    fn get_size(&self) -> GUISize {
+        self.size
+    }
+
+    // Set background color of the grid.
+    fn set_background_color(&mut self, color: GUIColor) {
+        self.background.fill = GUIFill::Solid(color);
+        self.dirty = true;
+    }
+
+    fn set_id(&mut self, id: u128) {
+        self.id = id;
+    }
+
+    fn get_id(&self) -> u128 {
+        self.id
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    fn set_dirty(&mut self, dirty: bool) {
+        self.dirty = dirty;
+    }
+}
+
+impl Parent for GUIGrid {
+    /// Adds a child to the cell after the last child's, moving on to
+    /// the next row once the end of the columns is reached.
+    fn add_child(&mut self, child: Box<dyn Family>) {
+        let (row, column) = match self.cells.last() {
+            Some(last) if last.column_end() < self.columns.len() => (last.row, last.column_end()),
+            Some(last) => (last.row + 1, 0),
+            None => (0, 0),
+        };
+        self.add_child_with_cell(GUIGridCell::new(row, column, child.get_size()), child);
+    }
+
+    /// Sets the children, placing them in order from the top left.
+    fn set_children(&mut self, children: Vec<Box<dyn Family>>) {
+        self.children.clear();
+        self.cells.clear();
+        for child in children {
+            self.add_child(child);
+        }
+        self.dirty = true;
+    }
+
+    /// Gets the children.
+    fn get_children(&self) -> &Vec<Box<dyn Family>> {
+        &self.children
+    }
+
+    /// Gets the children mutably.
+    fn get_children_mut(&mut self) -> &mut Vec<Box<dyn Family>> {
+        &mut self.children
+    }
+
+    /// Sizes the tracks, stretches each child over its cells, and then lays
+    /// out the children's own children. Relative padding is resolved against
+    /// the grid's width, and relative tracks, gaps and base sizes are resolved
+    /// against the space inside the padding.
+    fn layout(&mut self, context: &GUILayoutContext) {
+        if self.fill_parent && self.size != context.parent_size {
+            self.set_size(context.parent_size);
+        }
+
+        // Children that were added through get_children_mut go in the first cell.
+        self.cells.truncate(self.children.len());
+        while self.cells.len() < self.children.len() {
+            let child = &self.children[self.cells.len()];
+            self.cells.push(GUIGridCell::new(0, 0, child.get_size()));
+        }
+
+        let padding = self
+            .padding
+            .resolve(GUIAxis::Horizontal, &context.with_parent(self.size, None));
+        // The area inside of the padding, relative to the grid.
+        let content = GUIRect::new(GUIPosition::from_pixels(0., 0.), self.size).inset(padding);
+        let inner_context = context.with_parent(content.size, None);
+        let base_sizes: Vec<GUISize> = self
+            .cells
+            .iter()
+            .map(|cell| cell.base_size.resolve(&inner_context))
+            .collect();
+        let column_count = self
+            .cells
+            .iter()
+            .map(GUIGridCell::column_end)
+            .fold(self.columns.len(), usize::max);
+        let row_count = self
+            .cells
+            .iter()
+            .map(GUIGridCell::row_end)
+            .fold(self.rows.len(), usize::max);
+        let columns = track_offsets(
+            &self.columns,
+            column_count,
+            GUIAxis::Horizontal,
+            &inner_context,
+            self.column_gap,
+            self.cells
+                .iter()
+                .zip(base_sizes.iter())
+                .filter(|(cell, _)| cell.column_span <= 1)
+                .map(|(cell, base_size)| (cell.column, base_size.width.get_length())),
+        );
+        let rows = track_offsets(
+            &self.rows,
+            row_count,
+            GUIAxis::Vertical,
+            &inner_context,
+            self.row_gap,
+            self.cells
+                .iter()
+                .zip(base_sizes.iter())
+                .filter(|(cell, _)| cell.row_span <= 1)
+                .map(|(cell, base_size)| (cell.row, base_size.height.get_length())),
+        );
+
+        for (child, cell) in self.children.iter_mut().zip(self.cells.iter()) {
+            let (left, right) = (columns[cell.column].0, columns[cell.column_end() - 1].1);
+            let (top, bottom) = (rows[cell.row].0, rows[cell.row_end() - 1].1);
+            widget_utils::place_child(
+                child,
+                content.position + GUIPosition::from_pixels(left, top),
+                GUISize::from_pixels(right - left, bottom - top),
+                context,
+            );
+        }
+    }
+}
+
+/// Returns where each track starts and ends along an axis, relative to the
+/// first track. The tracks share the context's parent_size along the axis.
+/// `contents` are the track and length of every child that sits in only one
+/// track, which are what auto tracks are sized to fit.
+fn track_offsets(
+    tracks: &[GUITrack],
+    count: usize,
+    axis: GUIAxis,
+    context: &GUILayoutContext,
+    gap: GUILength,
+    contents: impl Iterator<Item = (usize, f64)>,
+) -> Vec<(f64, f64)> {
+    let length = match axis {
+        GUIAxis::Horizontal => context.parent_size.width.get_length(),
+        GUIAxis::Vertical => context.parent_size.height.get_length(),
+    };
+    let gap = gap.resolve(axis, context).get_length();
+    let track = |index: usize| tracks.get(index).copied().unwrap_or(GUITrack::Auto);
+    let mut lengths = vec![0.; count];
+    for (index, content) in contents {
+        if track(index) == GUITrack::Auto {
+            lengths[index] = f64::max(lengths[index], content);
+        }
+    }
+    let mut fractions = 0.;
+    for (index, length) in lengths.iter_mut().enumerate() {
+        match track(index) {
+            GUITrack::Fixed(fixed) => *length = fixed.resolve(axis, context).get_length(),
+            GUITrack::Fraction(fraction) => fractions += fraction,
+            GUITrack::Auto => {}
+        }
+    }
+    let gaps = gap * count.saturating_sub(1) as f64;
+    let left_over = (length - gaps - lengths.iter().sum::<f64>()).max(0.);
+    if fractions > 0. {
+        for (index, length) in lengths.iter_mut().enumerate() {
+            if let GUITrack::Fraction(fraction) = track(index) {
+                *length = left_over * fraction / fractions;
+            }
+        }
+    }
+
+    let mut start = 0.;
+    lengths
+        .into_iter()
+        .map(|length| {
+            let offsets = (start, start + length);
+            start += length + gap;
+            offsets
+        })
+        .collect()
+}
+
+impl Child for GUIGrid {
+    /// Tessellates the grid's background, if it has one.
+    fn get_vertices_and_indices(
+        &self,
+        parent_position: &GUIPosition,
+        indice_offset: u32,
+    ) -> (Vec<Vertex>, Vec<u32>) {
+        let rect = GUIRect::new(*parent_position + self.position, self.size);
+        self.background
+            .get_vertices_and_indices(&rect, self.id, indice_offset)
+    }
+
+    fn get_fills(&self, parent_position: &GUIPosition) -> Vec<GUIFill> {
+        let rect = GUIRect::new(*parent_position + self.position, self.size);
+        self.background.get_fills(&rect)
+    }
+
+    /// Gets the grid's shadow, if it has one.
+    fn get_rects(&self, parent_position: &GUIPosition) -> Vec<GUIRoundedRect> {
+        let rect = GUIRect::new(*parent_position + self.position, self.size);
+        self.background.get_rects(&rect)
+    }
+
+    fn get_position(&self) -> GUIPosition {
+        self.position
+    }
+
+    fn set_position_from_pixels(&mut self, x: f64, y: f64) {
+        self.set_position_from_position(GUIPosition::from_pixels(x, y));
+    }
+
+    fn set_position_from_lengths(&mut self, x: GUILength, y: GUILength) {
+        self.set_position_from_position(GUIPosition::from_lengths(x, y));
+    }
+
+    fn set_position_from_position(&mut self, position: GUIPosition) {
+        self.position = position;
+        self.dirty = true;
+    }
+}
+
+impl Container for GUIGrid {
+    fn get_background_mut(&mut self) -> &mut GUIBackground {
+        &mut self.background
+    }
+}
+
+impl Family for GUIGrid {}
+
+impl GUIGrid {
+    /// Returns an empty grid with the given rows and columns.
+    pub fn new(rows: Vec<GUITrack>, columns: Vec<GUITrack>) -> Self {
+        Self {
+            rows,
+            columns,
+            ..Default::default()
+        }
+    }
+
+    /// Adds a child that covers the given cells.
+    pub fn add_child_with_cell(&mut self, cell: GUIGridCell, child: Box<dyn Family>) {
+        self.cells.truncate(self.children.len());
+        self.cells.push(cell);
+        self.children.push(child);
+        self.dirty = true;
+    }
+
+    /// Adds a child to a single cell.
+    pub fn add_child_at(&mut self, child: Box<dyn Family>, row: usize, column: usize) {
+        self.add_child_spanning(child, row, column, 1, 1);
+    }
+
+    /// Adds a child that covers row_span rows and column_span
+    /// columns, starting from the given cell.
+    pub fn add_child_spanning(
+        &mut self,
+        child: Box<dyn Family>,
+        row: usize,
+        column: usize,
+        row_span: usize,
+        column_span: usize,
+    ) {
+        let cell = GUIGridCell {
+            row,
+            column,
+            row_span,
+            column_span,
+            base_size: child.get_size(),
+        };
+        self.add_child_with_cell(cell, child);
+    }
+
+    /// Sets the rows, from top to bottom.
+    pub fn set_rows(&mut self, rows: Vec<GUITrack>) {
+        self.rows = rows;
+        self.dirty = true;
+    }
+
+    /// Sets the columns, from left to right.
+    pub fn set_columns(&mut self, columns: Vec<GUITrack>) {
+        self.columns = columns;
+        self.dirty = true;
+    }
+
+    /// Sets the space between neighbouring rows and columns in logical pixels.
+    pub fn set_gaps_from_pixels(&mut self, row_gap: f64, column_gap: f64) {
+        self.row_gap = GUILength::from_pixels(row_gap);
+        self.column_gap = GUILength::from_pixels(column_gap);
+        self.dirty = true;
+    }
+
+    /// Sets the space between the edges of the grid and its cells in logical pixels.
+    pub fn set_padding_from_pixels(&mut self, pixels: f64) {
+        self.padding = GUILength::from_pixels(pixels);
+        self.dirty = true;
+    }
+
+    /// Sets whether the grid takes on the size of the area it's given.
+    pub fn set_fill_parent(&mut self, fill_parent: bool) {
+        self.fill_parent = fill_parent;
+        self.dirty = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::guiwidgets::{GUIButton, GUILabel};
+
+    fn label(width: f64, height: f64) -> Box<dyn Family> {
+        let mut label = GUILabel::default();
+        label.set_size(GUISize::from_pixels(width, height));
+        Box::new(label)
+    }
+
+    /// Returns the x, y, width and height of each child, to three decimal places.
+    fn frames(grid: &GUIGrid) -> Vec<[f64; 4]> {
+        grid.children
+            .iter()
+            .map(|child| {
+                let (position, size) = (child.get_position(), child.get_size());
+                [
+                    position.x.get_length(),
+                    position.y.get_length(),
+                    size.width.get_length(),
+                    size.height.get_length(),
+                ]
+                .map(|length| (length * 1000.).round() / 1000.)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn forms_are_laid_out_in_fixed_fractional_and_auto_tracks() {
+        let mut grid = GUIGrid::new(
+            vec![GUITrack::Auto, GUITrack::Auto],
+            vec![
+                GUITrack::Fixed(GUILength::from_pixels(80.)),
+                GUITrack::Fraction(1.),
+                GUITrack::Fraction(2.),
+            ],
+        );
+        grid.set_size(GUISize::from_pixels(320., 200.));
+        grid.set_padding_from_pixels(10.);
+        grid.set_gaps_from_pixels(5., 10.);
+        grid.add_child(label(50., 20.));
+        grid.add_child(label(50., 30.));
+        grid.add_child(label(50., 20.));
+        // Added in order, so it moves onto the next row.
+        grid.add_child(label(50., 25.));
+
+        grid.layout(&GUILayoutContext::new(GUISize::from_pixels(500., 500.), 1.));
+        // 300 across, less the fixed column and two gaps, leaves 200 for the fractions.
+        assert_eq!(
+            frames(&grid),
+            vec![
+                [10., 10., 80., 30.],
+                [100., 10., 66.667, 30.],
+                [176.667, 10., 133.333, 30.],
+                [10., 45., 80., 25.],
+            ]
+        );
+    }
+
+    #[test]
+    fn children_can_span_rows_and_columns() {
+        let mut grid = GUIGrid::new(
+            vec![GUITrack::Fraction(1.); 3],
+            vec![GUITrack::Fraction(1.); 3],
+        );
+        grid.set_size(GUISize::from_pixels(300., 300.));
+        grid.add_child_spanning(Box::new(GUIButton::default()), 0, 0, 1, 3);
+        grid.add_child_spanning(Box::new(GUIButton::default()), 1, 0, 2, 1);
+        grid.add_child_at(Box::new(GUIButton::default()), 2, 2);
+
+        grid.layout(&GUILayoutContext::new(GUISize::from_pixels(500., 500.), 1.));
+        assert_eq!(
+            frames(&grid),
+            vec![
+                [0., 0., 300., 100.],
+                [0., 100., 100., 200.],
+                [200., 200., 100., 100.],
+            ]
+        );
+    }
+}
@@ -0,0 +1,219 @@
+use uuid::Uuid;
+
+use crate::guiprocessing::vertices::Vertex;
+use crate::guiproperties::guiposition::{GUILength, GUIPosition, GUIRect, GUISize};
+use crate::guiproperties::guitraits::{Child, Family, Parent, Widget};
+use crate::guiproperties::{GUIAlignment, GUIColor, GUIFill, GUIText};
+use crate::guiwidgets::widget_utils;
+
+/// Represents a run of text.
+pub struct GUILabel {
+    /// The text of the label.
+    pub text: String,
+    /// The size of the area that the text is aligned within.
+    pub size: GUISize,
+    /// The location of the label.
+    pub position: GUIPosition,
+    /// The height of the text in logical pixels.
+    pub font_size: GUILength,
+    /// The color of the text.
+    pub color: GUIColor,
+    /// What's painted behind the text. Nothing is drawn if it's fully transparent.
+    pub background: GUIFill,
+    /// Where the text sits horizontally within the label.
+    pub horizontal_alignment: GUIAlignment,
+    /// Where the text sits vertically within the label.
+    pub vertical_alignment: GUIAlignment,
+    /// A list of child widgets
+    pub children: Vec<Box<dyn Family>>,
+    /// The human readable name of the label
+    pub name: &'static str,
+    pub id: u128,
+    dirty: bool,
+}
+
+const DEFAULT_LABEL_NAME: &str = "this is the default name of the label";
+
+impl Default for GUILabel {
+    // Returns a label with all of the default values.
+    fn default() -> GUILabel {
+        GUILabel {
+            text: String::from("Label"),
+            size: GUISize::from_pixels(200., 30.),
+            position: GUIPosition::from_pixels(0., 0.),
+            font_size: GUILength::from_pixels(20.),
+            color: GUIColor {
+                r: 0.0,
+                g: 0.0,
+                b: 0.0,
+                a: 1.0,
+            },
+            background: GUIFill::Solid(GUIColor {
+                r: 0.0,
+                g: 0.0,
+                b: 0.0,
+                a: 0.0,
+            }),
+            horizontal_alignment: GUIAlignment::Start,
+            vertical_alignment: GUIAlignment::Start,
+            children: Vec::new(),
+            name: DEFAULT_LABEL_NAME,
+            id: Uuid::new_v4().as_u128(),
+            dirty: true,
+        }
+    }
+}
+
+impl Widget for GUILabel {
+    fn is_rendered(&self) -> bool {
+        true
+    }
+
+    /// Set the size (width and height) of the label in units of logical pixels.
+    fn set_size(&mut self, size: GUISize) {
+        self.size = size;
+        self.dirty = true;
+    }
+
+    fn get_size(&self) -> GUISize {
+        self.size
+    }
+
+    /// Ems within the label are relative to the size of its text.
+    fn get_font_size(&self) -> Option<GUILength> {
+        Some(self.font_size)
+    }
+
+    // Set background color of the label.
+    fn set_background_color(&mut self, color: GUIColor) {
+        self.background = GUIFill::Solid(color);
+        self.dirty = true;
+    }
+
+    fn set_id(&mut self, id: u128) {
+        self.id = id;
+    }
+
+    fn get_id(&self) -> u128 {
+        self.id
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    fn set_dirty(&mut self, dirty: bool) {
+        self.dirty = dirty;
+    }
+}
+
+impl Parent for GUILabel {
+    /// Adds a child to the GUILabel.
+    /// Children, and grandchildren will be rendered in the order
+    /// in which they're added so children that should be
+    /// visually obscured by other children should be added
+    /// before the obscuring children.
+    fn add_child(&mut self, child: Box<dyn Family>) {
+        self.children.push(child);
+        self.dirty = true;
+    }
+
+    fn set_children(&mut self, children: Vec<Box<dyn Family>>) {
+        self.children = children;
+        self.dirty = true;
+    }
+
+    /// Gets the children.
+    fn get_children(&self) -> &Vec<Box<dyn Family>> {
+        &self.children
+    }
+
+    /// Gets the children mutably.
+    fn get_children_mut(&mut self) -> &mut Vec<Box<dyn Family>> {
+        &mut self.children
+    }
+}
+
+impl Child for GUILabel {
+    /// Tessellates the label's background, if it has one.
+    fn get_vertices_and_indices(
+        &self,
+        parent_position: &GUIPosition,
+        indice_offset: u32,
+    ) -> (Vec<Vertex>, Vec<u32>) {
+        if self.background.is_transparent() {
+            return (Vec::new(), Vec::new());
+        }
+        let rect = GUIRect::new(*parent_position + self.position, self.size);
+        widget_utils::make_rectangle(&rect, &self.background, self.id, indice_offset)
+    }
+
+    fn get_fills(&self, parent_position: &GUIPosition) -> Vec<GUIFill> {
+        let rect = GUIRect::new(*parent_position + self.position, self.size);
+        widget_utils::fills(&self.background, &rect)
+    }
+
+    fn get_text(&self, parent_position: &GUIPosition) -> Vec<GUIText> {
+        vec![GUIText {
+            text: self.text.clone(),
+            font_size: self.font_size,
+            color: self.color,
+            position: *parent_position + self.position,
+            size: self.size,
+            horizontal_alignment: self.horizontal_alignment,
+            vertical_alignment: self.vertical_alignment,
+        }]
+    }
+
+    fn get_position(&self) -> GUIPosition {
+        self.position
+    }
+
+    fn set_position_from_pixels(&mut self, x: f64, y: f64) {
+        self.set_position_from_position(GUIPosition::from_pixels(x, y));
+    }
+
+    fn set_position_from_lengths(&mut self, x: GUILength, y: GUILength) {
+        self.set_position_from_position(GUIPosition::from_lengths(x, y));
+    }
+
+    fn set_position_from_position(&mut self, position: GUIPosition) {
+        self.position = position;
+        self.dirty = true;
+    }
+}
+
+impl Family for GUILabel {}
+
+impl GUILabel {
+    /// Sets the text of the label.
+    pub fn set_text<S: Into<String>>(&mut self, text: S) {
+        self.text = text.into();
+        self.dirty = true;
+    }
+
+    /// Sets the height of the text.
+    pub fn set_font_size_from_pixels(&mut self, pixels: f64) {
+        self.font_size = GUILength::from_pixels(pixels);
+        self.dirty = true;
+    }
+
+    /// Sets the color of the text.
+    pub fn set_color(&mut self, color: GUIColor) {
+        self.color = color;
+        self.dirty = true;
+    }
+
+    /// Sets where the text sits within the label.
+    pub fn set_alignment(&mut self, horizontal: GUIAlignment, vertical: GUIAlignment) {
+        self.horizontal_alignment = horizontal;
+        self.vertical_alignment = vertical;
+        self.dirty = true;
+    }
+
+    /// Sets what's painted behind the text.
+    pub fn set_background(&mut self, fill: GUIFill) {
+        self.background = fill;
+        self.dirty = true;
+    }
+}
@@ -0,0 +1,496 @@
+use uuid::Uuid;
+
+use crate::guiprocessing::tessellation;
+use crate::guiprocessing::vertices::Vertex;
+use crate::guiproperties::guiposition::{
+    GUILayoutContext, GUILength, GUIPosition, GUIRect, GUISize,
+};
+use crate::guiproperties::guitraits::{Child, Family, Parent, Widget};
+use crate::guiproperties::{GUIColor, GUIFill, GUIPath, GUIPolygon, GUIStroke};
+use crate::guiwidgets::{widget_utils, widget_utils::arcs};
+
+/// Fills arbitrary polygons, which can be concave and have holes, or a path.
+/// The polygons' bounds are stretched over the shape's size, so a shape
+/// can be resized by layouts like any other widget.
+pub struct GUIShape {
+    /// The polygons that are drawn.
+    pub polygons: Vec<GUIPolygon>,
+    /// The path that the polygons are flattened from, if they came from one.
+    /// It's flattened again whenever the scale factor changes.
+    pub path: Option<GUIPath>,
+    /// The scale factor that the shape was last laid out at, which the path
+    /// was flattened at and decides how finely round joins and caps are tessellated.
+    scale_factor: f64,
+    /// The size that the polygons' bounds are stretched to.
+    pub size: GUISize,
+    /// The location of the shape.
+    pub position: GUIPosition,
+    /// What the polygons are filled with. Gradients are placed within the shape's rectangle.
+    pub fill: GUIFill,
+    /// The stroke drawn along the polygons' outlines and holes, or the path's
+    /// outlines, over the fill.
+    pub stroke: Option<GUIStroke>,
+    /// A list of child widgets
+    pub children: Vec<Box<dyn Family>>,
+    /// The human readable name of the shape
+    pub name: &'static str,
+    pub id: u128,
+    dirty: bool,
+}
+
+const DEFAULT_SHAPE_NAME: &str = "this is the default name of the shape";
+
+impl Default for GUIShape {
+    // Returns a shape with all of the default values, which is a square.
+    fn default() -> GUIShape {
+        GUIShape::new(GUIPolygon::new(
+            GUIRect::from_pixels(0., 0., 100., 100.).corners().to_vec(),
+        ))
+    }
+}
+
+impl Widget for GUIShape {
+    fn is_rendered(&self) -> bool {
+        true
+    }
+
+    /// Set the size (width and height) of the shape in units of logical pixels.
+    fn set_size(&mut self, size: GUISize) {
+        self.size = size;
+        self.dirty = true;
+    }
+
+    fn get_size(&self) -> GUISize {
+        self.size
+    }
+
+    // Set the fill color of the shape.
+    fn set_background_color(&mut self, color: GUIColor) {
+        self.fill = GUIFill::Solid(color);
+        self.dirty = true;
+    }
+
+    fn set_id(&mut self, id: u128) {
+        self.id = id;
+    }
+
+    fn get_id(&self) -> u128 {
+        self.id
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    fn set_dirty(&mut self, dirty: bool) {
+        self.dirty = dirty;
+    }
+}
+
+impl Parent for GUIShape {
+    /// Adds a child to the GUIShape.
+    /// Children, and grandchildren will be rendered in the order
+    /// in which they're added so children that should be
+    /// visually obscured by other children should be added
+    /// before the obscuring children.
+    fn add_child(&mut self, child: Box<dyn Family>) {
+        self.children.push(child);
+        self.dirty = true;
+    }
+
+    fn set_children(&mut self, children: Vec<Box<dyn Family>>) {
+        self.children = children;
+        self.dirty = true;
+    }
+
+    /// Gets the children.
+    fn get_children(&self) -> &Vec<Box<dyn Family>> {
+        &self.children
+    }
+
+    /// Gets the children mutably.
+    fn get_children_mut(&mut self) -> &mut Vec<Box<dyn Family>> {
+        &mut self.children
+    }
+
+    /// Flattens the path again if the scale factor has changed, so that its
+    /// curves are as smooth on a high density display as on any other.
+    fn layout(&mut self, context: &GUILayoutContext) {
+        if self.scale_factor != context.scale_factor {
+            self.scale_factor = context.scale_factor;
+            if let Some(path) = self.path.as_ref() {
+                self.polygons = path.flatten(self.scale_factor);
+            }
+            self.dirty = true;
+        }
+        let context = context.with_parent(self.size, None);
+        for child in self.children.iter_mut() {
+            child.resolve_lengths(&context);
+            child.layout(&context);
+        }
+    }
+}
+
+impl Child for GUIShape {
+    /// Tessellates the polygons, and then the stroke if there is one,
+    /// offsetting their indices by indice_offset.
+    fn get_vertices_and_indices(
+        &self,
+        parent_position: &GUIPosition,
+        indice_offset: u32,
+    ) -> (Vec<Vertex>, Vec<u32>) {
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        for polygon in self.placed_polygons(parent_position) {
+            let offset = indice_offset + vertices.len() as u32;
+            indices.extend(polygon.indices.iter().map(|index| index + offset));
+            vertices.extend(widget_utils::make_filled_vertices(
+                &polygon.positions(),
+                &self.fill,
+                self.id,
+            ));
+        }
+
+        if let Some(stroke) = self.stroke.as_ref() {
+            let tolerance = arcs::tolerance(self.scale_factor);
+            for (outline, closed) in self.placed_outlines(parent_position) {
+                let (positions, stroke_indices) =
+                    tessellation::tessellate_stroke(&outline, closed, stroke, tolerance);
+                let offset = indice_offset + vertices.len() as u32;
+                indices.extend(stroke_indices.iter().map(|index| index + offset));
+                vertices.extend(widget_utils::make_vertices(
+                    &positions,
+                    &stroke.color,
+                    self.id,
+                ));
+            }
+        }
+        (vertices, indices)
+    }
+
+    fn get_fills(&self, parent_position: &GUIPosition) -> Vec<GUIFill> {
+        let rect = GUIRect::new(*parent_position + self.position, self.size);
+        widget_utils::fills(&self.fill, &rect)
+    }
+
+    fn get_position(&self) -> GUIPosition {
+        self.position
+    }
+
+    fn set_position_from_pixels(&mut self, x: f64, y: f64) {
+        self.set_position_from_position(GUIPosition::from_pixels(x, y));
+    }
+
+    fn set_position_from_lengths(&mut self, x: GUILength, y: GUILength) {
+        self.set_position_from_position(GUIPosition::from_lengths(x, y));
+    }
+
+    fn set_position_from_position(&mut self, position: GUIPosition) {
+        self.position = position;
+        self.dirty = true;
+    }
+
+    /// Whether or not the point lies within one of the polygons, and outside of its holes.
+    fn contains_point(&self, parent_position: &GUIPosition, point: &GUIPosition) -> bool {
+        self.placed_polygons(parent_position)
+            .iter()
+            .any(|polygon| polygon.contains(point))
+    }
+}
+
+impl Family for GUIShape {}
+
+impl GUIShape {
+    /// Returns a shape that fills the polygon, sized to fit it.
+    pub fn new(mut polygon: GUIPolygon) -> Self {
+        if polygon.indices.is_empty() {
+            polygon.tessellate();
+        }
+        Self::from_polygons(vec![polygon], None, 1.)
+    }
+
+    /// Returns a shape that fills the path by its fill rule, sized to fit it.
+    /// It's flattened for a scale factor of 1 until it's laid out.
+    pub fn from_path(path: GUIPath) -> Self {
+        Self::from_polygons(path.flatten(1.), Some(path), 1.)
+    }
+
+    fn from_polygons(polygons: Vec<GUIPolygon>, path: Option<GUIPath>, scale_factor: f64) -> Self {
+        let bounds = bounds(&polygons);
+        GUIShape {
+            polygons,
+            path,
+            scale_factor,
+            size: bounds.size,
+            position: bounds.position,
+            fill: GUIFill::Solid(GUIColor {
+                r: 0.0,
+                g: 0.0,
+                b: 0.0,
+                a: 1.0,
+            }),
+            stroke: None,
+            children: Vec::new(),
+            name: DEFAULT_SHAPE_NAME,
+            id: Uuid::new_v4().as_u128(),
+            dirty: true,
+        }
+    }
+
+    /// Replaces what's drawn with the polygon, tessellating it if it hasn't been already.
+    pub fn set_polygon(&mut self, mut polygon: GUIPolygon) {
+        if polygon.indices.is_empty() {
+            polygon.tessellate();
+        }
+        self.polygons = vec![polygon];
+        self.path = None;
+        self.dirty = true;
+    }
+
+    /// Replaces what's drawn with the path, flattening it at the current scale factor.
+    pub fn set_path(&mut self, path: GUIPath) {
+        self.polygons = path.flatten(self.scale_factor);
+        self.path = Some(path);
+        self.dirty = true;
+    }
+
+    /// Sets the color that the polygons are filled with.
+    pub fn set_color(&mut self, color: GUIColor) {
+        self.set_fill(GUIFill::Solid(color));
+    }
+
+    /// Sets what the polygons are filled with.
+    pub fn set_fill(&mut self, fill: GUIFill) {
+        self.fill = fill;
+        self.dirty = true;
+    }
+
+    /// Sets the stroke that's drawn along the shape's outlines, or removes it.
+    pub fn set_stroke(&mut self, stroke: Option<GUIStroke>) {
+        self.stroke = stroke;
+        self.dirty = true;
+    }
+
+    /// Returns the polygons stretched from their bounds over the shape's
+    /// rectangle, in absolute logical pixels.
+    fn placed_polygons(&self, parent_position: &GUIPosition) -> Vec<GUIPolygon> {
+        let place = self.placement(parent_position);
+        self.polygons
+            .iter()
+            .map(|polygon| GUIPolygon {
+                vertices: polygon.vertices.iter().map(&place).collect(),
+                holes: polygon
+                    .holes
+                    .iter()
+                    .map(|hole| hole.iter().map(&place).collect())
+                    .collect(),
+                indices: polygon.indices.clone(),
+            })
+            .collect()
+    }
+
+    /// Returns the outlines that are stroked, placed like the polygons,
+    /// along with whether or not each is closed.
+    fn placed_outlines(&self, parent_position: &GUIPosition) -> Vec<(Vec<GUIPosition>, bool)> {
+        let place = self.placement(parent_position);
+        let outlines = match self.path.as_ref() {
+            Some(path) => path.outlines(self.scale_factor),
+            None => self
+                .polygons
+                .iter()
+                .flat_map(|polygon| {
+                    std::iter::once(polygon.vertices.clone()).chain(polygon.holes.iter().cloned())
+                })
+                .map(|outline| (outline, true))
+                .collect(),
+        };
+        outlines
+            .into_iter()
+            .map(|(outline, closed)| (outline.iter().map(&place).collect(), closed))
+            .collect()
+    }
+
+    /// Returns a function that moves a position from the polygons' bounds
+    /// onto the shape's rectangle, in absolute logical pixels.
+    fn placement(&self, parent_position: &GUIPosition) -> impl Fn(&GUIPosition) -> GUIPosition {
+        let bounds = bounds(&self.polygons);
+        let rect = GUIRect::new(*parent_position + self.position, self.size);
+        // A polygon with no width or height keeps its length along that axis.
+        let scale = |bounds_length: GUILength, rect_length: GUILength| {
+            if bounds_length.get_length() > 0. {
+                rect_length.get_length() / bounds_length.get_length()
+            } else {
+                1.
+            }
+        };
+        let scale_x = scale(bounds.size.width, rect.size.width);
+        let scale_y = scale(bounds.size.height, rect.size.height);
+        move |vertex: &GUIPosition| {
+            GUIPosition::from_pixels(
+                rect.left().get_length()
+                    + (vertex.x.get_length() - bounds.left().get_length()) * scale_x,
+                rect.top().get_length()
+                    + (vertex.y.get_length() - bounds.top().get_length()) * scale_y,
+            )
+        }
+    }
+}
+
+/// Returns the smallest rectangle that contains all of the polygons.
+fn bounds(polygons: &[GUIPolygon]) -> GUIRect {
+    polygons
+        .iter()
+        .map(GUIPolygon::bounds)
+        .reduce(|a, b| a.union(&b))
+        .unwrap_or_else(|| GUIRect::from_pixels(0., 0., 0., 0.))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::guiproperties::{GUIFillRule, GUILineCap, GUILineJoin};
+    use crate::guiresources::{GUIBackend, GUIResources, GUISampleCount};
+    use crate::guitesting::crate_goldens;
+    use crate::guiwidgets::GUIWindow;
+
+    /// A ring with a notch cut into its right side.
+    fn notched_ring() -> GUIPolygon {
+        let mut polygon = GUIPolygon::new(
+            [
+                [0., 0.],
+                [0., 60.],
+                [60., 60.],
+                [60., 40.],
+                [40., 30.],
+                [60., 20.],
+                [60., 0.],
+            ]
+            .iter()
+            .map(|&[x, y]| GUIPosition::from_pixels(x, y))
+            .collect(),
+        );
+        polygon.add_hole(GUIRect::from_pixels(10., 20., 20., 20.).corners().to_vec());
+        polygon
+    }
+
+    #[test]
+    fn shapes_are_hit_tested_against_their_polygon() {
+        let mut shape = GUIShape::new(notched_ring());
+        shape.set_position_from_pixels(10., 10.);
+        let origin = GUIPosition::from_pixels(0., 0.);
+        let hit =
+            |shape: &GUIShape, x, y| shape.contains_point(&origin, &GUIPosition::from_pixels(x, y));
+        assert!(hit(&shape, 15., 15.));
+        // In the hole, and in the notch.
+        assert!(!hit(&shape, 30., 40.));
+        assert!(!hit(&shape, 65., 40.));
+
+        // Stretching the shape stretches its polygon.
+        shape.set_size(GUISize::from_pixels(120., 60.));
+        assert!(hit(&shape, 80., 40.));
+        assert!(!hit(&shape, 50., 40.));
+        assert!(!hit(&shape, 120., 40.));
+    }
+
+    #[test]
+    fn paths_are_flattened_again_when_the_scale_factor_changes() {
+        let mut path = GUIPath::new(GUIFillRule::NonZero);
+        path.move_to(GUIPosition::from_pixels(40., 0.));
+        path.arc_to(GUIPosition::from_pixels(20., 0.), std::f64::consts::PI);
+        path.close();
+        let mut guiwindow = GUIWindow::default();
+        guiwindow.add_child(Box::new(GUIShape::from_path(path)));
+
+        let vertex_count = |guiwindow: &mut GUIWindow, scale_factor| {
+            guiwindow.logical_scale = Some(scale_factor);
+            guiwindow.layout_children();
+            let shape = &guiwindow.children[0];
+            shape
+                .get_vertices_and_indices(&GUIPosition::from_pixels(0., 0.), 0)
+                .0
+                .len()
+        };
+        let coarse = vertex_count(&mut guiwindow, 1.);
+        assert!(vertex_count(&mut guiwindow, 3.) > coarse);
+    }
+
+    #[test]
+    fn concave_shape_with_a_hole_matches_golden() {
+        let mut shape = GUIShape::new(notched_ring());
+        shape.set_position_from_pixels(10., 10.);
+        shape.set_color(GUIColor::from_rgba_u8u8u8u8(40, 90, 200, 255));
+        let mut guiwindow = GUIWindow::default();
+        guiwindow.set_size(GUISize::from_pixels(80., 80.));
+        guiwindow.add_child(Box::new(shape));
+        crate_goldens().assert_window("shape_notched_ring", guiwindow);
+    }
+
+    #[test]
+    fn multisampled_shape_matches_golden() {
+        // A leaf, which has curved and slanted edges for the samples to smooth.
+        let mut path = GUIPath::new(GUIFillRule::NonZero);
+        path.move_to(GUIPosition::from_pixels(10., 70.));
+        path.cubic_to(
+            GUIPosition::from_pixels(10., 20.),
+            GUIPosition::from_pixels(40., 10.),
+            GUIPosition::from_pixels(70., 10.),
+        );
+        path.cubic_to(
+            GUIPosition::from_pixels(70., 40.),
+            GUIPosition::from_pixels(60., 70.),
+            GUIPosition::from_pixels(10., 70.),
+        );
+        path.close();
+        let mut shape = GUIShape::from_path(path);
+        shape.set_color(GUIColor::from_rgba_u8u8u8u8(40, 90, 200, 255));
+        let mut guiwindow = GUIWindow::default();
+        guiwindow.set_size(GUISize::from_pixels(80., 80.));
+        guiwindow.add_child(Box::new(shape));
+        let mut guiresources = GUIResources::default();
+        guiresources.set_backend(GUIBackend::Software);
+        guiresources.set_sample_count(GUISampleCount::Four);
+        crate_goldens().assert_window_with("shape_leaf_multisampled", guiwindow, guiresources);
+    }
+
+    #[test]
+    fn stroked_shapes_match_golden() {
+        let mut guiwindow = GUIWindow::default();
+        guiwindow.set_size(GUISize::from_pixels(160., 80.));
+
+        let mut ring = GUIShape::new(notched_ring());
+        ring.set_position_from_pixels(10., 10.);
+        ring.set_color(GUIColor::from_rgba_u8u8u8u8(40, 90, 200, 255));
+        ring.set_stroke(Some(GUIStroke {
+            join: GUILineJoin::Bevel,
+            ..GUIStroke::new(
+                GUILength::from_pixels(4.),
+                GUIColor::from_rgba_u8u8u8u8(20, 20, 20, 255),
+            )
+        }));
+        guiwindow.add_child(Box::new(ring));
+
+        // An open zigzag, which isn't filled because it has no area to speak of.
+        let mut path = GUIPath::new(GUIFillRule::NonZero);
+        path.move_to(GUIPosition::from_pixels(90., 60.));
+        path.line_to(GUIPosition::from_pixels(110., 20.));
+        path.line_to(GUIPosition::from_pixels(130., 60.));
+        path.quad_to(
+            GUIPosition::from_pixels(150., 20.),
+            GUIPosition::from_pixels(150., 60.),
+        );
+        let mut zigzag = GUIShape::from_path(path);
+        zigzag.set_color(GUIColor::from_rgba_u8u8u8u8(0, 0, 0, 0));
+        zigzag.set_stroke(Some(GUIStroke {
+            join: GUILineJoin::Round,
+            cap: GUILineCap::Round,
+            dashes: vec![GUILength::from_pixels(24.), GUILength::from_pixels(8.)],
+            ..GUIStroke::new(
+                GUILength::from_pixels(6.),
+                GUIColor::from_rgba_u8u8u8u8(200, 60, 40, 255),
+            )
+        }));
+        guiwindow.add_child(Box::new(zigzag));
+
+        crate_goldens().assert_window("shape_stroked", guiwindow);
+    }
+}
@@ -1,11 +1,15 @@
 use uuid::Uuid;
 
+use crate::guiprocessing::window_building_utils;
 use crate::guiproperties::guiposition::GUILength;
-use crate::guiproperties::guiposition::{GUIPosition, GUISize};
+use crate::guiproperties::guiposition::{GUILayoutContext, GUIPosition, GUISize};
 use crate::guiproperties::guitraits::{Family, Parent, Widget, Wind};
 use crate::guiproperties::GUIColor;
 use crate::guiproperties::GUIIcon;
 
+/// A closure that's called with the window once per frame.
+pub type GUIUpdateFn = Box<dyn FnMut(&mut GUIWindow)>;
+
 /// Represents a gui window.
 /// Given the number of properties that a window has,
 /// rather than accounting for all of them in the creation
@@ -29,15 +33,26 @@ pub struct GUIWindow {
     pub window_icon: Option<GUIIcon>,
     /// The window's IME position
     pub ime_position: Option<GUIPosition>,
-    /// The background color for the window.
+    /// The background color for the window. Translucent backgrounds don't show
+    /// what's behind the window yet: wgpu 0.12 always composites window
+    /// surfaces as opaque, so they're shown over black. Their alpha is only
+    /// kept in images rendered offscreen, with render_to_image.
     pub background_color: GUIColor,
     /// A list of child widgets.
     pub children: Vec<Box<dyn Family>>,
     /// The scale that converts between the devices logical and physical pixels.
     pub logical_scale: Option<f64>,
+    /// The number of physical pixels in an inch on the window's display, which
+    /// millimetres and points are measured with. If it isn't known, 96 logical
+    /// pixels are taken to be an inch.
+    pub pixels_per_inch: Option<f64>,
     /// The human readable name of the window
     pub name: &'static str,
     pub id: u128,
+    /// Called once per frame, before the window is drawn, so that
+    /// widgets can be changed while the gui is running.
+    pub on_update: Option<GUIUpdateFn>,
+    dirty: bool,
 }
 
 const DEFAULT_WINDOW_NAME: &str = "this is the default name of the window";
@@ -71,8 +86,11 @@ impl Default for GUIWindow {
             },
             children: Vec::new(),
             logical_scale: None,
+            pixels_per_inch: None,
             name: DEFAULT_WINDOW_NAME,
             id: Uuid::new_v4().as_u128(),
+            on_update: None,
+            dirty: true,
         }
     }
 }
@@ -85,6 +103,11 @@ impl Widget for GUIWindow {
     /// Set the size (width and height) of the window in units of logical pixels.
     fn set_size(&mut self, size: GUISize) {
         self.size = size;
+        self.dirty = true;
+    }
+
+    fn get_size(&self) -> GUISize {
+        self.size
     }
 
     // Set background color of the window.
@@ -95,6 +118,18 @@ impl Widget for GUIWindow {
     fn set_id(&mut self, id: u128) {
         self.id = id;
     }
+
+    fn get_id(&self) -> u128 {
+        self.id
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    fn set_dirty(&mut self, dirty: bool) {
+        self.dirty = dirty;
+    }
 }
 
 impl Wind for GUIWindow {
@@ -132,10 +167,12 @@ impl Parent for GUIWindow {
     /// before the obscuring children.
     fn add_child(&mut self, child: Box<dyn Family>) {
         self.children.push(child);
+        self.dirty = true;
     }
 
     fn set_children(&mut self, children: Vec<Box<dyn Family>>) {
         self.children = children;
+        self.dirty = true;
     }
 
     /// Gets the children.
@@ -143,13 +180,52 @@ impl Parent for GUIWindow {
         &self.children
     }
 
-    // /// Gets the children.
-    // fn get_children_mut(&mut self) -> &mut Vec<Box<dyn Family>> {
-    //     &mut self.children
-    // }
+    /// Gets the children mutably.
+    fn get_children_mut(&mut self) -> &mut Vec<Box<dyn Family>> {
+        &mut self.children
+    }
 
     // fn give_children(&mut self) -> Vec<Box<dyn Family>> {
     //     let a = self.children;
     //     a
     // }
 }
+
+impl GUIWindow {
+    /// Sets the closure that's called once per frame, before the window
+    /// is drawn. Widgets that are changed through their setters are
+    /// re-tessellated and redrawn.
+    pub fn set_on_update(&mut self, on_update: impl FnMut(&mut GUIWindow) + 'static) {
+        self.on_update = Some(Box::new(on_update));
+    }
+
+    /// Calls the on_update closure, if there is one.
+    pub fn run_on_update(&mut self) {
+        if let Some(mut on_update) = self.on_update.take() {
+            on_update(self);
+            // Put the closure back unless it replaced itself.
+            self.on_update.get_or_insert(on_update);
+        }
+    }
+
+    /// Returns what the lengths of the window's children are resolved against.
+    pub fn layout_context(&self) -> GUILayoutContext {
+        let mut context = GUILayoutContext::new(self.size, self.logical_scale.unwrap_or(1.));
+        if let Some(pixels_per_inch) = self.pixels_per_inch {
+            context.pixels_per_inch = pixels_per_inch;
+        }
+        context
+    }
+
+    /// Lays out the window's widgets, resolving any relative lengths
+    /// against the window's current size and scale factor.
+    pub fn layout_children(&mut self) {
+        let context = self.layout_context();
+        self.layout(&context);
+    }
+
+    /// Finds the widget with the given id anywhere in the window.
+    pub fn find_child_mut(&mut self, id: u128) -> Option<&mut Box<dyn Family>> {
+        window_building_utils::find_child_mut(&mut self.children, id)
+    }
+}
@@ -1,7 +1,22 @@
 mod guiwindow;
-pub use guiwindow::GUIWindow;
+pub use guiwindow::{GUIUpdateFn, GUIWindow};
 
 mod guibutton;
-pub use guibutton::GUIButton;
+pub use guibutton::{GUIButton, GUIButtonRendering, GUIButtonState, GUIButtonStyle};
 
-mod widget_utils;
+mod guilabel;
+pub use guilabel::GUILabel;
+
+mod guiflex;
+pub use guiflex::{GUIAlignItems, GUIFlex, GUIFlexDirection, GUIFlexItem, GUIJustifyContent};
+
+mod guigrid;
+pub use guigrid::{GUIGrid, GUIGridCell, GUITrack};
+
+mod guianchorlayout;
+pub use guianchorlayout::{GUIAnchorLayout, GUIAnchors};
+
+mod guishape;
+pub use guishape::GUIShape;
+
+pub(crate) mod widget_utils;
@@ -1,29 +1,174 @@
-use crate::guiproperties::guiposition::{GUILength, GUIPosition};
+use crate::guiprocessing::vertices::Vertex;
+use crate::guiproperties::guiposition::{
+    GUILayoutContext, GUILength, GUIPosition, GUIRect, GUISize,
+};
+use crate::guiproperties::guitraits::Family;
+use crate::guiproperties::{GUIColor, GUIFill};
 
 pub mod arcs {
     use crate::guiproperties::guiposition::{GUILength, GUIPosition};
 
-    const PI: f64 = 3.141592653589793238;
+    use std::f64::consts::PI;
+
+    /// How far, in physical pixels, a flattened curve is allowed to stray from the true curve.
+    pub const TOLERANCE: f64 = 0.25;
+
+    /// Returns TOLERANCE in logical pixels at the scale factor, so that
+    /// curves are flattened more finely on higher density displays.
+    pub fn tolerance(scale_factor: f64) -> f64 {
+        TOLERANCE / scale_factor
+    }
+
+    /// Returns how many segments an arc needs so that none of them stray more
+    /// than tolerance from it. The radius and tolerance are in the same units.
+    pub fn segment_count(radius: f64, sweep: f64, tolerance: f64) -> usize {
+        if radius <= tolerance {
+            return 1;
+        }
+        // A chord spanning an angle of θ strays r(1 - cos(θ / 2)) from the arc.
+        let step = 2. * (1. - tolerance / radius).acos();
+        ((sweep.abs() / step).ceil() as usize).max(1)
+    }
+
+    /// Creates a set of vertices to approximate an arc of a circle, starting
+    /// from the given angle and sweeping through another. Angles are in
+    /// radians, with positive angles going counter clockwise on screen from
+    /// the right. The tolerance is in logical pixels, like the radius.
+    /// Both ends of the arc are included.
+    pub fn make_arc(
+        center: GUIPosition,
+        radius: GUILength,
+        start_angle: f64,
+        sweep: f64,
+        tolerance: f64,
+    ) -> Vec<GUIPosition> {
+        let radius = radius.get_length();
+        let segments = segment_count(radius, sweep, tolerance);
+        (0..=segments)
+            .map(|i| {
+                let angle = start_angle + sweep * i as f64 / segments as f64;
+                GUIPosition::from_pixels(
+                    center.x.get_length() + radius * angle.cos(),
+                    center.y.get_length() - radius * angle.sin(),
+                )
+            })
+            .collect()
+    }
+
+    /// Creates a set of vertices to approximate a quarter of a superellipse,
+    /// |x / r|ⁿ + |y / r|ⁿ = 1, starting from the given angle and going a
+    /// quarter of the way round counter clockwise on screen. An exponent
+    /// of 2 gives a circle. The center has the coordinates 0, 0.
+    /// Both ends of the arc are included.
+    pub fn make_superellipse_arc(
+        radius: GUILength,
+        start_angle: f64,
+        exponent: f64,
+        tolerance: f64,
+    ) -> Vec<GUIPosition> {
+        let radius = radius.get_length();
+        // Superellipses bend more sharply than circles, so get twice the fascets.
+        let fascets = 2 * segment_count(radius, PI / 2., tolerance);
+        let power = 2. / exponent.max(f64::EPSILON);
+        (0..=fascets)
+            .map(|i| {
+                let angle = start_angle + PI / 2. * i as f64 / fascets as f64;
+                let (sin, cos) = angle.sin_cos();
+                GUIPosition::from_pixels(
+                    radius * cos.signum() * cos.abs().powf(power),
+                    -radius * sin.signum() * sin.abs().powf(power),
+                )
+            })
+            .collect()
+    }
+
+    /// Creates a set of vertices to approximate a quadratic Bézier curve,
+    /// straying no more than tolerance logical pixels from it.
+    /// The start of the curve isn't included, but the end is.
+    pub fn make_quadratic_curve(
+        from: GUIPosition,
+        control: GUIPosition,
+        to: GUIPosition,
+        tolerance: f64,
+    ) -> Vec<GUIPosition> {
+        let [p0, p1, p2] = [from, control, to].map(to_point);
+        // Straight lines between n evenly spaced points stray at most
+        // |p0 - 2p1 + p2| / 4n² from the curve.
+        let deviation = length(second_difference(p0, p1, p2));
+        let segments = ((deviation / (4. * tolerance)).sqrt().ceil() as usize).max(1);
+        (1..=segments)
+            .map(|i| {
+                let t = i as f64 / segments as f64;
+                let u = 1. - t;
+                from_point(
+                    [0, 1].map(|axis| u * u * p0[axis] + 2. * u * t * p1[axis] + t * t * p2[axis]),
+                )
+            })
+            .collect()
+    }
+
+    /// Creates a set of vertices to approximate a cubic Bézier curve,
+    /// straying no more than tolerance logical pixels from it.
+    /// The start of the curve isn't included, but the end is.
+    pub fn make_cubic_curve(
+        from: GUIPosition,
+        first_control: GUIPosition,
+        second_control: GUIPosition,
+        to: GUIPosition,
+        tolerance: f64,
+    ) -> Vec<GUIPosition> {
+        let [p0, p1, p2, p3] = [from, first_control, second_control, to].map(to_point);
+        // The second derivative is at most 6 times the larger second
+        // difference, and straight lines stray an eighth of that over n².
+        let deviation =
+            length(second_difference(p0, p1, p2)).max(length(second_difference(p1, p2, p3)));
+        let segments = ((3. * deviation / (4. * tolerance)).sqrt().ceil() as usize).max(1);
+        (1..=segments)
+            .map(|i| {
+                let t = i as f64 / segments as f64;
+                let u = 1. - t;
+                from_point([0, 1].map(|axis| {
+                    u * u * u * p0[axis]
+                        + 3. * u * u * t * p1[axis]
+                        + 3. * u * t * t * p2[axis]
+                        + t * t * t * p3[axis]
+                }))
+            })
+            .collect()
+    }
+
+    fn to_point(position: GUIPosition) -> [f64; 2] {
+        [position.x.get_length(), position.y.get_length()]
+    }
+
+    fn from_point([x, y]: [f64; 2]) -> GUIPosition {
+        GUIPosition::from_pixels(x, y)
+    }
+
+    fn second_difference(a: [f64; 2], b: [f64; 2], c: [f64; 2]) -> [f64; 2] {
+        [a[0] - 2. * b[0] + c[0], a[1] - 2. * b[1] + c[1]]
+    }
+
+    fn length([x, y]: [f64; 2]) -> f64 {
+        x.hypot(y)
+    }
 
     /// Creates a set of vertices to approximate the top right quadrent of a circle.
     /// The center of the arc has the coordinates 0, 0.
-    /// fascets is the number of fascets in the arc, so one quater of the
-    /// number of fascets that'd be in a similarly detialed circle.
+    /// The arc has as few fascets as it can without straying more than
+    /// tolerance logical pixels from the circle.
     /// The bottom right most vertice is the first with them in counter clockwise order.
-    pub fn make_top_right_arc(radius: GUILength, fascets: usize) -> Vec<GUIPosition> {
-        let vertices = (fascets + 1) as usize;
+    pub fn make_top_right_arc(radius: GUILength, tolerance: f64) -> Vec<GUIPosition> {
+        let fascets = segment_count(radius.get_length(), PI / 2., tolerance);
+        let vertices = fascets + 1;
         let mut positions = Vec::with_capacity(vertices);
 
         for i in 0..vertices {
             let angle = 2. * PI * i as f64 / (fascets * 4) as f64;
 
             positions.push(GUIPosition {
-                x: GUILength {
-                    length: radius.length * angle.cos(),
-                },
-                y: GUILength {
-                    length: -radius.length * angle.sin(),
-                },
+                x: GUILength::from_pixels(radius.length * angle.cos()),
+                y: GUILength::from_pixels(-radius.length * angle.sin()),
             });
         }
 
@@ -32,18 +177,16 @@ pub mod arcs {
 
     /// Creates a set of vertices to approximate the top left quadrent of a circle.
     /// The center of the arc has the coordinates 0, 0.
-    /// fascets is the number of fascets in the arc, so one quater of the
-    /// number of fascets that'd be in a similarly detialed circle.
+    /// The arc has as few fascets as it can without straying more than
+    /// tolerance logical pixels from the circle.
     /// The top right most vertice is the first with them in counter clockwise order.
-    pub fn make_top_left_arc(radius: GUILength, fascets: usize) -> Vec<GUIPosition> {
-        let mut top_right = make_top_right_arc(radius, fascets);
+    pub fn make_top_left_arc(radius: GUILength, tolerance: f64) -> Vec<GUIPosition> {
+        let mut top_right = make_top_right_arc(radius, tolerance);
         top_right.reverse();
         top_right
             .iter()
             .map(|position| GUIPosition {
-                x: GUILength {
-                    length: position.x.length * -1.,
-                },
+                x: GUILength::from_pixels(-position.x.length),
                 y: position.y,
             })
             .collect()
@@ -51,39 +194,33 @@ pub mod arcs {
 
     /// Creates a set of vertices to approximate the bottom right quadrent of a circle.
     /// The center of the arc has the coordinates 0, 0.
-    /// fascets is the number of fascets in the arc, so one quater of the
-    /// number of fascets that'd be in a similarly detialed circle.
+    /// The arc has as few fascets as it can without straying more than
+    /// tolerance logical pixels from the circle.
     /// The bottom left most vertice is the first with them in counter clockwise order.
-    pub fn make_bottom_right_arc(radius: GUILength, fascets: usize) -> Vec<GUIPosition> {
-        let mut top_right = make_top_right_arc(radius, fascets);
+    pub fn make_bottom_right_arc(radius: GUILength, tolerance: f64) -> Vec<GUIPosition> {
+        let mut top_right = make_top_right_arc(radius, tolerance);
         top_right.reverse();
         top_right
             .iter()
             .map(|position| GUIPosition {
                 x: position.x,
-                y: GUILength {
-                    length: position.y.length * -1.,
-                },
+                y: GUILength::from_pixels(-position.y.length),
             })
             .collect()
     }
 
     /// Creates a set of vertices to approximate the bottom left quadrent of a circle.
     /// The center of the arc has the coordinates 0, 0.
-    /// fascets is the number of fascets in the arc, so one quater of the
-    /// number of fascets that'd be in a similarly detialed circle.
+    /// The arc has as few fascets as it can without straying more than
+    /// tolerance logical pixels from the circle.
     /// The top left most vertice is the first with them in counter clockwise order.
-    pub fn make_bottom_left_arc(radius: GUILength, fascets: usize) -> Vec<GUIPosition> {
-        let top_right = make_top_right_arc(radius, fascets);
+    pub fn make_bottom_left_arc(radius: GUILength, tolerance: f64) -> Vec<GUIPosition> {
+        let top_right = make_top_right_arc(radius, tolerance);
         top_right
             .iter()
             .map(|position| GUIPosition {
-                x: GUILength {
-                    length: position.x.length * -1.,
-                },
-                y: GUILength {
-                    length: position.y.length * -1.,
-                },
+                x: GUILength::from_pixels(-position.x.length),
+                y: GUILength::from_pixels(-position.y.length),
             })
             .collect()
     }
@@ -102,7 +239,104 @@ pub fn translate(
     vertices
 }
 
+/// Whether or not a point lies within a polygon, using the even-odd rule.
+pub fn contains(polygon: &[GUIPosition], point: &GUIPosition) -> bool {
+    let (x, y) = (point.x.get_length(), point.y.get_length());
+    let mut inside = false;
+    let mut j = polygon.len().wrapping_sub(1);
+    for i in 0..polygon.len() {
+        let (xi, yi) = (polygon[i].x.get_length(), polygon[i].y.get_length());
+        let (xj, yj) = (polygon[j].x.get_length(), polygon[j].y.get_length());
+        if (yi > y) != (yj > y) && x < (xj - xi) * (y - yi) / (yj - yi) + xi {
+            inside = !inside;
+        }
+        j = i;
+    }
+    inside
+}
+
+/// Makes a vertex, in logical pixels, for each position.
+pub fn make_vertices(positions: &[GUIPosition], color: &GUIColor, id: u128) -> Vec<Vertex> {
+    positions
+        .iter()
+        .map(|position| {
+            Vertex::new(
+                [
+                    position.x.get_length() as f32,
+                    position.y.get_length() as f32,
+                    0.,
+                ],
+                [
+                    color.r as f32,
+                    color.g as f32,
+                    color.b as f32,
+                    color.a as f32,
+                ],
+                id,
+            )
+        })
+        .collect()
+}
+
+/// Makes a vertex, in logical pixels, for each position, colored by a fill.
+/// Solid fills are baked into the vertices' colors, anything else is worked
+/// out for every pixel from the widget's first fill, which `fills` returns.
+pub fn make_filled_vertices(positions: &[GUIPosition], fill: &GUIFill, id: u128) -> Vec<Vertex> {
+    match fill {
+        GUIFill::Solid(color) => make_vertices(positions, color, id),
+        _ => make_vertices(positions, &GUIColor::default(), id)
+            .into_iter()
+            .map(|vertex| vertex.with_fill(1))
+            .collect(),
+    }
+}
+
+/// Returns what get_fills gives for a widget whose vertices were made by
+/// make_filled_vertices with the fill, when the widget takes up the rect.
+pub fn fills(fill: &GUIFill, rect: &GUIRect) -> Vec<GUIFill> {
+    match fill {
+        GUIFill::Solid(_) => Vec::new(),
+        _ => vec![fill.placed(rect)],
+    }
+}
+
+/// Tessellates a rectangle, in logical pixels, as two triangles.
+pub fn make_rectangle(
+    rect: &GUIRect,
+    fill: &GUIFill,
+    id: u128,
+    indice_offset: u32,
+) -> (Vec<Vertex>, Vec<u32>) {
+    let vertices = make_filled_vertices(&rect.corners(), fill, id);
+    let indices = [0, 1, 2, 0, 2, 3]
+        .iter()
+        .map(|i| indice_offset + i)
+        .collect();
+    (vertices, indices)
+}
+
+/// Moves and sizes a child that's being laid out by a container, and then
+/// lays out its own children within the area it's been given. Only what's
+/// changed is set, so that children that haven't moved aren't re-tessellated
+/// every update.
+pub fn place_child(
+    child: &mut Box<dyn Family>,
+    position: GUIPosition,
+    size: GUISize,
+    context: &GUILayoutContext,
+) {
+    if child.get_size() != size {
+        child.set_size(size);
+    }
+    if child.get_position() != position {
+        child.set_position_from_position(position);
+    }
+    child.layout(&context.with_parent(size, None));
+}
+
 #[cfg(test)]
+// The expected arcs are written out to every digit that was printed for them.
+#[allow(clippy::excessive_precision)]
 mod tests {
     use crate::guiproperties::guiposition::{GUILength, GUIPosition};
 
@@ -110,7 +344,7 @@ mod tests {
 
     #[test]
     fn make_top_right_arc_good() {
-        let actual = make_top_right_arc(GUILength::from_pixels(5.), 4);
+        let actual = make_top_right_arc(GUILength::from_pixels(5.), 0.1);
         let expected = Vec::from([
             GUIPosition::from_pixels(5., 0.),
             GUIPosition::from_pixels(4.6193976625564339, -1.913417161825449),
@@ -123,7 +357,7 @@ mod tests {
 
     #[test]
     fn make_top_left_arc_good() {
-        let actual = make_top_left_arc(GUILength::from_pixels(5.), 4);
+        let actual = make_top_left_arc(GUILength::from_pixels(5.), 0.1);
         let expected = Vec::from([
             GUIPosition::from_pixels(0., -5.),
             GUIPosition::from_pixels(-1.9134171618254492, -4.6193976625564339),
@@ -136,7 +370,7 @@ mod tests {
 
     #[test]
     fn make_bottom_right_arc_good() {
-        let actual = make_bottom_right_arc(GUILength::from_pixels(5.), 4);
+        let actual = make_bottom_right_arc(GUILength::from_pixels(5.), 0.1);
         let expected = Vec::from([
             GUIPosition::from_pixels(0., 5.),
             GUIPosition::from_pixels(1.9134171618254492, 4.6193976625564339),
@@ -149,7 +383,7 @@ mod tests {
 
     #[test]
     fn make_bottom_left_arc_good() {
-        let actual = make_bottom_left_arc(GUILength::from_pixels(5.), 4);
+        let actual = make_bottom_left_arc(GUILength::from_pixels(5.), 0.1);
         let expected = Vec::from([
             GUIPosition::from_pixels(-5., 0.),
             GUIPosition::from_pixels(-4.6193976625564339, 1.913417161825449),
@@ -159,4 +393,25 @@ mod tests {
         ]);
         assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn arcs_get_finer_with_the_radius_and_scale_factor() {
+        let fascets = |radius: f64, scale_factor: f64| {
+            make_top_right_arc(GUILength::from_pixels(radius), tolerance(scale_factor)).len() - 1
+        };
+        assert_eq!(fascets(0.1, 1.), 1);
+        assert!(fascets(25., 1.) < fascets(100., 1.));
+        assert!(fascets(25., 1.) < fascets(25., 2.));
+
+        // No chord strays further than the tolerance from the circle.
+        for radius in [1., 10., 250.] {
+            let arc = make_top_right_arc(GUILength::from_pixels(radius), TOLERANCE);
+            let (a, b) = (arc[0], arc[1]);
+            let middle = (
+                (a.x.get_length() + b.x.get_length()) / 2.,
+                (a.y.get_length() + b.y.get_length()) / 2.,
+            );
+            assert!(radius - middle.0.hypot(middle.1) <= TOLERANCE);
+        }
+    }
 }
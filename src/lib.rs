@@ -4,4 +4,5 @@
 pub mod guiprocessing;
 pub mod guiproperties;
 pub mod guiresources;
+pub mod guitesting;
 pub mod guiwidgets;
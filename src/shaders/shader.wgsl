@@ -1,13 +1,58 @@
 // Vertex shader
 
+struct Globals {
+    // Maps logical pixels onto clip space.
+    projection: mat4x4<f32>;
+    // The number of physical pixels in a logical pixel.
+    scale_factor: f32;
+};
+
+[[group(0), binding(0)]]
+var<uniform> globals: Globals;
+
+struct Fill {
+    // The start and end of a linear gradient, or the center and radius
+    // of a radial one, in logical pixels.
+    geometry: vec4<f32>;
+    // 0 for a linear gradient, 1 for a radial one.
+    kind: u32;
+    first_stop: u32;
+    stop_count: u32;
+    padding: u32;
+};
+
+struct Fills {
+    fills: array<Fill>;
+};
+
+struct Stop {
+    // Premultiplied.
+    color: vec4<f32>;
+    offset: f32;
+};
+
+struct Stops {
+    stops: array<Stop>;
+};
+
+[[group(1), binding(0)]]
+var<storage, read> fill_buffer: Fills;
+
+[[group(1), binding(1)]]
+var<storage, read> stop_buffer: Stops;
+
 struct VertexInput {
     [[location(0)]] position: vec3<f32>;
-    [[location(1)]] color: vec3<f32>;
+    [[location(1)]] color: vec4<f32>;
+    [[location(2)]] fill: u32;
 };
 
 struct VertexOutput {
     [[builtin(position)]] clip_position: vec4<f32>;
-    [[location(0)]] color: vec3<f32>;
+    [[location(0)]] color: vec4<f32>;
+    // The position in logical pixels, which gradients are worked out from.
+    [[location(1)]] position: vec2<f32>;
+    [[location(2), interpolate(flat)]] fill: u32;
 };
 
 [[stage(vertex)]]
@@ -16,13 +61,195 @@ fn vs_main(
 ) -> VertexOutput {
     var out: VertexOutput;
     out.color = model.color;
-    out.clip_position = vec4<f32>(model.position, 1.0);
+    out.position = model.position.xy;
+    out.fill = model.fill;
+    out.clip_position = globals.projection * vec4<f32>(model.position, 1.0);
     return out;
 }
 
 // Fragment shader
 
+// How far along a fill's gradient a position is.
+fn gradient_offset(fill: Fill, position: vec2<f32>) -> f32 {
+    if (fill.kind == 1u) {
+        return length(position - fill.geometry.xy) / max(fill.geometry.z, 1.19209290e-07);
+    }
+    let direction = fill.geometry.zw - fill.geometry.xy;
+    let length_squared = dot(direction, direction);
+    if (length_squared <= 0.0) {
+        return 0.0;
+    }
+    return dot(position - fill.geometry.xy, direction) / length_squared;
+}
+
+// The premultiplied color of a fill's gradient at an offset along it.
+fn gradient_color(fill: Fill, offset: f32) -> vec4<f32> {
+    if (fill.stop_count == 0u) {
+        return vec4<f32>(0.0, 0.0, 0.0, 0.0);
+    }
+    var previous = stop_buffer.stops[fill.first_stop];
+    if (offset <= previous.offset) {
+        return previous.color;
+    }
+    var index = 1u;
+    loop {
+        if (index >= fill.stop_count) {
+            break;
+        }
+        let stop = stop_buffer.stops[fill.first_stop + index];
+        if (offset <= stop.offset) {
+            let amount = (offset - previous.offset) / (stop.offset - previous.offset);
+            return mix(previous.color, stop.color, vec4<f32>(amount, amount, amount, amount));
+        }
+        previous = stop;
+        continuing {
+            index = index + 1u;
+        }
+    }
+    return previous.color;
+}
+
+// The premultiplied color of a fragment that's either colored by one of
+// the fills, counting from 1, or by a color if the index is 0.
+fn fill_color(index: u32, color: vec4<f32>, position: vec2<f32>) -> vec4<f32> {
+    if (index > 0u) {
+        // Gradients are worked out for every fragment rather than at each
+        // vertex, so they follow curved outlines however few facets they have.
+        let fill = fill_buffer.fills[index - 1u];
+        return gradient_color(fill, gradient_offset(fill, position));
+    }
+    // Colors are blended as premultiplied alpha.
+    return vec4<f32>(color.rgb * color.a, color.a);
+}
+
 [[stage(fragment)]]
 fn fs_main(in: VertexOutput) -> [[location(0)]] vec4<f32> {
-    return vec4<f32>(in.color, 1.0);
-}
\ No newline at end of file
+    return fill_color(in.fill, in.color, in.position);
+}
+
+// Rounded rectangles, drawn as one quad each
+
+struct RectInput {
+    [[location(0)]] position: vec2<f32>;
+    // The left, top, right and bottom edges, in logical pixels.
+    [[location(1)]] rect: vec4<f32>;
+    // Clockwise from the top left.
+    [[location(2)]] corner_radii: vec4<f32>;
+    [[location(3)]] color: vec4<f32>;
+    [[location(4)]] border_color: vec4<f32>;
+    [[location(5)]] border_width: f32;
+    [[location(6)]] fill: u32;
+    // The standard deviation of the blur of the edge, or 0 for a sharp edge.
+    [[location(7)]] blur: f32;
+};
+
+struct RectOutput {
+    [[builtin(position)]] clip_position: vec4<f32>;
+    [[location(0)]] position: vec2<f32>;
+    [[location(1), interpolate(flat)]] rect: vec4<f32>;
+    [[location(2), interpolate(flat)]] corner_radii: vec4<f32>;
+    [[location(3), interpolate(flat)]] color: vec4<f32>;
+    [[location(4), interpolate(flat)]] border_color: vec4<f32>;
+    [[location(5), interpolate(flat)]] border_width: f32;
+    [[location(6), interpolate(flat)]] fill: u32;
+    [[location(7), interpolate(flat)]] blur: f32;
+};
+
+[[stage(vertex)]]
+fn vs_rect(
+    model: RectInput,
+) -> RectOutput {
+    var out: RectOutput;
+    out.position = model.position;
+    out.rect = model.rect;
+    out.corner_radii = model.corner_radii;
+    out.color = model.color;
+    out.border_color = model.border_color;
+    out.border_width = model.border_width;
+    out.fill = model.fill;
+    out.blur = model.blur;
+    out.clip_position = globals.projection * vec4<f32>(model.position, 0.0, 1.0);
+    return out;
+}
+
+// The signed distance from a position to the edge of a rounded rectangle,
+// in logical pixels, which is negative inside of it.
+fn rounded_rect_distance(rect: vec4<f32>, corner_radii: vec4<f32>, position: vec2<f32>) -> f32 {
+    let center = (rect.xy + rect.zw) / 2.0;
+    let half_size = (rect.zw - rect.xy) / 2.0;
+    let p = position - center;
+    let right = p.x >= 0.0;
+    let top_radius = select(corner_radii.x, corner_radii.y, right);
+    let bottom_radius = select(corner_radii.w, corner_radii.z, right);
+    let radius = select(top_radius, bottom_radius, p.y >= 0.0);
+    let q = abs(p) - half_size + vec2<f32>(radius, radius);
+    return min(max(q.x, q.y), 0.0) + length(max(q, vec2<f32>(0.0, 0.0))) - radius;
+}
+
+// How much of a pixel is inside of an edge at the signed distance, with
+// the edge blurred across a physical pixel.
+fn coverage(distance: f32) -> f32 {
+    return clamp(0.5 - distance * globals.scale_factor, 0.0, 1.0);
+}
+
+// An approximation of the error function, good to about 5e-4.
+fn erf(x: f32) -> f32 {
+    let a = abs(x);
+    var denominator = 1.0 + (0.278393 + (0.230389 + 0.078108 * a * a) * a) * a;
+    denominator = denominator * denominator;
+    return sign(x) - sign(x) / (denominator * denominator);
+}
+
+// How much of a rounded rectangle, blurred by a Gaussian with the standard
+// deviation sigma, covers a position. The blur is separable, so across each
+// row it's worked out exactly from the error function, and down the rows
+// it's summed over a few samples of the Gaussian. The rows are cut short by
+// the radius of the corner of the quadrant that the position is in.
+fn blurred_coverage(rect: vec4<f32>, corner_radii: vec4<f32>, position: vec2<f32>, sigma: f32) -> f32 {
+    let center = (rect.xy + rect.zw) / 2.0;
+    let half_size = (rect.zw - rect.xy) / 2.0;
+    let p = position - center;
+    let right = p.x >= 0.0;
+    let top_radius = select(corner_radii.x, corner_radii.y, right);
+    let bottom_radius = select(corner_radii.w, corner_radii.z, right);
+    let radius = select(top_radius, bottom_radius, p.y >= 0.0);
+
+    let start = clamp(-3.0 * sigma, p.y - half_size.y, p.y + half_size.y);
+    let end = clamp(3.0 * sigma, p.y - half_size.y, p.y + half_size.y);
+    let step = (end - start) / 4.0;
+    var coverage = 0.0;
+    var sample = 0;
+    loop {
+        if (sample >= 4) {
+            break;
+        }
+        let offset = start + step * (f32(sample) + 0.5);
+        let row = p.y - offset;
+        let past_corner = min(half_size.y - radius - abs(row), 0.0);
+        let half_width = half_size.x - radius + sqrt(max(radius * radius - past_corner * past_corner, 0.0));
+        let row_coverage = 0.5 * (erf((p.x + half_width) * 0.70710678 / sigma) - erf((p.x - half_width) * 0.70710678 / sigma));
+        let gaussian = exp(-(offset * offset) / (2.0 * sigma * sigma)) / (2.50662827 * sigma);
+        coverage = coverage + row_coverage * gaussian * step;
+        continuing {
+            sample = sample + 1;
+        }
+    }
+    return coverage;
+}
+
+[[stage(fragment)]]
+fn fs_rect(in: RectOutput) -> [[location(0)]] vec4<f32> {
+    if (in.blur > 0.0) {
+        // Blurred rectangles, like shadows, have no border.
+        return fill_color(in.fill, in.color, in.position) * blurred_coverage(in.rect, in.corner_radii, in.position, in.blur);
+    }
+    let distance = rounded_rect_distance(in.rect, in.corner_radii, in.position);
+    var border = 0.0;
+    if (in.border_width > 0.0) {
+        border = 1.0 - coverage(distance + in.border_width);
+    }
+    let fill = fill_color(in.fill, in.color, in.position);
+    let border_color = vec4<f32>(in.border_color.rgb * in.border_color.a, in.border_color.a) * border;
+    // The border is drawn over the fill.
+    return (border_color + fill * (1.0 - border_color.a)) * coverage(distance);
+}
@@ -0,0 +1,50 @@
+// Vertex shader
+
+struct Globals {
+    // Maps logical pixels onto clip space.
+    projection: mat4x4<f32>;
+    // The number of physical pixels in a logical pixel.
+    scale_factor: f32;
+};
+
+[[group(0), binding(0)]]
+var<uniform> globals: Globals;
+
+struct VertexInput {
+    [[location(0)]] position: vec3<f32>;
+    [[location(1)]] tex_coords: vec2<f32>;
+    [[location(2)]] color: vec4<f32>;
+};
+
+struct VertexOutput {
+    [[builtin(position)]] clip_position: vec4<f32>;
+    [[location(0)]] tex_coords: vec2<f32>;
+    [[location(1)]] color: vec4<f32>;
+};
+
+[[stage(vertex)]]
+fn vs_main(
+    model: VertexInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.tex_coords = model.tex_coords;
+    out.color = model.color;
+    out.clip_position = globals.projection * vec4<f32>(model.position, 1.0);
+    return out;
+}
+
+// Fragment shader
+
+[[group(1), binding(0)]]
+var t_atlas: texture_2d<f32>;
+[[group(1), binding(1)]]
+var s_atlas: sampler;
+
+[[stage(fragment)]]
+fn fs_main(in: VertexOutput) -> [[location(0)]] vec4<f32> {
+    // The atlas holds how much of each pixel the glyph covers.
+    let coverage = textureSample(t_atlas, s_atlas, in.tex_coords).r;
+    // Colors are blended as premultiplied alpha.
+    let alpha = in.color.a * coverage;
+    return vec4<f32>(in.color.rgb * alpha, alpha);
+}